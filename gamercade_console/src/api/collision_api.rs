@@ -0,0 +1,66 @@
+pub trait CollisionApi {
+    #[allow(clippy::too_many_arguments)]
+    fn rects_overlap(
+        &self,
+        x0: i32,
+        y0: i32,
+        width0: i32,
+        height0: i32,
+        x1: i32,
+        y1: i32,
+        width1: i32,
+        height1: i32,
+    ) -> i32;
+
+    fn circles_overlap(
+        &self,
+        x0: i32,
+        y0: i32,
+        radius0: i32,
+        x1: i32,
+        y1: i32,
+        radius1: i32,
+    ) -> i32;
+
+    #[allow(clippy::too_many_arguments)]
+    fn sprites_overlap(
+        &self,
+        graphics_parameters0: i32,
+        transparency_mask0: i64,
+        x0: i32,
+        y0: i32,
+        graphics_parameters1: i32,
+        transparency_mask1: i64,
+        x1: i32,
+        y1: i32,
+    ) -> i32;
+
+    fn sprite_pixel_at(
+        &self,
+        graphics_parameters: i32,
+        transparency_mask: i64,
+        x: i32,
+        y: i32,
+        screen_x: i32,
+        screen_y: i32,
+    ) -> i32;
+}
+
+macro_rules! derive_bind_collision_api {
+    ($($name:ident,)*) => {
+        pub trait CollisionApiBinding {
+            $(fn $name(&mut self);)*
+
+            fn bind_collision_api(&mut self) {
+                $(self.$name();)*
+            }
+        }
+    };
+}
+
+derive_bind_collision_api! {
+    bind_rects_overlap,
+    bind_circles_overlap,
+    bind_sprites_overlap,
+    bind_sprite_pixel_at,
+}
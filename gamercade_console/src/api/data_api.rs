@@ -15,6 +15,9 @@ pub trait DataApi {
     fn bgm_length_frames(&self, bgm_index: i32) -> i32;
     fn sfx_length_secs(&self, sfx_index: i32) -> f32;
     fn sfx_length_frames(&self, sfx_index: i32) -> i32;
+
+    fn read_sprite(&self, sheet_index: i32, sprite_index: i32, out: &mut [u8]) -> i32;
+    fn read_palette(&self, palette_index: i32, out: &mut [u8]) -> i32;
 }
 
 macro_rules! derive_bind_data_api {
@@ -43,4 +46,6 @@ derive_bind_data_api! {
     bind_bgm_length_frames,
     bind_sfx_length_secs,
     bind_sfx_length_frames,
+    bind_read_sprite,
+    bind_read_palette,
 }
@@ -27,6 +27,12 @@ pub trait DrawApi {
     fn sprite(&mut self, graphics_parameters: i32, transparency_mask: i64, x: i32, y: i32);
 
     fn write_pixel_buffer(&mut self, start_index: usize, data: &[u32]);
+
+    fn enable_indexed_mode(&mut self, palette_index: i32);
+    fn disable_indexed_mode(&mut self);
+    fn write_indexed_buffer(&mut self, start_index: usize, data: &[u8]);
+
+    fn read_pixel_buffer(&self, start_index: usize, out: &mut [u8]) -> i32;
 }
 
 derive_bind_draw_api! {
@@ -39,4 +45,8 @@ derive_bind_draw_api! {
     bind_line,
     bind_sprite,
     bind_write_pixel_buffer,
+    bind_enable_indexed_mode,
+    bind_disable_indexed_mode,
+    bind_write_indexed_buffer,
+    bind_read_pixel_buffer,
 }
@@ -37,6 +37,9 @@ macro_rules! derive_bind_input_api {
                 $(
                     fn [<mouse_ $maxis_name _pos>](&self, player_id: i32) -> i32;
                     fn [<mouse_ $maxis_name _delta>](&self, player_id: i32) -> i32;
+                    fn [<mouse_ $maxis_name _previous_pos>](&self, player_id: i32) -> i32;
+                    fn [<mouse_ $maxis_name _delta_precise>](&self, player_id: i32) -> f32;
+                    fn [<mouse_wheel_ $maxis_name>](&self, player_id: i32) -> i32;
                 )*
 
                 $(
@@ -45,6 +48,19 @@ macro_rules! derive_bind_input_api {
 
                 fn lock_mouse(&mut self, locked: i32);
 
+                fn touch_count(&self, player_id: i32) -> i32;
+                fn touch_pressed(&self, player_id: i32, index: i32) -> i32;
+                fn touch_released(&self, player_id: i32, index: i32) -> i32;
+                fn touch_held(&self, player_id: i32, index: i32) -> i32;
+                fn touch_x_pos(&self, player_id: i32, index: i32) -> i32;
+                fn touch_y_pos(&self, player_id: i32, index: i32) -> i32;
+
+                fn controller_connected(&self, player_id: i32) -> i32;
+
+                fn action_pressed(&self, player_id: i32, action_id: i32) -> i32;
+                fn action_released(&self, player_id: i32, action_id: i32) -> i32;
+                fn action_held(&self, player_id: i32, action_id: i32) -> i32;
+
                 fn raw_input_state(&self, player_id: i32) -> i64;
                 fn raw_mouse_state(&self, player_id: i32) -> i64;
             }
@@ -74,6 +90,9 @@ macro_rules! derive_bind_input_api {
                 $(
                     fn [<bind_mouse_ $maxis_name _pos>](&mut self);
                     fn [<bind_mouse_ $maxis_name _delta>](&mut self);
+                    fn [<bind_mouse_ $maxis_name _previous_pos>](&mut self);
+                    fn [<bind_mouse_ $maxis_name _delta_precise>](&mut self);
+                    fn [<bind_mouse_wheel_ $maxis_name>](&mut self);
                 )*
 
                 $(
@@ -84,6 +103,19 @@ macro_rules! derive_bind_input_api {
                 fn bind_raw_mouse_state(&mut self);
                 fn bind_lock_mouse(&mut self);
 
+                fn bind_touch_count(&mut self);
+                fn bind_touch_pressed(&mut self);
+                fn bind_touch_released(&mut self);
+                fn bind_touch_held(&mut self);
+                fn bind_touch_x_pos(&mut self);
+                fn bind_touch_y_pos(&mut self);
+
+                fn bind_controller_connected(&mut self);
+
+                fn bind_action_pressed(&mut self);
+                fn bind_action_released(&mut self);
+                fn bind_action_held(&mut self);
+
                 fn bind_input_api(&mut self) {
                     $(
                         self.[<bind_button_ $btn_name _pressed>]();
@@ -109,6 +141,9 @@ macro_rules! derive_bind_input_api {
                     $(
                         self.[<bind_mouse_ $maxis_name _pos>]();
                         self.[<bind_mouse_ $maxis_name _delta>]();
+                        self.[<bind_mouse_ $maxis_name _previous_pos>]();
+                        self.[<bind_mouse_ $maxis_name _delta_precise>]();
+                        self.[<bind_mouse_wheel_ $maxis_name>]();
                     )*
 
                     $(
@@ -116,6 +151,20 @@ macro_rules! derive_bind_input_api {
                     )*
 
                     self.bind_lock_mouse();
+
+                    self.bind_touch_count();
+                    self.bind_touch_pressed();
+                    self.bind_touch_released();
+                    self.bind_touch_held();
+                    self.bind_touch_x_pos();
+                    self.bind_touch_y_pos();
+
+                    self.bind_controller_connected();
+
+                    self.bind_action_pressed();
+                    self.bind_action_released();
+                    self.bind_action_held();
+
                     self.bind_raw_input_state();
                     self.bind_raw_mouse_state();
                 }
@@ -1,17 +1,23 @@
 mod audio_api;
+mod collision_api;
 mod data_api;
 mod draw_api;
 mod graphics_parameter_api;
 mod input_api;
 mod multiplayer_api;
 mod random_api;
+mod rumble_api;
 mod text_api;
+mod text_input_api;
 
 pub use audio_api::*;
+pub use collision_api::*;
 pub use data_api::*;
 pub use draw_api::*;
 pub use graphics_parameter_api::*;
 pub use input_api::*;
 pub use multiplayer_api::*;
 pub use random_api::*;
+pub use rumble_api::*;
 pub use text_api::*;
+pub use text_input_api::*;
@@ -2,6 +2,7 @@ pub trait MultiplayerApi {
     fn num_players(&self) -> i32;
     fn is_local_player(&self, player_id: i32) -> i32;
     fn is_remote_player(&self, player_id: i32) -> i32;
+    fn is_player_connected(&self, player_id: i32) -> i32;
 }
 
 macro_rules! derive_bind_multiplayer_api {
@@ -20,4 +21,5 @@ derive_bind_multiplayer_api! {
     bind_num_players,
     bind_is_local_player,
     bind_is_remote_player,
+    bind_is_player_connected,
 }
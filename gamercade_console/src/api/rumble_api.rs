@@ -0,0 +1,21 @@
+pub trait RumbleApi {
+    fn rumble(&mut self, player_id: i32, strong: f32, weak: f32, duration_frames: i32);
+    fn stop_rumble(&mut self, player_id: i32);
+}
+
+macro_rules! derive_bind_rumble_api {
+    ($($name:ident,)*) => {
+        pub trait RumbleApiBinding {
+            $(fn $name(&mut self);)*
+
+            fn bind_rumble_api(&mut self) {
+                $(self.$name();)*
+            }
+        }
+    };
+}
+
+derive_bind_rumble_api! {
+    bind_rumble,
+    bind_stop_rumble,
+}
@@ -0,0 +1,31 @@
+pub trait TextInputApi {
+    fn start_text_input(&mut self) -> i32;
+    fn stop_text_input(&mut self);
+    fn text_input_active(&self) -> i32;
+    fn text_input_len(&self) -> i32;
+    fn text_input_backspace_count(&self) -> i32;
+    fn text_input_enter_pressed(&self) -> i32;
+    fn read_text_input(&self, out: &mut [u8]) -> i32;
+}
+
+macro_rules! derive_bind_text_input_api {
+    ($($name:ident,)*) => {
+        pub trait TextInputApiBinding {
+            $(fn $name(&mut self);)*
+
+            fn bind_text_input_api(&mut self) {
+                $(self.$name();)*
+            }
+        }
+    };
+}
+
+derive_bind_text_input_api! {
+    bind_start_text_input,
+    bind_stop_text_input,
+    bind_text_input_active,
+    bind_text_input_len,
+    bind_text_input_backspace_count,
+    bind_text_input_enter_pressed,
+    bind_read_text_input,
+}
@@ -0,0 +1,56 @@
+use crate::api::{CollisionApi, CollisionApiBinding};
+use paste::paste;
+use wasmtime::{Caller, Linker};
+
+use crate::console::Contexts;
+
+macro_rules! derive_collision_api_binding {
+    ($($ident:ident ($($name:ident:$args:ty $(,)? )*) $(,)?)*) => {
+        paste! {
+            impl CollisionApiBinding for Linker<Contexts> {
+                $(
+                    fn [<bind_ $ident>](&mut self) {
+                        self.func_wrap(
+                            "env",
+                            stringify!($ident),
+                            |caller: Caller<'_, Contexts>, $($name: $args,)*| {
+                                caller.data().collision_context.$ident($($name as $args,)*)
+                        }).unwrap();
+                    }
+                )*
+            }
+        }
+    };
+}
+
+derive_collision_api_binding! {
+    rects_overlap(
+        x0: i32,
+        y0: i32,
+        width0: i32,
+        height0: i32,
+        x1: i32,
+        y1: i32,
+        width1: i32,
+        height1: i32,
+    ),
+    circles_overlap(x0: i32, y0: i32, radius0: i32, x1: i32, y1: i32, radius1: i32),
+    sprites_overlap(
+        graphics_parameters0: i32,
+        transparency_mask0: i64,
+        x0: i32,
+        y0: i32,
+        graphics_parameters1: i32,
+        transparency_mask1: i64,
+        x1: i32,
+        y1: i32,
+    ),
+    sprite_pixel_at(
+        graphics_parameters: i32,
+        transparency_mask: i64,
+        x: i32,
+        y: i32,
+        screen_x: i32,
+        screen_y: i32,
+    ),
+}
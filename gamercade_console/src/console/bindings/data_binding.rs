@@ -1,7 +1,8 @@
 use crate::api::{DataApi, DataApiBinding};
 use crate::console::Contexts;
+use gamercade_core::BYTES_PER_PIXEL;
 use paste::paste;
-use wasmtime::{Caller, Linker};
+use wasmtime::{Caller, Extern, Linker, Trap};
 
 macro_rules! derive_data_api_binding {
     ($($ident:ident ($($name:ident:$args:ty $(,)? )*) $(,)?)*) => {
@@ -17,6 +18,54 @@ macro_rules! derive_data_api_binding {
                         }).unwrap();
                     }
                 )*
+
+                fn bind_read_sprite(&mut self) {
+                    self.func_wrap(
+                        "env",
+                        "read_sprite",
+                        |mut caller: Caller<'_, Contexts>, sheet_index: i32, sprite_index: i32, out_ptr: i32, len: i32| {
+                            let mem = match caller.get_export("memory") {
+                                Some(Extern::Memory(mem)) => mem,
+                                _ => return Err(Trap::new("failed to find host memory")),
+                            };
+
+                            let (data, store) = mem.data_and_store_mut(&mut caller);
+
+                            let out = match data
+                                .get_mut(out_ptr as u32 as usize..)
+                                .and_then(|arr| arr.get_mut(..len as u32 as usize))
+                            {
+                                Some(out) => out,
+                                None => return Err(Trap::new("invalid data")),
+                            };
+
+                            Ok(store.data_context.read_sprite(sheet_index, sprite_index, out))
+                    }).unwrap();
+                }
+
+                fn bind_read_palette(&mut self) {
+                    self.func_wrap(
+                        "env",
+                        "read_palette",
+                        |mut caller: Caller<'_, Contexts>, palette_index: i32, out_ptr: i32, len: i32| {
+                            let mem = match caller.get_export("memory") {
+                                Some(Extern::Memory(mem)) => mem,
+                                _ => return Err(Trap::new("failed to find host memory")),
+                            };
+
+                            let (data, store) = mem.data_and_store_mut(&mut caller);
+
+                            let out = match data
+                                .get_mut(out_ptr as u32 as usize..)
+                                .and_then(|arr| arr.get_mut(..len as u32 as usize * BYTES_PER_PIXEL))
+                            {
+                                Some(out) => out,
+                                None => return Err(Trap::new("invalid data")),
+                            };
+
+                            Ok(store.data_context.read_palette(palette_index, out))
+                    }).unwrap();
+                }
             }
         }
     };
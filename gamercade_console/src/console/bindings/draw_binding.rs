@@ -42,6 +42,54 @@ macro_rules! derive_draw_api_binding {
                             Ok(store.draw_context.write_pixel_buffer(start_index as usize, data))
                     }).unwrap();
                 }
+
+                fn bind_write_indexed_buffer(&mut self) {
+                    self.func_wrap(
+                        "env",
+                        "write_indexed_buffer",
+                        |mut caller: Caller<'_, Contexts>, start_index: i32, data_ptr: i32, len: i32| {
+                            let mem = match caller.get_export("memory") {
+                                Some(Extern::Memory(mem)) => mem,
+                                _ => return Err(Trap::new("failed to find host memory")),
+                            };
+
+                            let (data, store) = mem.data_and_store_mut(&mut caller);
+
+                            let data = match data
+                                .get(data_ptr as u32 as usize..)
+                                .and_then(|arr| arr.get(..len as u32 as usize))
+                            {
+                                Some(data) => data,
+                                None => return Err(Trap::new("invalid data")),
+                            };
+
+                            Ok(store.draw_context.write_indexed_buffer(start_index as usize, data))
+                    }).unwrap();
+                }
+
+                fn bind_read_pixel_buffer(&mut self) {
+                    self.func_wrap(
+                        "env",
+                        "read_pixel_buffer",
+                        |mut caller: Caller<'_, Contexts>, start_index: i32, out_ptr: i32, len: i32| {
+                            let mem = match caller.get_export("memory") {
+                                Some(Extern::Memory(mem)) => mem,
+                                _ => return Err(Trap::new("failed to find host memory")),
+                            };
+
+                            let (data, store) = mem.data_and_store_mut(&mut caller);
+
+                            let out = match data
+                                .get_mut(out_ptr as u32 as usize..)
+                                .and_then(|arr| arr.get_mut(..len as u32 as usize * BYTES_PER_PIXEL))
+                            {
+                                Some(out) => out,
+                                None => return Err(Trap::new("invalid data")),
+                            };
+
+                            Ok(store.draw_context.read_pixel_buffer(start_index as u32 as usize, out))
+                    }).unwrap();
+                }
             }
         }
     };
@@ -78,4 +126,7 @@ derive_draw_api_binding! {
         x: i32,
         y: i32
     ),
+
+    enable_indexed_mode(palette_index: i32),
+    disable_indexed_mode(),
 }
@@ -130,6 +130,33 @@ macro_rules! derive_bind_wasm_input_api {
                                 caller.data().input_context.[<mouse_ $maxis_name _delta>](id)
                         }).unwrap();
                     }
+
+                    fn [<bind_mouse_ $maxis_name _previous_pos>](&mut self) {
+                        self.func_wrap(
+                            "env",
+                            stringify!([<mouse_ $maxis_name _previous_pos>]),
+                            |caller: Caller<'_, Contexts>, id: i32| {
+                                caller.data().input_context.[<mouse_ $maxis_name _previous_pos>](id)
+                        }).unwrap();
+                    }
+
+                    fn [<bind_mouse_ $maxis_name _delta_precise>](&mut self) {
+                        self.func_wrap(
+                            "env",
+                            stringify!([<mouse_ $maxis_name _delta_precise>]),
+                            |caller: Caller<'_, Contexts>, id: i32| {
+                                caller.data().input_context.[<mouse_ $maxis_name _delta_precise>](id)
+                        }).unwrap();
+                    }
+
+                    fn [<bind_mouse_wheel_ $maxis_name>](&mut self) {
+                        self.func_wrap(
+                            "env",
+                            stringify!([<mouse_wheel_ $maxis_name>]),
+                            |caller: Caller<'_, Contexts>, id: i32| {
+                                caller.data().input_context.[<mouse_wheel_ $maxis_name>](id)
+                        }).unwrap();
+                    }
                 )*
 
                 $(
@@ -156,6 +183,66 @@ macro_rules! derive_bind_wasm_input_api {
                     }).unwrap();
                 }
 
+                fn bind_touch_count(&mut self) {
+                    self.func_wrap("env", "touch_count", |caller: Caller<'_, Contexts>, id: i32| {
+                        caller.data().input_context.touch_count(id)
+                    }).unwrap();
+                }
+
+                fn bind_touch_pressed(&mut self) {
+                    self.func_wrap("env", "touch_pressed", |caller: Caller<'_, Contexts>, id: i32, index: i32| {
+                        caller.data().input_context.touch_pressed(id, index)
+                    }).unwrap();
+                }
+
+                fn bind_touch_released(&mut self) {
+                    self.func_wrap("env", "touch_released", |caller: Caller<'_, Contexts>, id: i32, index: i32| {
+                        caller.data().input_context.touch_released(id, index)
+                    }).unwrap();
+                }
+
+                fn bind_touch_held(&mut self) {
+                    self.func_wrap("env", "touch_held", |caller: Caller<'_, Contexts>, id: i32, index: i32| {
+                        caller.data().input_context.touch_held(id, index)
+                    }).unwrap();
+                }
+
+                fn bind_touch_x_pos(&mut self) {
+                    self.func_wrap("env", "touch_x_pos", |caller: Caller<'_, Contexts>, id: i32, index: i32| {
+                        caller.data().input_context.touch_x_pos(id, index)
+                    }).unwrap();
+                }
+
+                fn bind_touch_y_pos(&mut self) {
+                    self.func_wrap("env", "touch_y_pos", |caller: Caller<'_, Contexts>, id: i32, index: i32| {
+                        caller.data().input_context.touch_y_pos(id, index)
+                    }).unwrap();
+                }
+
+                fn bind_controller_connected(&mut self) {
+                    self.func_wrap("env", "controller_connected", |caller: Caller<'_, Contexts>, id: i32| {
+                        caller.data().input_context.controller_connected(id)
+                    }).unwrap();
+                }
+
+                fn bind_action_pressed(&mut self) {
+                    self.func_wrap("env", "action_pressed", |caller: Caller<'_, Contexts>, id: i32, action_id: i32| {
+                        caller.data().input_context.action_pressed(id, action_id)
+                    }).unwrap();
+                }
+
+                fn bind_action_released(&mut self) {
+                    self.func_wrap("env", "action_released", |caller: Caller<'_, Contexts>, id: i32, action_id: i32| {
+                        caller.data().input_context.action_released(id, action_id)
+                    }).unwrap();
+                }
+
+                fn bind_action_held(&mut self) {
+                    self.func_wrap("env", "action_held", |caller: Caller<'_, Contexts>, id: i32, action_id: i32| {
+                        caller.data().input_context.action_held(id, action_id)
+                    }).unwrap();
+                }
+
                 fn bind_lock_mouse(&mut self) {
                     self.func_wrap("env", "lock_mouse", |mut caller: Caller<'_, Contexts>, locked: i32| {
                         caller.data_mut().input_context.lock_mouse(locked)
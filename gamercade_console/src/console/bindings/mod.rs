@@ -1,13 +1,16 @@
 use crate::api::*;
 
 mod audio_binding;
+mod collision_binding;
 mod data_binding;
 mod draw_binding;
 mod graphics_parameter_binding;
 mod input_binding;
 mod multiplayer_binding;
 mod random_binding;
+mod rumble_binding;
 mod text_binding;
+mod text_input_binding;
 
 pub fn bind_all_apis(linker: &mut wasmtime::Linker<super::Contexts>) {
     linker.bind_draw_api();
@@ -18,4 +21,7 @@ pub fn bind_all_apis(linker: &mut wasmtime::Linker<super::Contexts>) {
     linker.bind_text_api();
     linker.bind_multiplayer_api();
     linker.bind_audio_api();
+    linker.bind_collision_api();
+    linker.bind_text_input_api();
+    linker.bind_rumble_api();
 }
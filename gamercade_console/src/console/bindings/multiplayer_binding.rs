@@ -27,4 +27,5 @@ derive_multiplayer_api_binding! {
     num_players(),
     is_local_player(player_id: i32),
     is_remote_player(player_id: i32),
+    is_player_connected(player_id: i32),
 }
@@ -0,0 +1,29 @@
+use crate::api::{RumbleApi, RumbleApiBinding};
+use paste::paste;
+use wasmtime::{Caller, Linker};
+
+use crate::console::Contexts;
+
+macro_rules! derive_rumble_api_binding {
+    ($($ident:ident ($($name:ident:$args:ty $(,)? )*) $(,)?)*) => {
+        paste! {
+            impl RumbleApiBinding for Linker<Contexts> {
+                $(
+                    fn [<bind_ $ident>](&mut self) {
+                        self.func_wrap(
+                            "env",
+                            stringify!($ident),
+                            |mut caller: Caller<'_, Contexts>, $($name: $args,)*| {
+                                caller.data_mut().rumble_context.$ident($($name as $args,)*)
+                        }).unwrap();
+                    }
+                )*
+            }
+        }
+    };
+}
+
+derive_rumble_api_binding! {
+    rumble(player_id: i32, strong: f32, weak: f32, duration_frames: i32),
+    stop_rumble(player_id: i32),
+}
@@ -0,0 +1,67 @@
+use crate::api::{TextInputApi, TextInputApiBinding};
+use paste::paste;
+use wasmtime::{Caller, Extern, Linker, Trap};
+
+use crate::console::Contexts;
+
+macro_rules! derive_text_input_api_binding {
+    ($($ident:ident,)*) => {
+        paste! {
+            impl TextInputApiBinding for Linker<Contexts> {
+                $(
+                    fn [<bind_ $ident>](&mut self) {
+                        self.func_wrap(
+                            "env",
+                            stringify!($ident),
+                            |caller: Caller<'_, Contexts>| {
+                                caller.data().text_input_context.$ident()
+                        }).unwrap();
+                    }
+                )*
+
+                fn bind_start_text_input(&mut self) {
+                    self.func_wrap("env", "start_text_input", |mut caller: Caller<'_, Contexts>| {
+                        caller.data_mut().text_input_context.start_text_input()
+                    }).unwrap();
+                }
+
+                fn bind_stop_text_input(&mut self) {
+                    self.func_wrap("env", "stop_text_input", |mut caller: Caller<'_, Contexts>| {
+                        caller.data_mut().text_input_context.stop_text_input()
+                    }).unwrap();
+                }
+
+                fn bind_read_text_input(&mut self) {
+                    self.func_wrap(
+                        "env",
+                        "read_text_input",
+                        |mut caller: Caller<'_, Contexts>, out_ptr: i32, len: i32| {
+                            let mem = match caller.get_export("memory") {
+                                Some(Extern::Memory(mem)) => mem,
+                                _ => return Err(Trap::new("failed to find host memory")),
+                            };
+
+                            let (data, store) = mem.data_and_store_mut(&mut caller);
+
+                            let out = match data
+                                .get_mut(out_ptr as u32 as usize..)
+                                .and_then(|arr| arr.get_mut(..len as u32 as usize))
+                            {
+                                Some(out) => out,
+                                None => return Err(Trap::new("invalid data")),
+                            };
+
+                            Ok(store.text_input_context.read_text_input(out))
+                    }).unwrap();
+                }
+            }
+        }
+    };
+}
+
+derive_text_input_api_binding! {
+    text_input_active,
+    text_input_len,
+    text_input_backspace_count,
+    text_input_enter_pressed,
+}
@@ -0,0 +1,114 @@
+use std::{io::ErrorKind, net::SocketAddr, sync::Arc};
+
+use gamercade_lobby::RelayConfig;
+use ggrs::{Message, NonBlockingSocket};
+use parking_lot::Mutex;
+
+use super::{
+    desync_detector::{ChecksumReport, DesyncDetector},
+    HandshakeMessage, PeerSocket,
+};
+
+/// The first byte of each packet, which tells GGRS' messages apart from the console's own.
+const GGRS_PACKET: u8 = 0;
+const CHECKSUM_PACKET: u8 = 1;
+pub(crate) const HANDSHAKE_PACKET: u8 = 2;
+
+pub(crate) const RECV_BUFFER_SIZE: usize = 4096;
+
+/// A socket which carries GGRS' messages, along with the
+/// checksums used to detect desyncs between peers.
+pub struct ConsoleSocket {
+    socket: PeerSocket,
+    buffer: [u8; RECV_BUFFER_SIZE],
+    desync_detector: Arc<Mutex<DesyncDetector>>,
+
+    /// Sent in reply to peers which are still handshaking
+    handshake: HandshakeMessage,
+}
+
+impl ConsoleSocket {
+    pub fn bind_to_port(
+        port: u16,
+        relay: Option<&RelayConfig>,
+        desync_detector: Arc<Mutex<DesyncDetector>>,
+        handshake: HandshakeMessage,
+    ) -> Result<Self, std::io::Error> {
+        let socket = PeerSocket::bind(port, relay)?;
+
+        Ok(Self {
+            socket,
+            buffer: [0; RECV_BUFFER_SIZE],
+            desync_detector,
+            handshake,
+        })
+    }
+
+    fn send_packet(&self, kind: u8, body: &[u8], addr: &SocketAddr) {
+        let mut packet = Vec::with_capacity(body.len() + 1);
+        packet.push(kind);
+        packet.extend_from_slice(body);
+
+        // Lost packets are handled by GGRS, or the next checksum
+        let _ = self.socket.send_to(&packet, addr);
+    }
+
+    /// Sends the checksums of any newly confirmed frames to every remote peer.
+    fn send_checksums(&self) {
+        let (reports, remotes) = {
+            let mut desync_detector = self.desync_detector.lock();
+            (
+                desync_detector.take_outgoing(),
+                desync_detector.remotes().to_vec(),
+            )
+        };
+
+        reports.iter().for_each(|report| {
+            remotes
+                .iter()
+                .for_each(|addr| self.send_packet(CHECKSUM_PACKET, &report.to_bytes(), addr))
+        });
+    }
+}
+
+impl NonBlockingSocket<SocketAddr> for ConsoleSocket {
+    fn send_to(&mut self, msg: &Message, addr: &SocketAddr) {
+        let body = bincode::serialize(msg).unwrap();
+        self.send_packet(GGRS_PACKET, &body, addr);
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(SocketAddr, Message)> {
+        // GGRS polls this regularly, so it's a good time to send our own packets
+        self.send_checksums();
+
+        let mut received_messages = Vec::new();
+        loop {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((number_of_bytes, addr)) => match self.buffer[..number_of_bytes].split_first() {
+                    Some((&GGRS_PACKET, body)) => {
+                        if let Ok(msg) = bincode::deserialize(body) {
+                            received_messages.push((addr, msg));
+                        }
+                    }
+                    Some((&CHECKSUM_PACKET, body)) => {
+                        if let Some(report) = ChecksumReport::from_bytes(body) {
+                            self.desync_detector.lock().receive(addr, report);
+                        }
+                    }
+                    // Peers which started later, or lost our earlier replies
+                    Some((&HANDSHAKE_PACKET, _)) => {
+                        self.send_packet(HANDSHAKE_PACKET, &self.handshake.to_bytes(), &addr)
+                    }
+                    _ => (),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return received_messages,
+                // Windows reports the remote closing its socket as an error, which can be ignored
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => {
+                    println!("Error receiving packets: {}", e);
+                    return received_messages;
+                }
+            }
+        }
+    }
+}
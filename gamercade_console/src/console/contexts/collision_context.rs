@@ -0,0 +1,430 @@
+use std::sync::Arc;
+
+use gamercade_core::{ColorIndex, GraphicsParameters, SpriteIndex, SpriteSheet};
+use gamercade_fs::Rom;
+
+use crate::api::CollisionApi;
+
+#[derive(Clone)]
+pub struct CollisionContext {
+    rom: Arc<Rom>,
+}
+
+impl CollisionContext {
+    pub fn new(rom: Arc<Rom>) -> Self {
+        Self { rom }
+    }
+}
+
+/// A sprite placed on the screen, ready for collision checks.
+struct PlacedSprite<'a> {
+    sheet: &'a SpriteSheet,
+    sprite_index: SpriteIndex,
+    transparent_colors: u64,
+    flip: (bool, bool),
+    x: i32,
+    y: i32,
+}
+
+impl<'a> PlacedSprite<'a> {
+    /// Returns the opaque color index at the screen position, if any.
+    /// Follows the same transparency rules as drawing a sprite.
+    fn opaque_pixel(&self, screen_x: i32, screen_y: i32) -> Option<ColorIndex> {
+        let color_index = self.sheet.pixel(
+            self.sprite_index,
+            (screen_x.checked_sub(self.x)?, screen_y.checked_sub(self.y)?),
+            self.flip,
+        )?;
+
+        if (1 << color_index.0) & self.transparent_colors != 0 {
+            None
+        } else {
+            Some(color_index)
+        }
+    }
+}
+
+impl CollisionApi for CollisionContext {
+    fn rects_overlap(
+        &self,
+        x0: i32,
+        y0: i32,
+        width0: i32,
+        height0: i32,
+        x1: i32,
+        y1: i32,
+        width1: i32,
+        height1: i32,
+    ) -> i32 {
+        let (x0, y0, width0, height0) = (x0 as i64, y0 as i64, width0 as i64, height0 as i64);
+        let (x1, y1, width1, height1) = (x1 as i64, y1 as i64, width1 as i64, height1 as i64);
+
+        (x0 < x1 + width1 && x1 < x0 + width0 && y0 < y1 + height1 && y1 < y0 + height0) as i32
+    }
+
+    fn circles_overlap(
+        &self,
+        x0: i32,
+        y0: i32,
+        radius0: i32,
+        x1: i32,
+        y1: i32,
+        radius1: i32,
+    ) -> i32 {
+        // Squaring the distance between two i32s can overflow an i64
+        let dx = x1 as i128 - x0 as i128;
+        let dy = y1 as i128 - y0 as i128;
+        let radii = radius0 as i128 + radius1 as i128;
+
+        (dx * dx + dy * dy <= radii * radii) as i32
+    }
+
+    fn sprites_overlap(
+        &self,
+        graphics_parameters0: i32,
+        transparency_mask0: i64,
+        x0: i32,
+        y0: i32,
+        graphics_parameters1: i32,
+        transparency_mask1: i64,
+        x1: i32,
+        y1: i32,
+    ) -> i32 {
+        let (a, b) = match (
+            self.place_sprite(graphics_parameters0, transparency_mask0, x0, y0),
+            self.place_sprite(graphics_parameters1, transparency_mask1, x1, y1),
+        ) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return -1,
+        };
+
+        // Only the intersection of both bounding boxes needs to be checked
+        let left = a.x.max(b.x);
+        let top = a.y.max(b.y);
+        let right =
+            a.x.saturating_add(a.sheet.width as i32)
+                .min(b.x.saturating_add(b.sheet.width as i32));
+        let bottom =
+            a.y.saturating_add(a.sheet.height as i32)
+                .min(b.y.saturating_add(b.sheet.height as i32));
+
+        let overlaps = (top..bottom).any(|y| {
+            (left..right).any(|x| a.opaque_pixel(x, y).is_some() && b.opaque_pixel(x, y).is_some())
+        });
+
+        overlaps as i32
+    }
+
+    fn sprite_pixel_at(
+        &self,
+        graphics_parameters: i32,
+        transparency_mask: i64,
+        x: i32,
+        y: i32,
+        screen_x: i32,
+        screen_y: i32,
+    ) -> i32 {
+        self.place_sprite(graphics_parameters, transparency_mask, x, y)
+            .and_then(|sprite| sprite.opaque_pixel(screen_x, screen_y))
+            .map(|color_index| color_index.0 as i32)
+            .unwrap_or(-1)
+    }
+}
+
+impl CollisionContext {
+    fn place_sprite(
+        &self,
+        graphics_parameters: i32,
+        transparency_mask: i64,
+        x: i32,
+        y: i32,
+    ) -> Option<PlacedSprite<'_>> {
+        let GraphicsParameters {
+            palette_index,
+            sprite_sheet_index,
+            sprite_index,
+            flip_x,
+            flip_y,
+            ..
+        } = graphics_parameters.into();
+
+        let graphics = &self.rom.graphics;
+        let sheet = graphics.sprite_sheet(sprite_sheet_index)?;
+
+        if sprite_index.0 >= sheet.count {
+            return None;
+        }
+
+        // Colors which are transparent in the palette are never drawn,
+        // so they should never collide either.
+        let mut transparent_colors = transparency_mask as u64;
+        if let Some(palette) = graphics.palette(palette_index) {
+            palette
+                .colors
+                .iter()
+                .enumerate()
+                .filter(|(_, color)| color.a == 0)
+                .for_each(|(index, _)| transparent_colors |= 1 << index);
+        }
+
+        Some(PlacedSprite {
+            sheet,
+            sprite_index,
+            transparent_colors,
+            flip: (flip_x, flip_y),
+            x,
+            y,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gamercade_core::{Color, GraphicsData, Palette, PALETTE_COLORS};
+
+    use super::*;
+
+    const CLEAR: i64 = 1 << 0;
+
+    // Sprite 0, color 0 is cleared by the mask and color 3 is transparent in the palette:
+    // 1 0 0 0
+    // 1 2 0 0
+    // 0 0 0 0
+    // 0 0 0 3
+    // Sprite 1 is entirely color 2.
+    fn context() -> CollisionContext {
+        #[rustfmt::skip]
+        let sprites = [
+            1, 0, 0, 0,
+            1, 2, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 3,
+
+            2, 2, 2, 2,
+            2, 2, 2, 2,
+            2, 2, 2, 2,
+            2, 2, 2, 2,
+        ];
+
+        let mut colors = [Color::new(255, 255, 255, 255); PALETTE_COLORS];
+        colors[3].a = 0;
+
+        let rom = Rom {
+            graphics: GraphicsData {
+                sprite_sheets: vec![SpriteSheet {
+                    width: 4,
+                    height: 4,
+                    sprites: sprites.into_iter().map(ColorIndex).collect(),
+                    count: 2,
+                }]
+                .into_boxed_slice(),
+                palettes: vec![Palette { colors }].into_boxed_slice(),
+            },
+            ..Rom::default()
+        };
+
+        CollisionContext::new(Arc::new(rom))
+    }
+
+    fn sprite(sprite_index: u8, flip_x: bool, flip_y: bool) -> i32 {
+        GraphicsParameters::default()
+            .sprite_index(sprite_index)
+            .flip_x(flip_x)
+            .flip_y(flip_y)
+            .into()
+    }
+
+    #[test]
+    fn test_rects_overlap() {
+        let context = context();
+        let table = [
+            ((0, 0, 10, 10), (0, 0, 10, 10), 1),
+            ((0, 0, 10, 10), (9, 9, 10, 10), 1),
+            ((0, 0, 10, 10), (2, 2, 2, 2), 1),
+            // Touching edges don't overlap
+            ((0, 0, 10, 10), (10, 0, 10, 10), 0),
+            ((0, 0, 10, 10), (0, -10, 10, 10), 0),
+            // Empty and negative sizes never overlap
+            ((0, 0, 0, 10), (0, 0, 10, 10), 0),
+            ((5, 5, -10, -10), (0, 0, 10, 10), 0),
+            // Edges past i32::MAX don't wrap around
+            ((i32::MAX - 1, 0, 10, 10), (i32::MIN, 0, 10, 10), 0),
+            ((i32::MAX, 0, i32::MAX, 1), (i32::MAX, 0, 1, 1), 1),
+        ];
+
+        table
+            .iter()
+            .for_each(|&((x0, y0, w0, h0), (x1, y1, w1, h1), expected)| {
+                assert_eq!(
+                    context.rects_overlap(x0, y0, w0, h0, x1, y1, w1, h1),
+                    expected
+                );
+                assert_eq!(
+                    context.rects_overlap(x1, y1, w1, h1, x0, y0, w0, h0),
+                    expected
+                );
+            });
+    }
+
+    #[test]
+    fn test_circles_overlap() {
+        let context = context();
+        let table = [
+            ((0, 0, 5), (0, 0, 5), 1),
+            ((0, 0, 2), (3, 4, 3), 1),
+            // Touching circles overlap
+            ((0, 0, 5), (10, 0, 5), 1),
+            ((0, 0, 5), (11, 0, 5), 0),
+            ((0, 0, 2), (3, 4, 2), 0),
+            // Distances and radii which don't fit in an i32
+            ((i32::MIN, 0, 1), (i32::MAX, 0, 1), 0),
+            (
+                (i32::MIN, i32::MIN, i32::MAX),
+                (i32::MAX, i32::MAX, i32::MAX),
+                0,
+            ),
+            ((i32::MIN, 0, i32::MAX), (i32::MAX, 0, i32::MAX), 0),
+            ((i32::MIN, 0, i32::MAX), (i32::MAX - 1, 0, i32::MAX), 1),
+        ];
+
+        table
+            .iter()
+            .for_each(|&((x0, y0, r0), (x1, y1, r1), expected)| {
+                assert_eq!(context.circles_overlap(x0, y0, r0, x1, y1, r1), expected);
+                assert_eq!(context.circles_overlap(x1, y1, r1, x0, y0, r0), expected);
+            });
+    }
+
+    #[test]
+    fn test_sprite_pixel_at() {
+        let context = context();
+        let table = [
+            ((false, false), (10, 20), 1),
+            ((false, false), (11, 21), 2),
+            // Cleared by the mask, and transparent in the palette
+            ((false, false), (12, 20), -1),
+            ((false, false), (13, 23), -1),
+            // Outside of the sprite
+            ((false, false), (9, 20), -1),
+            ((false, false), (14, 20), -1),
+            ((false, false), (10, 24), -1),
+            // Flipped
+            ((true, false), (13, 20), 1),
+            ((true, false), (12, 21), 2),
+            ((true, false), (10, 20), -1),
+            ((false, true), (10, 23), 1),
+            ((false, true), (11, 22), 2),
+            ((true, true), (13, 23), 1),
+            ((true, true), (10, 20), -1),
+        ];
+
+        table
+            .iter()
+            .for_each(|&((flip_x, flip_y), (x, y), expected)| {
+                assert_eq!(
+                    context.sprite_pixel_at(sprite(0, flip_x, flip_y), CLEAR, 10, 20, x, y),
+                    expected
+                );
+            });
+
+        // Without the mask, the cleared color is returned
+        assert_eq!(
+            context.sprite_pixel_at(sprite(0, false, false), 0, 10, 20, 12, 20),
+            0
+        );
+
+        // Invalid sprites
+        assert_eq!(
+            context.sprite_pixel_at(sprite(2, false, false), CLEAR, 0, 0, 0, 0),
+            -1
+        );
+        let missing_sheet = GraphicsParameters::default().sprite_sheet_index(1).into();
+        assert_eq!(
+            context.sprite_pixel_at(missing_sheet, CLEAR, 0, 0, 0, 0),
+            -1
+        );
+    }
+
+    #[test]
+    fn test_sprite_pixel_at_edges() {
+        let context = context();
+        let solid = sprite(1, false, false);
+
+        assert_eq!(
+            context.sprite_pixel_at(solid, CLEAR, i32::MAX, i32::MAX, i32::MAX, i32::MAX),
+            2
+        );
+        assert_eq!(
+            context.sprite_pixel_at(solid, CLEAR, i32::MIN, i32::MIN, i32::MIN, i32::MIN),
+            2
+        );
+        assert_eq!(
+            context.sprite_pixel_at(
+                sprite(0, false, false),
+                CLEAR,
+                i32::MAX - 1,
+                i32::MAX - 1,
+                i32::MAX,
+                i32::MAX
+            ),
+            2
+        );
+
+        // The offset from the sprite doesn't fit in an i32
+        assert_eq!(
+            context.sprite_pixel_at(solid, CLEAR, i32::MIN, 0, i32::MAX, 0),
+            -1
+        );
+        assert_eq!(
+            context.sprite_pixel_at(solid, CLEAR, i32::MAX, 0, i32::MIN, 0),
+            -1
+        );
+    }
+
+    #[test]
+    fn test_sprites_overlap() {
+        let context = context();
+        let solid = sprite(1, false, false);
+        let table = [
+            ((solid, 0, 0), (solid, 0, 0), 1),
+            ((solid, 0, 0), (solid, 3, 3), 1),
+            ((solid, 0, 0), (sprite(0, false, false), 0, 0), 1),
+            // Touching edges don't overlap
+            ((solid, 0, 0), (solid, 4, 0), 0),
+            ((solid, 0, 0), (solid, 0, -4), 0),
+            // The overlapping corner is only transparent pixels, until flipped
+            ((solid, 2, 2), (sprite(0, false, false), 0, 0), 0),
+            ((solid, 2, 2), (sprite(0, true, true), 0, 0), 1),
+            ((solid, 2, 0), (sprite(0, true, false), 0, 0), 1),
+            ((solid, 0, 2), (sprite(0, false, true), 0, 0), 1),
+            // At the edges of the coordinate space
+            (
+                (solid, i32::MAX - 4, i32::MAX - 4),
+                (solid, i32::MAX - 5, i32::MAX - 5),
+                1,
+            ),
+            (
+                (solid, i32::MIN, i32::MIN),
+                (solid, i32::MIN + 3, i32::MIN + 3),
+                1,
+            ),
+            ((solid, i32::MIN, 0), (solid, i32::MAX, 0), 0),
+            ((solid, i32::MAX, i32::MAX), (solid, i32::MAX, i32::MAX), 0),
+            // Invalid sprites
+            ((solid, 0, 0), (sprite(2, false, false), 0, 0), -1),
+        ];
+
+        table
+            .iter()
+            .for_each(|&((gp0, x0, y0), (gp1, x1, y1), expected)| {
+                assert_eq!(
+                    context.sprites_overlap(gp0, CLEAR, x0, y0, gp1, CLEAR, x1, y1),
+                    expected
+                );
+                assert_eq!(
+                    context.sprites_overlap(gp1, CLEAR, x1, y1, gp0, CLEAR, x0, y0),
+                    expected
+                );
+            });
+    }
+}
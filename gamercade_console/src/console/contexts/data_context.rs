@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use gamercade_core::SpriteSheet;
+use gamercade_core::{SpriteSheet, BYTES_PER_PIXEL};
 use gamercade_fs::Rom;
 
 use crate::api::DataApi;
@@ -78,6 +78,44 @@ impl DataApi for DataContext {
             .map(|secs| self.secs_to_frames(secs))
             .unwrap_or(-1)
     }
+
+    fn read_sprite(&self, sheet_index: i32, sprite_index: i32, out: &mut [u8]) -> i32 {
+        let (sheet_index, sprite_index) = match self
+            .rom
+            .graphics
+            .validate_sheet_and_sprite(sheet_index, sprite_index)
+        {
+            Ok(indices) => indices,
+            Err(_) => return -1,
+        };
+
+        let sprite = &self.rom.graphics.sprite_sheets[sheet_index.0 as usize][sprite_index];
+        let len = sprite.len().min(out.len());
+
+        out[..len]
+            .iter_mut()
+            .zip(sprite.iter())
+            .for_each(|(out, color_index)| *out = color_index.0);
+
+        len as i32
+    }
+
+    fn read_palette(&self, palette_index: i32, out: &mut [u8]) -> i32 {
+        let palette = match self.rom.graphics.validate_palette_index(palette_index) {
+            Ok(index) => &self.rom.graphics.palettes[index.0 as usize],
+            Err(_) => return -1,
+        };
+
+        let mut count = 0;
+        out.chunks_exact_mut(BYTES_PER_PIXEL)
+            .zip(palette.colors.iter())
+            .for_each(|(out, color)| {
+                out.copy_from_slice(&color.into_pixel_data());
+                count += 1;
+            });
+
+        count
+    }
 }
 
 impl DataContext {
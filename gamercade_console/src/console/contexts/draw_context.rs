@@ -1,5 +1,7 @@
 use crate::{api::DrawApi, pixel_buffer::PixelBuffer};
-use gamercade_core::{Color, GraphicsParameters, XCord, YCord, BYTES_PER_PIXEL};
+use gamercade_core::{
+    Color, GraphicsParameters, PaletteIndex, XCord, YCord, BYTES_PER_PIXEL, PALETTE_COLORS,
+};
 use gamercade_fs::Rom;
 use std::{
     ops::{Add, Sub},
@@ -10,16 +12,44 @@ use std::{
 pub struct DrawContext {
     pub(crate) frame_buffer: PixelBuffer,
     pub(crate) rom: Arc<Rom>,
+
+    /// One color index per pixel, used when indexed mode is enabled.
+    pub(crate) indexed_buffer: Box<[u8]>,
+
+    /// The palette used to resolve the indexed buffer. Indexed mode
+    /// is enabled when this is Some.
+    pub(crate) indexed_palette: Option<PaletteIndex>,
 }
 
 impl DrawContext {
     pub fn new(rom: Arc<Rom>) -> Self {
         Self {
             frame_buffer: PixelBuffer::init_from_rom(&rom),
+            indexed_buffer: vec![0; rom.resolution.total_pixels() as usize].into_boxed_slice(),
+            indexed_palette: None,
             rom,
         }
     }
 
+    /// The active palette's colors, when indexed mode is enabled.
+    fn indexed_colors(&self) -> Option<[[u8; BYTES_PER_PIXEL]; PALETTE_COLORS]> {
+        self.indexed_palette
+            .and_then(|palette| self.rom.graphics.palette(palette))
+            .map(|palette| palette.as_pixel_colors())
+    }
+
+    /// Copies the current frame into the output buffer. When indexed mode
+    /// is enabled, the indexed buffer is resolved through the active palette
+    /// instead, and the regular frame buffer is ignored.
+    pub fn blit(&self, buffer: &mut [u8]) {
+        match self.indexed_colors() {
+            Some(colors) => {
+                resolve_indexed(&colors, &self.indexed_buffer, buffer);
+            }
+            None => buffer.copy_from_slice(&self.frame_buffer.pixel_buffer),
+        }
+    }
+
     pub fn try_get_xcord<T: Into<i32>>(&self, x: T) -> Option<XCord> {
         self.rom.resolution.try_get_xcord(x)
     }
@@ -267,6 +297,64 @@ impl DrawApi for DrawContext {
                     .copy_from_slice(&color.into_pixel_data());
             });
     }
+
+    fn enable_indexed_mode(&mut self, palette_index: i32) {
+        if let Ok(palette_index) = self.rom.graphics.validate_palette_index(palette_index) {
+            self.indexed_palette = Some(palette_index);
+        }
+    }
+
+    fn disable_indexed_mode(&mut self) {
+        self.indexed_palette = None;
+    }
+
+    fn write_indexed_buffer(&mut self, start_index: usize, data: &[u8]) {
+        if let Some(target) = self.indexed_buffer.get_mut(start_index..) {
+            let len = target.len().min(data.len());
+            target[..len].copy_from_slice(&data[..len]);
+        }
+    }
+
+    fn read_pixel_buffer(&self, start_index: usize, out: &mut [u8]) -> i32 {
+        // Reads what's shown on screen, so indexed mode reads through the palette
+        if let Some(colors) = self.indexed_colors() {
+            return match self.indexed_buffer.get(start_index..) {
+                Some(source) => resolve_indexed(&colors, source, out) as i32,
+                None => -1,
+            };
+        }
+
+        let source = match start_index
+            .checked_mul(BYTES_PER_PIXEL)
+            .and_then(|start| self.frame_buffer.pixel_buffer.get(start..))
+        {
+            Some(source) => source,
+            None => return -1,
+        };
+
+        let len = source.len().min(out.len());
+        out[..len].copy_from_slice(&source[..len]);
+
+        (len / BYTES_PER_PIXEL) as i32
+    }
+}
+
+/// Writes the color of each index into the output pixels, returning how many were written.
+/// Indices outside of the palette are drawn as transparent black.
+fn resolve_indexed(
+    colors: &[[u8; BYTES_PER_PIXEL]; PALETTE_COLORS],
+    indices: &[u8],
+    out: &mut [u8],
+) -> usize {
+    out.chunks_exact_mut(BYTES_PER_PIXEL)
+        .zip(indices.iter())
+        .map(|(pixel, color_index)| {
+            let color = colors
+                .get(*color_index as usize)
+                .unwrap_or(&[0; BYTES_PER_PIXEL]);
+            pixel.copy_from_slice(color)
+        })
+        .count()
 }
 
 impl DrawContext {
@@ -1,6 +1,6 @@
 use paste::paste;
 
-use gamercade_core::{ButtonCode, InputState};
+use gamercade_core::{ButtonCode, InputState, TouchPoint, MAX_INPUT_ACTIONS};
 
 use crate::api::InputApi;
 use crate::console::PlayerInputEntry;
@@ -9,17 +9,45 @@ use crate::console::PlayerInputEntry;
 pub struct InputContext {
     pub(crate) input_entries: Box<[PlayerInputEntry]>,
     pub(crate) mouse_locked: bool,
+
+    /// The number of actions declared by the game
+    action_count: usize,
 }
 
 impl InputContext {
-    pub fn new(num_players: usize) -> Self {
+    pub fn new(num_players: usize, action_count: usize) -> Self {
         Self {
             input_entries: (0..num_players)
                 .map(|_| PlayerInputEntry::default())
                 .collect(),
             mouse_locked: false,
+            action_count: action_count.min(MAX_INPUT_ACTIONS),
         }
     }
+
+    /// Returns if the action was held on the previous and current frame.
+    fn action_states(&self, player_id: i32, action_id: i32) -> Option<(bool, bool)> {
+        let player_input = self.input_entries.get(usize::try_from(player_id).ok()?)?;
+        let action_id = usize::try_from(action_id)
+            .ok()
+            .filter(|action_id| *action_id < self.action_count)?;
+
+        Some((
+            player_input.previous_actions.get_action(action_id),
+            player_input.current_actions.get_action(action_id),
+        ))
+    }
+
+    /// Returns the previous and current state of the touch point.
+    fn touch_points(&self, player_id: i32, index: i32) -> Option<(TouchPoint, TouchPoint)> {
+        let player_input = self.input_entries.get(usize::try_from(player_id).ok()?)?;
+        let index = usize::try_from(index).ok()?;
+
+        Some((
+            player_input.previous_touch.get_point(index)?,
+            player_input.current_touch.get_point(index)?,
+        ))
+    }
 }
 
 /// This file automatically derives the various "get input" or "check input"
@@ -138,7 +166,31 @@ macro_rules! derive_generate_input_api {
 
                     fn [<mouse_ $maxis_name _delta>](&self, player_id: i32) -> i32 {
                         if let Some(player_input) = &self.input_entries.get(player_id as usize) {
-                            player_input.current_mouse.[<get_ $maxis_name _delta>]() as i32
+                            player_input.current_motion.[<get_ $maxis_name>]() as i32
+                        } else {
+                            i32::MIN
+                        }
+                    }
+
+                    fn [<mouse_ $maxis_name _previous_pos>](&self, player_id: i32) -> i32 {
+                        if let Some(player_input) = &self.input_entries.get(player_id as usize) {
+                            player_input.previous_mouse.[<get_ $maxis_name _pos>]() as i32
+                        } else {
+                            -1
+                        }
+                    }
+
+                    fn [<mouse_ $maxis_name _delta_precise>](&self, player_id: i32) -> f32 {
+                        if let Some(player_input) = &self.input_entries.get(player_id as usize) {
+                            player_input.current_motion.[<get_ $maxis_name>]()
+                        } else {
+                            f32::NAN
+                        }
+                    }
+
+                    fn [<mouse_wheel_ $maxis_name>](&self, player_id: i32) -> i32 {
+                        if let Some(player_input) = &self.input_entries.get(player_id as usize) {
+                            player_input.current_mouse.[<get_wheel_ $maxis_name>]()
                         } else {
                             i32::MIN
                         }
@@ -173,6 +225,86 @@ macro_rules! derive_generate_input_api {
                     unsafe { std::mem::transmute(state) }
                 }
 
+                fn touch_count(&self, player_id: i32) -> i32 {
+                    if let Some(player_input) = self.input_entries.get(player_id as usize) {
+                        player_input.current_touch.active_count() as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn touch_pressed(&self, player_id: i32, index: i32) -> i32 {
+                    if let Some((prev, curr)) = self.touch_points(player_id, index) {
+                        (!prev.get_active() && curr.get_active()) as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn touch_released(&self, player_id: i32, index: i32) -> i32 {
+                    if let Some((prev, curr)) = self.touch_points(player_id, index) {
+                        (prev.get_active() && !curr.get_active()) as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn touch_held(&self, player_id: i32, index: i32) -> i32 {
+                    if let Some((_, curr)) = self.touch_points(player_id, index) {
+                        curr.get_active() as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn touch_x_pos(&self, player_id: i32, index: i32) -> i32 {
+                    if let Some((_, curr)) = self.touch_points(player_id, index) {
+                        curr.get_x_pos() as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn touch_y_pos(&self, player_id: i32, index: i32) -> i32 {
+                    if let Some((_, curr)) = self.touch_points(player_id, index) {
+                        curr.get_y_pos() as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn controller_connected(&self, player_id: i32) -> i32 {
+                    if let Some(player_input) = self.input_entries.get(player_id as usize) {
+                        player_input.current_status.get_connected() as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn action_pressed(&self, player_id: i32, action_id: i32) -> i32 {
+                    if let Some((prev, curr)) = self.action_states(player_id, action_id) {
+                        (!prev && curr) as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn action_released(&self, player_id: i32, action_id: i32) -> i32 {
+                    if let Some((prev, curr)) = self.action_states(player_id, action_id) {
+                        (prev && !curr) as i32
+                    } else {
+                        -1
+                    }
+                }
+
+                fn action_held(&self, player_id: i32, action_id: i32) -> i32 {
+                    if let Some((_, curr)) = self.action_states(player_id, action_id) {
+                        curr as i32
+                    } else {
+                        -1
+                    }
+                }
+
                 fn lock_mouse(&mut self, locked: i32) {
                     if locked != 0 {
                         self.mouse_locked = true
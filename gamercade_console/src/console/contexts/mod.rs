@@ -1,15 +1,19 @@
 mod audio_context;
+mod collision_context;
 mod data_context;
 mod draw_context;
 mod graphics_parameter_context;
 mod input_context;
 mod multiplayer_context;
 mod random_context;
+mod rumble_context;
 mod text_context;
+mod text_input_context;
 
 use std::sync::Arc;
 
 use audio_context::*;
+use collision_context::CollisionContext;
 use data_context::DataContext;
 use draw_context::DrawContext;
 use gamercade_fs::Rom;
@@ -18,9 +22,12 @@ use graphics_parameter_context::GraphicsParameterContext;
 use input_context::InputContext;
 use multiplayer_context::MultiplayerContext;
 use random_context::RandomContext;
+use rumble_context::RumbleContext;
+pub(crate) use rumble_context::RumbleState;
 use text_context::TextContext;
+use text_input_context::TextInputContext;
 
-use super::SessionDescriptor;
+use super::{SessionDescriptor, SessionMode};
 pub struct Contexts {
     pub(crate) draw_context: DrawContext,
     pub(crate) input_context: InputContext,
@@ -30,6 +37,9 @@ pub struct Contexts {
     pub(crate) text_context: TextContext,
     pub(crate) multiplayer_context: MultiplayerContext,
     pub(crate) audio_context: AudioContext,
+    pub(crate) collision_context: CollisionContext,
+    pub(crate) text_input_context: TextInputContext,
+    pub(crate) rumble_context: RumbleContext,
 }
 
 impl Contexts {
@@ -37,18 +47,22 @@ impl Contexts {
         rom: &Arc<Rom>,
         seed: u64,
         session: SessionDescriptor,
+        mode: SessionMode,
         sound_rom: &Arc<SoundRomInstance>,
         output_sample_rate: usize,
     ) -> Self {
         Self {
             draw_context: DrawContext::new(rom.clone()),
-            input_context: InputContext::new(session.num_players),
+            input_context: InputContext::new(session.num_players, rom.input_actions.len()),
             random_context: RandomContext::new(seed),
             data_context: DataContext::new(rom.clone()),
             graphics_parameter_context: GraphicsParameterContext::default(),
             text_context: TextContext::default(),
+            text_input_context: TextInputContext::new(&session, mode),
+            rumble_context: RumbleContext::new(&session),
             multiplayer_context: MultiplayerContext::new(session),
             audio_context: AudioContext::new(sound_rom, output_sample_rate),
+            collision_context: CollisionContext::new(rom.clone()),
         }
     }
 }
@@ -5,11 +5,18 @@ use crate::{api::MultiplayerApi, console::SessionDescriptor};
 #[derive(Clone)]
 pub struct MultiplayerContext {
     pub(crate) session: SessionDescriptor,
+
+    /// Set from each frame's input statuses, so every console agrees on when a player left
+    pub(crate) disconnected: Box<[bool]>,
 }
 
 impl MultiplayerContext {
     pub fn new(session: SessionDescriptor) -> Self {
-        Self { session }
+        let disconnected = vec![false; session.num_players].into_boxed_slice();
+        Self {
+            session,
+            disconnected,
+        }
     }
 }
 
@@ -33,4 +40,12 @@ impl MultiplayerApi for MultiplayerContext {
             None => -1,                       // Invalid index
         }
     }
+
+    fn is_player_connected(&self, player_id: i32) -> i32 {
+        match self.disconnected.get(player_id as usize) {
+            Some(false) => 1, // Target player is connected
+            Some(true) => 0,  // Valid index, but disconnected
+            None => -1,       // Invalid index
+        }
+    }
 }
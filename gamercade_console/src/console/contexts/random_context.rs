@@ -13,6 +13,16 @@ impl RandomContext {
             shared_rng: Rng::with_seed(shared_seed),
         }
     }
+
+    /// The generator's current state, which is saved so rollbacks
+    /// produce the same random numbers again.
+    pub(crate) fn state(&self) -> u64 {
+        self.shared_rng.get_seed()
+    }
+
+    pub(crate) fn restore_state(&self, state: u64) {
+        self.shared_rng.seed(state);
+    }
 }
 
 impl RandomApi for RandomContext {
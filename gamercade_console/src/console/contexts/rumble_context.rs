@@ -0,0 +1,80 @@
+use ggrs::PlayerType;
+
+use crate::{api::RumbleApi, console::SessionDescriptor};
+
+/// The rumble requested by the game for a single player.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RumbleState {
+    pub strong: f32,
+    pub weak: f32,
+    pub remaining_frames: u32,
+}
+
+impl RumbleState {
+    /// Returns the strength of the strong and weak motors, each in the range [0, 1].
+    pub fn strength(&self) -> (f32, f32) {
+        if self.remaining_frames > 0 {
+            (self.strong, self.weak)
+        } else {
+            (0.0, 0.0)
+        }
+    }
+}
+
+/// Rumble is stored as part of the game state, so that it rolls back along
+/// with everything else. The host only applies it to the gamepads after
+/// all frames have been simulated, so resimulated frames don't rumble twice.
+#[derive(Clone)]
+pub struct RumbleContext {
+    local_players: Box<[bool]>,
+    pub(crate) players: Box<[RumbleState]>,
+}
+
+impl RumbleContext {
+    pub fn new(session: &SessionDescriptor) -> Self {
+        Self {
+            local_players: session
+                .player_types
+                .iter()
+                .map(|player| matches!(player, PlayerType::Local))
+                .collect(),
+            players: (0..session.num_players)
+                .map(|_| RumbleState::default())
+                .collect(),
+        }
+    }
+
+    /// Counts down the remaining rumble, called at the start of each frame.
+    pub fn advance(&mut self) {
+        self.players.iter_mut().for_each(|player| {
+            player.remaining_frames = player.remaining_frames.saturating_sub(1);
+        });
+    }
+
+    fn local_player_mut(&mut self, player_id: i32) -> Option<&mut RumbleState> {
+        let player_id = usize::try_from(player_id).ok()?;
+
+        match self.local_players.get(player_id) {
+            Some(true) => self.players.get_mut(player_id),
+            _ => None,
+        }
+    }
+}
+
+impl RumbleApi for RumbleContext {
+    fn rumble(&mut self, player_id: i32, strong: f32, weak: f32, duration_frames: i32) {
+        if let Some(player) = self.local_player_mut(player_id) {
+            *player = RumbleState {
+                strong: strong.clamp(0.0, 1.0),
+                weak: weak.clamp(0.0, 1.0),
+                remaining_frames: duration_frames.max(0) as u32,
+            };
+        }
+    }
+
+    fn stop_rumble(&mut self, player_id: i32) {
+        if let Some(player) = self.local_player_mut(player_id) {
+            *player = RumbleState::default();
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use ggrs::PlayerType;
+
+use crate::{
+    api::TextInputApi,
+    console::{SessionDescriptor, SessionMode, TextInputCollector},
+};
+
+/// Holds the text typed by the local players during the current frame.
+/// Text isn't part of the synchronized input state, so it is only
+/// available in sessions without any remote players. SyncTest sessions
+/// are excluded too, as resimulated frames wouldn't see the text again.
+#[derive(Clone)]
+pub struct TextInputContext {
+    pub(crate) allowed: bool,
+    pub(crate) active: bool,
+    pub(crate) frame: TextInputCollector,
+}
+
+impl TextInputContext {
+    pub fn new(session: &SessionDescriptor, mode: SessionMode) -> Self {
+        Self {
+            allowed: !matches!(mode, SessionMode::SyncTest { .. })
+                && session
+                    .player_types
+                    .iter()
+                    .all(|player| matches!(player, PlayerType::Local)),
+            active: false,
+            frame: TextInputCollector::default(),
+        }
+    }
+}
+
+impl TextInputApi for TextInputContext {
+    fn start_text_input(&mut self) -> i32 {
+        self.active = self.allowed;
+        self.active as i32
+    }
+
+    fn stop_text_input(&mut self) {
+        self.active = false;
+        self.frame = TextInputCollector::default();
+    }
+
+    fn text_input_active(&self) -> i32 {
+        self.active as i32
+    }
+
+    fn text_input_len(&self) -> i32 {
+        self.frame.text.len() as i32
+    }
+
+    fn text_input_backspace_count(&self) -> i32 {
+        self.frame.backspaces as i32
+    }
+
+    fn text_input_enter_pressed(&self) -> i32 {
+        self.frame.enter as i32
+    }
+
+    fn read_text_input(&self, out: &mut [u8]) -> i32 {
+        let text = &self.frame.text;
+
+        // Don't split a character in half if the buffer is too small
+        let mut len = text.len().min(out.len());
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        out[..len].copy_from_slice(&text.as_bytes()[..len]);
+        len as i32
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use ggrs::{Frame, NULL_FRAME};
+use wasmtime::Val;
+
+/// How often confirmed frames are compared with the remote peers.
+pub const DESYNC_CHECK_INTERVAL: Frame = 10;
+
+/// How many compared frames are kept around, while waiting for slower
+/// peers to send their checksums.
+const MAX_CHECKED_FRAMES: usize = 32;
+
+/// The directory, relative to the working directory, where desynced states are written.
+const DESYNC_DUMP_DIR: &str = "desyncs";
+
+/// A fast hash used to compare game states between peers. Unlike the std
+/// hashers, the output is the same for every build and platform.
+#[derive(Default)]
+pub(crate) struct StateHasher(u64);
+
+impl StateHasher {
+    const MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.0 = (self.0.rotate_left(5) ^ value).wrapping_mul(Self::MULTIPLIER);
+    }
+
+    /// Hashes the bytes eight at a time, which is fast enough to run over
+    /// all of the game's memory each frame.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        chunks
+            .by_ref()
+            .for_each(|chunk| self.write_u64(u64::from_le_bytes(chunk.try_into().unwrap())));
+
+        let mut remainder = [0; 8];
+        remainder[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+        self.write_u64(u64::from_le_bytes(remainder));
+        self.write_u64(bytes.len() as u64);
+    }
+
+    pub fn write_val(&mut self, val: &Val) {
+        match val {
+            Val::I32(value) => self.write_u64(*value as u64),
+            Val::I64(value) => self.write_u64(*value as u64),
+            Val::F32(bits) => self.write_u64(*bits as u64),
+            Val::F64(bits) => self.write_u64(*bits),
+            Val::V128(bits) => {
+                self.write_u64(*bits as u64);
+                self.write_u64((*bits >> 64) as u64);
+            }
+            // References aren't part of the game's state
+            Val::FuncRef(_) | Val::ExternRef(_) => (),
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The parts of a game state which are compared between peers,
+/// kept so they can be written to disk if the peers disagree.
+pub(crate) struct StateSnapshot {
+    pub checksum: u64,
+    pub memories: Vec<(String, Vec<u8>)>,
+    pub globals: Vec<(String, String)>,
+    pub bgm: Option<usize>,
+}
+
+impl StateSnapshot {
+    /// Writes a summary of the state, along with each memory as its own file.
+    fn dump(&self, frame: Frame, dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut summary = format!(
+            "frame: {}\nchecksum: {:016x}\nbgm: {:?}\n",
+            frame, self.checksum, self.bgm
+        );
+        self.globals.iter().for_each(|(name, value)| {
+            summary.push_str(&format!("global {}: {}\n", name, value));
+        });
+        std::fs::write(dir.join("summary.txt"), summary)?;
+
+        self.memories.iter().try_for_each(|(name, memory)| {
+            std::fs::write(dir.join(format!("memory_{}.bin", name)), memory)
+        })
+    }
+}
+
+/// A checksum of a confirmed frame, sent to the other peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ChecksumReport {
+    pub frame: Frame,
+    pub checksum: u64,
+}
+
+impl ChecksumReport {
+    pub const SIZE: usize = 12;
+
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut out = [0; Self::SIZE];
+        out[..4].copy_from_slice(&self.frame.to_le_bytes());
+        out[4..].copy_from_slice(&self.checksum.to_le_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::SIZE {
+            return None;
+        }
+
+        Some(Self {
+            frame: Frame::from_le_bytes(bytes[..4].try_into().ok()?),
+            checksum: u64::from_le_bytes(bytes[4..].try_into().ok()?),
+        })
+    }
+}
+
+/// A confirmed frame where a remote peer's state didn't match ours.
+#[derive(Debug, Clone)]
+pub struct Desync {
+    pub frame: Frame,
+    pub addr: SocketAddr,
+    pub local_checksum: u64,
+    pub remote_checksum: u64,
+
+    /// Where our side of the state was written, if it succeeded
+    pub dump_dir: Option<PathBuf>,
+}
+
+/// Compares the checksums of confirmed frames with the remote peers. As every
+/// peer has the same inputs for a confirmed frame, any difference means the
+/// game has desynced.
+pub struct DesyncDetector {
+    local_port: u16,
+
+    /// Where desynced states are written
+    dump_dir: PathBuf,
+    remotes: Vec<SocketAddr>,
+    confirmed_frame: Frame,
+
+    /// Recent local states, which are waiting to be compared
+    snapshots: BTreeMap<Frame, StateSnapshot>,
+
+    /// Checksums from the remote peers, for frames we haven't confirmed yet
+    received: Vec<(SocketAddr, ChecksumReport)>,
+
+    /// Checksums of newly confirmed frames, which need to be sent to the remote peers
+    outgoing: Vec<ChecksumReport>,
+
+    desyncs: Vec<Desync>,
+}
+
+impl DesyncDetector {
+    pub fn new(local_port: u16, remotes: Vec<SocketAddr>) -> Self {
+        Self {
+            local_port,
+            dump_dir: PathBuf::from(DESYNC_DUMP_DIR),
+            remotes,
+            confirmed_frame: NULL_FRAME,
+            snapshots: BTreeMap::new(),
+            received: Vec::new(),
+            outgoing: Vec::new(),
+            desyncs: Vec::new(),
+        }
+    }
+
+    pub fn remotes(&self) -> &[SocketAddr] {
+        &self.remotes
+    }
+
+    pub fn desyncs(&self) -> &[Desync] {
+        &self.desyncs
+    }
+
+    /// Returns true if the frame's state should be recorded. States are only
+    /// recorded during online sessions, every few frames.
+    pub(crate) fn wants_frame(&self, frame: Frame) -> bool {
+        !self.remotes.is_empty()
+            && frame % DESYNC_CHECK_INTERVAL == 0
+            && frame > self.confirmed_frame
+    }
+
+    /// Records the state for the frame. States which are saved again
+    /// after a rollback replace the earlier, mispredicted one.
+    pub(crate) fn record(&mut self, frame: Frame, snapshot: StateSnapshot) {
+        self.snapshots.insert(frame, snapshot);
+    }
+
+    /// Called once the inputs for all frames up to and including
+    /// this one have been received from every peer.
+    pub(crate) fn confirm(&mut self, frame: Frame) {
+        if frame <= self.confirmed_frame {
+            return;
+        }
+
+        self.snapshots
+            .range(self.confirmed_frame + 1..=frame)
+            .for_each(|(frame, snapshot)| {
+                self.outgoing.push(ChecksumReport {
+                    frame: *frame,
+                    checksum: snapshot.checksum,
+                })
+            });
+        self.confirmed_frame = frame;
+
+        while self.snapshots.len() > MAX_CHECKED_FRAMES {
+            let oldest = *self.snapshots.keys().next().unwrap();
+            self.snapshots.remove(&oldest);
+        }
+
+        self.compare();
+    }
+
+    pub(crate) fn receive(&mut self, addr: SocketAddr, report: ChecksumReport) {
+        if self.remotes.contains(&addr) {
+            self.received.push((addr, report));
+            self.compare();
+        }
+    }
+
+    pub(crate) fn take_outgoing(&mut self) -> Vec<ChecksumReport> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Compares the received checksums against our own confirmed frames.
+    /// Checksums for frames which we haven't confirmed yet are kept for later.
+    fn compare(&mut self) {
+        let received = std::mem::take(&mut self.received);
+
+        for (addr, report) in received {
+            if report.frame > self.confirmed_frame {
+                self.received.push((addr, report));
+                continue;
+            }
+
+            match self.snapshots.get(&report.frame) {
+                Some(snapshot) if snapshot.checksum != report.checksum => {
+                    let local_checksum = snapshot.checksum;
+                    self.report_desync(addr, report, local_checksum);
+                }
+                // Matches, or is too old to compare
+                _ => (),
+            }
+        }
+    }
+
+    fn report_desync(&mut self, addr: SocketAddr, report: ChecksumReport, local_checksum: u64) {
+        println!(
+            "Desync detected with {} at frame {}: local checksum {:016x}, remote checksum {:016x}",
+            addr, report.frame, local_checksum, report.checksum
+        );
+
+        // Each frame is only written once, even if several peers disagree with it
+        let already_dumped = self
+            .desyncs
+            .iter()
+            .find(|desync| desync.frame == report.frame)
+            .map(|desync| desync.dump_dir.clone());
+
+        let dump_dir = match already_dumped {
+            Some(dump_dir) => dump_dir,
+            None => {
+                let dir = self
+                    .dump_dir
+                    .join(format!("frame_{}_port_{}", report.frame, self.local_port));
+
+                match self.snapshots[&report.frame].dump(report.frame, &dir) {
+                    Ok(()) => {
+                        println!("Desynced state written to: {:?}", dir);
+                        Some(dir)
+                    }
+                    Err(e) => {
+                        println!("Unable to write desynced state to {:?}: {}", dir, e);
+                        None
+                    }
+                }
+            }
+        };
+
+        self.desyncs.push(Desync {
+            frame: report.frame,
+            addr,
+            local_checksum,
+            remote_checksum: report.checksum,
+            dump_dir,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote() -> SocketAddr {
+        "127.0.0.1:8001".parse().unwrap()
+    }
+
+    fn snapshot(checksum: u64) -> StateSnapshot {
+        StateSnapshot {
+            checksum,
+            memories: vec![("memory".to_string(), vec![1, 2, 3])],
+            globals: Vec::new(),
+            bgm: None,
+        }
+    }
+
+    fn detector(test_name: &str) -> DesyncDetector {
+        let mut detector = DesyncDetector::new(8000, vec![remote()]);
+        detector.dump_dir = std::env::temp_dir().join(format!("gamercade_{}", test_name));
+        detector
+    }
+
+    #[test]
+    fn test_checksum_report_round_trip() {
+        let report = ChecksumReport {
+            frame: 1234,
+            checksum: 0x0123_4567_89ab_cdef,
+        };
+
+        assert_eq!(ChecksumReport::from_bytes(&report.to_bytes()), Some(report));
+        assert_eq!(ChecksumReport::from_bytes(&report.to_bytes()[1..]), None);
+    }
+
+    #[test]
+    fn test_wants_frame() {
+        let mut detector = detector("wants_frame");
+        assert!(detector.wants_frame(DESYNC_CHECK_INTERVAL));
+        assert!(!detector.wants_frame(DESYNC_CHECK_INTERVAL + 1));
+
+        detector.confirm(DESYNC_CHECK_INTERVAL);
+        assert!(!detector.wants_frame(DESYNC_CHECK_INTERVAL));
+
+        // Offline sessions have nobody to compare with
+        let offline = DesyncDetector::new(8000, Vec::new());
+        assert!(!offline.wants_frame(DESYNC_CHECK_INTERVAL));
+    }
+
+    #[test]
+    fn test_matching_checksums() {
+        let mut detector = detector("matching_checksums");
+        detector.record(10, snapshot(1));
+        detector.record(20, snapshot(2));
+
+        // Rolled back states replace the mispredicted ones
+        detector.record(20, snapshot(3));
+
+        detector.confirm(20);
+        assert_eq!(
+            detector.take_outgoing(),
+            vec![
+                ChecksumReport {
+                    frame: 10,
+                    checksum: 1
+                },
+                ChecksumReport {
+                    frame: 20,
+                    checksum: 3
+                },
+            ]
+        );
+        assert!(detector.take_outgoing().is_empty());
+
+        detector.receive(
+            remote(),
+            ChecksumReport {
+                frame: 20,
+                checksum: 3,
+            },
+        );
+        assert!(detector.desyncs().is_empty());
+    }
+
+    #[test]
+    fn test_mismatched_checksum() {
+        let mut detector = detector("mismatched_checksum");
+        detector.record(10, snapshot(1));
+
+        // Reports for frames which aren't confirmed yet wait until they are
+        detector.receive(
+            remote(),
+            ChecksumReport {
+                frame: 10,
+                checksum: 2,
+            },
+        );
+        assert!(detector.desyncs().is_empty());
+
+        detector.confirm(10);
+        let desync = &detector.desyncs()[0];
+        assert_eq!(desync.frame, 10);
+        assert_eq!(desync.addr, remote());
+        assert_eq!(desync.local_checksum, 1);
+        assert_eq!(desync.remote_checksum, 2);
+
+        let dump_dir = desync.dump_dir.clone().unwrap();
+        assert!(dump_dir.join("summary.txt").exists());
+        assert!(dump_dir.join("memory_memory.bin").exists());
+        let _ = std::fs::remove_dir_all(detector.dump_dir);
+    }
+
+    #[test]
+    fn test_unknown_peers_are_ignored() {
+        let mut detector = detector("unknown_peers");
+        detector.record(10, snapshot(1));
+        detector.confirm(10);
+
+        detector.receive(
+            "127.0.0.1:9000".parse().unwrap(),
+            ChecksumReport {
+                frame: 10,
+                checksum: 2,
+            },
+        );
+        assert!(detector.desyncs().is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use ggrs::{
+    Frame, GGRSError, GGRSEvent, GGRSRequest, NetworkStats, P2PSession, PlayerHandle, PlayerType,
+    SessionBuilder, SessionState, SpectatorSession, SyncTestSession, NULL_FRAME,
+};
+use parking_lot::Mutex;
+
+use super::{
+    network::NetworkInputState, ConsoleSocket, DesyncDetector, HandshakeMessage, NetworkConditions,
+    NetworkSimulator, SessionDescriptor, WasmConsole,
+};
+
+/// The number of frames GGRS predicts ahead by default.
+pub const DEFAULT_MAX_PREDICTION: usize = 8;
+
+/// How long a peer can go without sending anything before it's disconnected.
+pub const DEFAULT_DISCONNECT_TIMEOUT_MS: u64 = 2000;
+
+/// How long a peer can go without sending anything before the players are warned.
+pub const DEFAULT_DISCONNECT_NOTIFY_MS: u64 = 500;
+
+/// The default number of frames each SyncTest rollback goes back.
+pub const DEFAULT_CHECK_DISTANCE: usize = 2;
+
+/// GGRS requires the check distance to be less than the max prediction.
+pub const MAX_CHECK_DISTANCE: usize = DEFAULT_MAX_PREDICTION - 1;
+
+/// How far a spectator can fall behind the host before catching up.
+pub const DEFAULT_MAX_FRAMES_BEHIND: usize = 10;
+
+/// How many frames a spectator simulates each frame while catching up.
+pub const DEFAULT_CATCHUP_SPEED: usize = 2;
+
+/// Trade-offs between responsiveness and rollbacks, which depend on the players' connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionSettings {
+    /// Frames each local input is delayed by. Hides latency, at the cost of responsiveness.
+    pub input_delay: usize,
+
+    /// How many frames can be predicted before waiting for the remote inputs.
+    pub max_prediction: usize,
+    pub disconnect_timeout_ms: u64,
+    pub disconnect_notify_ms: u64,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            input_delay: 0,
+            max_prediction: DEFAULT_MAX_PREDICTION,
+            disconnect_timeout_ms: DEFAULT_DISCONNECT_TIMEOUT_MS,
+            disconnect_notify_ms: DEFAULT_DISCONNECT_NOTIFY_MS,
+        }
+    }
+}
+
+/// The kind of session to start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionMode {
+    /// Local and remote players, along with any spectators.
+    P2P,
+
+    /// Rolls back every frame by the check distance, and compares the
+    /// checksums of the resimulated frames.
+    SyncTest { check_distance: usize },
+
+    /// Watches a match being hosted by another console. Spectators
+    /// only receive confirmed inputs, so never roll back.
+    Spectator {
+        host: SocketAddr,
+        max_frames_behind: usize,
+        catchup_speed: usize,
+    },
+}
+
+/// A running session, of any of the session modes.
+pub enum GameSession {
+    P2P(Box<P2PSession<WasmConsole>>),
+    SyncTest(Box<SyncTestSession<WasmConsole>>),
+    Spectator(Box<SpectatorSession<WasmConsole>>),
+}
+
+impl GameSession {
+    pub fn start(
+        fps: usize,
+        session_descriptor: &SessionDescriptor,
+        mode: SessionMode,
+        desync_detector: Arc<Mutex<DesyncDetector>>,
+        handshake: HandshakeMessage,
+        network_conditions: NetworkConditions,
+        settings: SessionSettings,
+    ) -> Result<Self, String> {
+        let port = session_descriptor.port;
+        let mut sess_builder = SessionBuilder::new()
+            .with_num_players(session_descriptor.num_players)
+            .with_input_delay(settings.input_delay)
+            .with_max_prediction_window(settings.max_prediction)
+            .with_disconnect_timeout(Duration::from_millis(settings.disconnect_timeout_ms))
+            .with_disconnect_notify_delay(Duration::from_millis(settings.disconnect_notify_ms))
+            .with_fps(fps)
+            .map_err(|e| e.to_string())?;
+
+        let bind_socket = || {
+            ConsoleSocket::bind_to_port(
+                port,
+                session_descriptor.relay.as_ref(),
+                desync_detector,
+                handshake,
+            )
+            .map(|socket| NetworkSimulator::new(socket, network_conditions))
+            .map_err(|e| format!("Unable to bind to port {}: {}", port, e))
+        };
+
+        match mode {
+            SessionMode::P2P => {
+                for (id, address) in session_descriptor.player_types.iter().enumerate() {
+                    sess_builder = sess_builder
+                        .add_player(*address, id)
+                        .map_err(|e| e.to_string())?;
+                }
+
+                // Spectators are given the handles after the players
+                for (index, address) in session_descriptor.spectators.iter().enumerate() {
+                    sess_builder = sess_builder
+                        .add_player(
+                            PlayerType::Spectator(*address),
+                            session_descriptor.num_players + index,
+                        )
+                        .map_err(|e| e.to_string())?;
+                }
+
+                sess_builder
+                    .start_p2p_session(bind_socket()?)
+                    .map(|session| Self::P2P(Box::new(session)))
+                    .map_err(|e| e.to_string())
+            }
+            SessionMode::SyncTest { check_distance } => sess_builder
+                .with_check_distance(check_distance)
+                .start_synctest_session()
+                .map(|session| Self::SyncTest(Box::new(session)))
+                .map_err(|e| e.to_string()),
+            SessionMode::Spectator {
+                host,
+                max_frames_behind,
+                catchup_speed,
+            } => {
+                let sess_builder = sess_builder
+                    .with_max_frames_behind(max_frames_behind)
+                    .and_then(|builder| builder.with_catchup_speed(catchup_speed))
+                    .map_err(|e| e.to_string())?;
+
+                Ok(Self::Spectator(Box::new(
+                    sess_builder.start_spectator_session(host, bind_socket()?),
+                )))
+            }
+        }
+    }
+
+    pub fn is_sync_test(&self) -> bool {
+        matches!(self, Self::SyncTest(_))
+    }
+
+    pub fn is_spectator(&self) -> bool {
+        matches!(self, Self::Spectator(_))
+    }
+
+    pub fn poll_remote_clients(&mut self) {
+        match self {
+            Self::P2P(session) => session.poll_remote_clients(),
+            Self::Spectator(session) => session.poll_remote_clients(),
+            Self::SyncTest(_) => (),
+        }
+    }
+
+    /// Connection events since the last call, such as peers being interrupted or disconnecting.
+    pub fn events(&mut self) -> Vec<GGRSEvent<WasmConsole>> {
+        match self {
+            Self::P2P(session) => session.events().collect(),
+            Self::Spectator(session) => session.events().collect(),
+            Self::SyncTest(_) => Vec::new(),
+        }
+    }
+
+    pub fn current_state(&self) -> SessionState {
+        match self {
+            Self::P2P(session) => session.current_state(),
+            Self::Spectator(session) => session.current_state(),
+            Self::SyncTest(_) => SessionState::Running,
+        }
+    }
+
+    /// Spectators catch up by simulating several frames at once, rather than
+    /// slowing down the session, so are never treated as being ahead.
+    pub fn frames_ahead(&self) -> i32 {
+        match self {
+            Self::P2P(session) => session.frames_ahead(),
+            Self::SyncTest(_) | Self::Spectator(_) => 0,
+        }
+    }
+
+    pub fn max_prediction(&self) -> usize {
+        match self {
+            Self::P2P(session) => session.max_prediction(),
+            Self::SyncTest(session) => session.max_prediction(),
+            Self::Spectator(_) => DEFAULT_MAX_PREDICTION,
+        }
+    }
+
+    /// Every player is local in a SyncTest session, and none are for spectators.
+    pub fn local_player_handles(&self) -> Vec<PlayerHandle> {
+        match self {
+            Self::P2P(session) => session.local_player_handles(),
+            Self::SyncTest(session) => (0..session.num_players()).collect(),
+            Self::Spectator(_) => Vec::new(),
+        }
+    }
+
+    /// The latest frame which can no longer be rolled back. Only P2P
+    /// sessions have peers to compare confirmed frames with.
+    pub fn confirmed_frame(&self) -> Frame {
+        match self {
+            Self::P2P(session) => session.confirmed_frame(),
+            Self::SyncTest(_) | Self::Spectator(_) => NULL_FRAME,
+        }
+    }
+
+    /// Spectators only have a connection to the host, so the handle is ignored.
+    pub fn network_stats(&self, player_handle: PlayerHandle) -> Result<NetworkStats, GGRSError> {
+        match self {
+            Self::P2P(session) => session.network_stats(player_handle),
+            Self::Spectator(session) => session.network_stats(),
+            Self::SyncTest(_) => Err(GGRSError::InvalidRequest {
+                info: "SyncTest sessions don't have any remote peers.".to_string(),
+            }),
+        }
+    }
+
+    pub fn add_local_input(
+        &mut self,
+        player_handle: PlayerHandle,
+        input: NetworkInputState,
+    ) -> Result<(), GGRSError> {
+        match self {
+            Self::P2P(session) => session.add_local_input(player_handle, input),
+            Self::SyncTest(session) => session.add_local_input(player_handle, input),
+            Self::Spectator(_) => Err(GGRSError::InvalidRequest {
+                info: "Spectators don't have any local players.".to_string(),
+            }),
+        }
+    }
+
+    pub fn advance_frame(&mut self) -> Result<Vec<GGRSRequest<WasmConsole>>, GGRSError> {
+        match self {
+            Self::P2P(session) => session.advance_frame(),
+            Self::SyncTest(session) => session.advance_frame(),
+            Self::Spectator(session) => session.advance_frame(),
+        }
+    }
+}
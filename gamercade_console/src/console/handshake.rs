@@ -0,0 +1,404 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use gamercade_fs::Rom;
+use ggrs::PlayerType;
+
+use super::{
+    console_socket::{HANDSHAKE_PACKET, RECV_BUFFER_SIZE},
+    desync_detector::StateHasher,
+    PeerSocket, SessionDescriptor, SessionMode,
+};
+
+/// Bumped whenever the inputs or packets sent between consoles change,
+/// so that mismatched consoles refuse to play together.
+pub const NETPLAY_PROTOCOL_VERSION: u32 = 1;
+
+/// How often the handshake is sent, until each peer has replied.
+const HANDSHAKE_RESEND_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long to wait for every peer to reply, before giving up.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Hashes the whole ROM, so peers can tell if they loaded the same game.
+pub fn rom_hash(rom: &Rom) -> u64 {
+    let mut hasher = StateHasher::default();
+    hasher.write_bytes(&bincode::serialize(rom).unwrap());
+    hasher.finish()
+}
+
+/// Sent between consoles before the session starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandshakeMessage {
+    pub version: u32,
+    pub rom_hash: u64,
+    pub seed: u64,
+
+    /// True once the seed has been agreed on, and the session has started
+    pub agreed: bool,
+}
+
+impl HandshakeMessage {
+    pub const SIZE: usize = 21;
+
+    pub fn new(rom_hash: u64, seed: u64) -> Self {
+        Self {
+            version: NETPLAY_PROTOCOL_VERSION,
+            rom_hash,
+            seed,
+            agreed: false,
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut out = [0; Self::SIZE];
+        out[..4].copy_from_slice(&self.version.to_le_bytes());
+        out[4..12].copy_from_slice(&self.rom_hash.to_le_bytes());
+        out[12..20].copy_from_slice(&self.seed.to_le_bytes());
+        out[20] = self.agreed as u8;
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::SIZE {
+            return None;
+        }
+
+        Some(Self {
+            version: u32::from_le_bytes(bytes[..4].try_into().ok()?),
+            rom_hash: u64::from_le_bytes(bytes[4..12].try_into().ok()?),
+            seed: u64::from_le_bytes(bytes[12..20].try_into().ok()?),
+            agreed: bytes[20] != 0,
+        })
+    }
+}
+
+pub enum HandshakeStatus {
+    Waiting,
+
+    /// Every peer has the same game, and agreed on the seed.
+    Complete(HandshakeMessage),
+    Failed(String),
+}
+
+/// Checks that every peer has loaded the same game and console version, and agrees on
+/// the seed, before the session starts. Each console uses the seed of the console with
+/// the first player. Spectators use the seed the host agreed on.
+pub struct Handshake {
+    socket: PeerSocket,
+    local: HandshakeMessage,
+
+    /// The consoles which must reply before the session can start
+    peers: Vec<SocketAddr>,
+
+    /// The peer whose seed is used, or None for our own
+    seed_from: Option<SocketAddr>,
+
+    /// Spectators can only use the seed once the host has agreed on it
+    wait_for_agreed: bool,
+
+    received: HashMap<SocketAddr, HandshakeMessage>,
+    started: Instant,
+    last_sent: Option<Instant>,
+}
+
+impl Handshake {
+    /// Binds to the session's port, which is released once the handshake is dropped.
+    pub fn start(
+        session_descriptor: &SessionDescriptor,
+        mode: SessionMode,
+        local: HandshakeMessage,
+    ) -> Result<Self, String> {
+        let (peers, seed_from, wait_for_agreed) = match mode {
+            SessionMode::P2P => {
+                let mut peers = Vec::new();
+                session_descriptor.player_types.iter().for_each(|player| {
+                    if let PlayerType::Remote(addr) = player {
+                        if !peers.contains(addr) {
+                            peers.push(*addr);
+                        }
+                    }
+                });
+
+                let seed_from = match session_descriptor.player_types.first() {
+                    Some(PlayerType::Remote(addr)) => Some(*addr),
+                    _ => None,
+                };
+                (peers, seed_from, false)
+            }
+            SessionMode::Spectator { host, .. } => (vec![host], Some(host), true),
+            SessionMode::SyncTest { .. } => (Vec::new(), None, false),
+        };
+
+        let port = session_descriptor.port;
+        let socket = PeerSocket::bind(port, session_descriptor.relay.as_ref())
+            .map_err(|e| format!("Unable to bind to port {}: {}", port, e))?;
+
+        Ok(Self {
+            socket,
+            local,
+            peers,
+            seed_from,
+            wait_for_agreed,
+            received: HashMap::new(),
+            started: Instant::now(),
+            last_sent: None,
+        })
+    }
+
+    /// The peers which haven't replied yet.
+    pub fn waiting_for(&self) -> Vec<SocketAddr> {
+        self.peers
+            .iter()
+            .filter(|addr| !self.received.contains_key(addr))
+            .copied()
+            .collect()
+    }
+
+    pub fn poll(&mut self) -> HandshakeStatus {
+        if self
+            .last_sent
+            .map_or(true, |sent| sent.elapsed() >= HANDSHAKE_RESEND_INTERVAL)
+        {
+            let mut packet = vec![HANDSHAKE_PACKET];
+            packet.extend_from_slice(&self.local.to_bytes());
+
+            // Lost packets are covered by the next resend
+            self.peers.iter().for_each(|addr| {
+                let _ = self.socket.send_to(&packet, addr);
+            });
+            self.last_sent = Some(Instant::now());
+        }
+
+        let mut buffer = [0; RECV_BUFFER_SIZE];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((number_of_bytes, addr)) => {
+                    if !self.peers.contains(&addr) {
+                        continue;
+                    }
+
+                    // Other packets are the peer's session starting, which will be resent
+                    let message = match buffer[..number_of_bytes].split_first() {
+                        Some((&HANDSHAKE_PACKET, body)) => HandshakeMessage::from_bytes(body),
+                        _ => None,
+                    };
+
+                    if let Some(message) = message {
+                        if let Err(e) = self.check(addr, &message) {
+                            return HandshakeStatus::Failed(e);
+                        }
+
+                        if message.agreed || !self.wait_for_agreed {
+                            self.received.insert(addr, message);
+                        }
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // Windows reports the remote closing its socket as an error, which can be ignored
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => return HandshakeStatus::Failed(format!("Handshake failed: {}", e)),
+            }
+        }
+
+        let waiting_for = self.waiting_for();
+        if !waiting_for.is_empty() {
+            return if self.started.elapsed() > HANDSHAKE_TIMEOUT {
+                HandshakeStatus::Failed(format!(
+                    "Timed out waiting for a reply from: {:?}",
+                    waiting_for
+                ))
+            } else {
+                HandshakeStatus::Waiting
+            };
+        }
+
+        let seed = match self.seed_from {
+            Some(addr) => self.received[&addr].seed,
+            None => self.local.seed,
+        };
+
+        HandshakeStatus::Complete(HandshakeMessage {
+            seed,
+            agreed: true,
+            ..self.local
+        })
+    }
+
+    fn check(&self, addr: SocketAddr, message: &HandshakeMessage) -> Result<(), String> {
+        if message.version != self.local.version {
+            Err(format!(
+                "{} is running netplay version {}, but this console is running version {}. Update both consoles to the same version.",
+                addr, message.version, self.local.version
+            ))
+        } else if message.rom_hash != self.local.rom_hash {
+            Err(format!(
+                "{} loaded a different game, or a different version of it (ROM hash {:016x}, but this console's is {:016x}).",
+                addr, message.rom_hash, self.local.rom_hash
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Finds a port which is free right now, as peers must know each other's ports up front.
+    fn free_port() -> u16 {
+        UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn descriptor(port: u16, player_types: Vec<PlayerType<SocketAddr>>) -> SessionDescriptor {
+        SessionDescriptor {
+            num_players: player_types.len(),
+            player_types: player_types.into_boxed_slice(),
+            port,
+            spectators: Box::new([]),
+            relay: None,
+        }
+    }
+
+    /// Starts a handshake between two players, where the first console has the first player.
+    fn start_pair(first: HandshakeMessage, second: HandshakeMessage) -> (Handshake, Handshake) {
+        let (first_port, second_port) = (free_port(), free_port());
+
+        let first = Handshake::start(
+            &descriptor(
+                first_port,
+                vec![PlayerType::Local, PlayerType::Remote(addr(second_port))],
+            ),
+            SessionMode::P2P,
+            first,
+        )
+        .unwrap();
+        let second = Handshake::start(
+            &descriptor(
+                second_port,
+                vec![PlayerType::Remote(addr(first_port)), PlayerType::Local],
+            ),
+            SessionMode::P2P,
+            second,
+        )
+        .unwrap();
+
+        (first, second)
+    }
+
+    /// Polls both handshakes until neither is waiting.
+    fn run_pair(
+        first: &mut Handshake,
+        second: &mut Handshake,
+    ) -> (HandshakeStatus, HandshakeStatus) {
+        let started = Instant::now();
+        let mut first_status = HandshakeStatus::Waiting;
+        let mut second_status = HandshakeStatus::Waiting;
+
+        while started.elapsed() < TIMEOUT {
+            if let HandshakeStatus::Waiting = first_status {
+                first_status = first.poll();
+            }
+            if let HandshakeStatus::Waiting = second_status {
+                second_status = second.poll();
+            }
+
+            match (&first_status, &second_status) {
+                (HandshakeStatus::Waiting, _) | (_, HandshakeStatus::Waiting) => {
+                    std::thread::sleep(Duration::from_millis(10))
+                }
+                _ => return (first_status, second_status),
+            }
+        }
+
+        panic!("Handshake didn't finish");
+    }
+
+    fn agreed_seed(status: HandshakeStatus) -> u64 {
+        match status {
+            HandshakeStatus::Complete(message) => {
+                assert!(message.agreed);
+                message.seed
+            }
+            HandshakeStatus::Failed(e) => panic!("Handshake failed: {}", e),
+            HandshakeStatus::Waiting => panic!("Handshake is still waiting"),
+        }
+    }
+
+    fn failure(status: HandshakeStatus) -> String {
+        match status {
+            HandshakeStatus::Failed(e) => e,
+            _ => panic!("Handshake should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = HandshakeMessage {
+            agreed: true,
+            ..HandshakeMessage::new(0x0123_4567_89ab_cdef, u64::MAX - 1)
+        };
+
+        let bytes = message.to_bytes();
+        assert_eq!(HandshakeMessage::from_bytes(&bytes), Some(message));
+        assert_eq!(HandshakeMessage::from_bytes(&bytes[1..]), None);
+        assert_eq!(
+            HandshakeMessage::from_bytes(&[0; HandshakeMessage::SIZE + 1]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_seed_agreement() {
+        let (mut first, mut second) =
+            start_pair(HandshakeMessage::new(1, 100), HandshakeMessage::new(1, 200));
+
+        let (first_status, second_status) = run_pair(&mut first, &mut second);
+
+        // Both use the seed of the console with the first player
+        assert_eq!(agreed_seed(first_status), 100);
+        assert_eq!(agreed_seed(second_status), 100);
+    }
+
+    #[test]
+    fn test_rom_mismatch() {
+        let (mut first, mut second) =
+            start_pair(HandshakeMessage::new(1, 100), HandshakeMessage::new(2, 100));
+
+        let (first_status, second_status) = run_pair(&mut first, &mut second);
+
+        assert!(failure(first_status).contains("different game"));
+        assert!(failure(second_status).contains("different game"));
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let outdated = HandshakeMessage {
+            version: NETPLAY_PROTOCOL_VERSION + 1,
+            ..HandshakeMessage::new(1, 100)
+        };
+        let (mut first, mut second) = start_pair(HandshakeMessage::new(1, 100), outdated);
+
+        let (first_status, second_status) = run_pair(&mut first, &mut second);
+
+        assert!(failure(first_status).contains("netplay version"));
+        assert!(failure(second_status).contains("netplay version"));
+    }
+}
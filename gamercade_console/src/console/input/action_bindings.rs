@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use gamercade_core::{ActionState, ButtonCode, Buttons, InputAction, MAX_INPUT_ACTIONS};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use super::key_bindings::INPUT_PROFILE_DIR;
+use crate::console_config::user_config_dir;
+
+/// The console buttons which trigger each of the game's actions. Each game
+/// has its own profile, where the player's changes are stored by action name.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ActionBindings {
+    pub buttons: HashMap<String, Vec<ButtonCode>>,
+
+    /// The name of the game these bindings are for
+    #[serde(skip)]
+    game: String,
+
+    /// The actions declared by the game, in order of their ids
+    #[serde(skip)]
+    actions: Vec<InputAction>,
+}
+
+impl ActionBindings {
+    fn profile_path(game: &str) -> Option<PathBuf> {
+        user_config_dir(INPUT_PROFILE_DIR).map(|dir| dir.join(format!("actions_{}.json", game)))
+    }
+
+    /// Loads the profile for the game, using the default buttons for
+    /// any actions which the player hasn't changed.
+    pub fn load(game: &str, actions: &[InputAction]) -> Self {
+        let actions = actions
+            .iter()
+            .take(MAX_INPUT_ACTIONS)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut bindings = match Self::profile_path(game) {
+            Some(path) if path.exists() => match std::fs::read(&path) {
+                Ok(file) => match serde_json::from_slice::<Self>(&file) {
+                    Ok(bindings) => {
+                        println!("Successfully loaded action bindings from: {:?}", path);
+                        bindings
+                    }
+                    Err(e) => {
+                        println!("{:?} found, but unable to parse: {}", path, e);
+                        Self::default()
+                    }
+                },
+                Err(e) => {
+                    println!("{:?} found, but unable to read: {}", path, e);
+                    Self::default()
+                }
+            },
+            _ => Self::default(),
+        };
+
+        actions.iter().for_each(|action| {
+            bindings
+                .buttons
+                .entry(action.name.clone())
+                .or_insert_with(|| action.default_buttons.clone());
+        });
+
+        bindings.game = game.to_string();
+        bindings.actions = actions;
+        bindings
+    }
+
+    pub fn save(&self) {
+        let path = match Self::profile_path(&self.game) {
+            Some(path) => path,
+            None => return,
+        };
+        let json = serde_json::to_string_pretty(self).unwrap();
+
+        match std::fs::write(&path, json) {
+            Ok(()) => println!("Successfully saved action bindings to: {:?}", path),
+            Err(e) => println!("Error writing {:?}: {}", path, e),
+        };
+    }
+
+    pub fn actions(&self) -> &[InputAction] {
+        &self.actions
+    }
+
+    /// Returns the buttons bound to the action.
+    pub fn buttons_for<'a>(&'a self, action: &'a InputAction) -> &'a [ButtonCode] {
+        self.buttons
+            .get(&action.name)
+            .map(|buttons| buttons.as_slice())
+            .unwrap_or(&action.default_buttons)
+    }
+
+    /// Binds or unbinds the button from the action.
+    pub fn toggle(&mut self, action_id: usize, button: ButtonCode) {
+        if let Some(action) = self.actions.get(action_id) {
+            let buttons = self
+                .buttons
+                .entry(action.name.clone())
+                .or_insert_with(|| action.default_buttons.clone());
+
+            if buttons.contains(&button) {
+                buttons.retain(|bound| *bound != button);
+            } else {
+                buttons.push(button);
+            }
+        }
+    }
+
+    /// Resets all of the game's actions back to their default buttons.
+    pub fn reset(&mut self) {
+        self.actions.iter().for_each(|action| {
+            self.buttons
+                .insert(action.name.clone(), action.default_buttons.clone());
+        });
+    }
+
+    /// Returns the names of the actions triggered by the button.
+    pub fn action_names_for(&self, button: ButtonCode) -> Vec<&str> {
+        self.actions
+            .iter()
+            .filter(|action| self.buttons_for(action).contains(&button))
+            .map(|action| action.name.as_str())
+            .collect()
+    }
+
+    /// An action is held while any of its buttons are held.
+    pub fn generate_action_state(&self, buttons: Buttons) -> ActionState {
+        let mut out = ActionState::default();
+
+        self.actions
+            .iter()
+            .enumerate()
+            .for_each(|(action_id, action)| {
+                let held = self
+                    .buttons_for(action)
+                    .iter()
+                    .any(|button| buttons.get_button_state(*button));
+                out.set_action(action_id, held);
+            });
+
+        out
+    }
+}
@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum DeadzoneShape {
+    /// Ignores the stick until its distance from the center passes the deadzone.
+    Radial,
+    /// Ignores each axis separately, which makes it easier to hold a straight direction.
+    Axial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum ResponseCurve {
+    Linear,
+    Quadratic,
+    Cubic,
+}
+
+impl ResponseCurve {
+    pub(crate) const ALL: [ResponseCurve; 3] = [Self::Linear, Self::Quadratic, Self::Cubic];
+
+    /// Applies the curve to a value in the range [0, 1].
+    pub(crate) fn apply(self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Quadratic => value * value,
+            Self::Cubic => value * value * value,
+        }
+    }
+}
+
+/// Rescales a value in the range [0, 1] so that anything inside of the
+/// deadzone is zero, and the remaining range starts back at zero.
+fn rescale(value: f32, deadzone: f32) -> f32 {
+    if value <= deadzone {
+        0.0
+    } else {
+        ((value - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct StickSettings {
+    pub deadzone: f32,
+    pub deadzone_shape: DeadzoneShape,
+    pub curve: ResponseCurve,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.1,
+            deadzone_shape: DeadzoneShape::Radial,
+            curve: ResponseCurve::Linear,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+impl StickSettings {
+    pub const MAX_DEADZONE: f32 = 0.9;
+
+    /// Converts the raw axis values reported by the device into
+    /// the values passed to the game, each in the range [-1, 1].
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let x = x.clamp(-1.0, 1.0);
+        let y = y.clamp(-1.0, 1.0);
+        let deadzone = self.deadzone.clamp(0.0, Self::MAX_DEADZONE);
+
+        let (x, y) = match self.deadzone_shape {
+            DeadzoneShape::Radial => {
+                let magnitude = x.hypot(y);
+                if magnitude <= deadzone {
+                    (0.0, 0.0)
+                } else {
+                    let scale = self.curve.apply(rescale(magnitude, deadzone)) / magnitude;
+                    (x * scale, y * scale)
+                }
+            }
+            DeadzoneShape::Axial => {
+                let axis =
+                    |value: f32| value.signum() * self.curve.apply(rescale(value.abs(), deadzone));
+                (axis(x), axis(y))
+            }
+        };
+
+        let x = if self.invert_x { -x } else { x };
+        let y = if self.invert_y { -y } else { y };
+
+        (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TriggerSettings {
+    pub deadzone: f32,
+    pub curve: ResponseCurve,
+}
+
+impl Default for TriggerSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.05,
+            curve: ResponseCurve::Linear,
+        }
+    }
+}
+
+impl TriggerSettings {
+    pub const MAX_DEADZONE: f32 = 0.9;
+
+    /// Converts the raw trigger value into the range [0, 1].
+    pub fn apply(&self, value: f32) -> f32 {
+        let deadzone = self.deadzone.clamp(0.0, Self::MAX_DEADZONE);
+        self.curve.apply(rescale(value.clamp(0.0, 1.0), deadzone))
+    }
+}
+
+/// Per device settings used to clean up the analog inputs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AnalogSettings {
+    pub left_stick: StickSettings,
+    pub right_stick: StickSettings,
+    pub triggers: TriggerSettings,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stick(deadzone: f32, deadzone_shape: DeadzoneShape, curve: ResponseCurve) -> StickSettings {
+        StickSettings {
+            deadzone,
+            deadzone_shape,
+            curve,
+            ..StickSettings::default()
+        }
+    }
+
+    fn assert_close((x, y): (f32, f32), (expected_x, expected_y): (f32, f32)) {
+        assert!(
+            (x - expected_x).abs() < 1e-5 && (y - expected_y).abs() < 1e-5,
+            "got ({}, {}), expected ({}, {})",
+            x,
+            y,
+            expected_x,
+            expected_y
+        );
+    }
+
+    #[test]
+    fn test_stick_apply() {
+        use DeadzoneShape::*;
+        use ResponseCurve::*;
+
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        let table = [
+            // Radial deadzones use the distance from the center
+            (stick(0.2, Radial, Linear), (0.1, 0.1), (0.0, 0.0)),
+            (stick(0.2, Radial, Linear), (0.6, 0.0), (0.5, 0.0)),
+            (stick(0.2, Radial, Linear), (0.36, 0.48), (0.3, 0.4)),
+            (
+                stick(0.2, Radial, Linear),
+                (0.1, 0.6),
+                (0.0839003, 0.5034015),
+            ),
+            (stick(0.2, Radial, Linear), (1.0, 0.0), (1.0, 0.0)),
+            // Axial deadzones treat each axis separately
+            (stick(0.2, Axial, Linear), (0.1, 0.6), (0.0, 0.5)),
+            (stick(0.2, Axial, Linear), (-0.6, -1.0), (-0.5, -1.0)),
+            // Curves
+            (stick(0.0, Radial, Quadratic), (0.5, 0.0), (0.25, 0.0)),
+            (stick(0.0, Radial, Cubic), (0.0, -0.5), (0.0, -0.125)),
+            (stick(0.0, Axial, Cubic), (-0.5, 0.5), (-0.125, 0.125)),
+            // Out of range values are clamped
+            (stick(0.2, Radial, Linear), (2.0, 0.0), (1.0, 0.0)),
+            (stick(0.0, Radial, Linear), (1.0, 1.0), (diagonal, diagonal)),
+            (stick(0.0, Axial, Linear), (-3.0, 3.0), (-1.0, 1.0)),
+            (stick(2.0, Axial, Linear), (0.95, 0.5), (0.5, 0.0)),
+            (stick(-1.0, Axial, Linear), (0.25, 0.0), (0.25, 0.0)),
+        ];
+
+        table.iter().for_each(|(settings, (x, y), expected)| {
+            assert_close(settings.apply(*x, *y), *expected)
+        });
+    }
+
+    #[test]
+    fn test_stick_invert() {
+        let mut settings = stick(0.0, DeadzoneShape::Radial, ResponseCurve::Linear);
+
+        settings.invert_x = true;
+        assert_close(settings.apply(0.5, 0.25), (-0.5, 0.25));
+
+        settings.invert_y = true;
+        assert_close(settings.apply(0.5, 0.25), (-0.5, -0.25));
+
+        settings.invert_x = false;
+        assert_close(settings.apply(0.5, 0.25), (0.5, -0.25));
+    }
+
+    #[test]
+    fn test_trigger_apply() {
+        use ResponseCurve::*;
+
+        let trigger = |deadzone, curve| TriggerSettings { deadzone, curve };
+        let table = [
+            (trigger(0.2, Linear), 0.1, 0.0),
+            (trigger(0.2, Linear), 0.2, 0.0),
+            (trigger(0.2, Linear), 0.6, 0.5),
+            (trigger(0.2, Linear), 1.0, 1.0),
+            (trigger(0.0, Quadratic), 0.5, 0.25),
+            (trigger(0.0, Cubic), 0.5, 0.125),
+            (trigger(0.2, Quadratic), 0.6, 0.25),
+            // Out of range values are clamped
+            (trigger(0.2, Linear), 1.5, 1.0),
+            (trigger(0.2, Linear), -0.5, 0.0),
+            (trigger(2.0, Linear), 0.95, 0.5),
+        ];
+
+        table.iter().for_each(|(settings, value, expected)| {
+            let result = settings.apply(*value);
+            assert!(
+                (result - expected).abs() < 1e-5,
+                "got {}, expected {}",
+                result,
+                expected
+            );
+        });
+    }
+}
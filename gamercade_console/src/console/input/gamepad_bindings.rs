@@ -1,33 +1,130 @@
+use std::path::PathBuf;
+
 use gamercade_core::ButtonCode;
-use gilrs::Button;
+use gilrs::{Button, Gamepad};
 use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    analog_settings::AnalogSettings,
+    key_bindings::INPUT_PROFILE_DIR,
+    key_types::{KeyType, TriggerSide},
+};
+use crate::console_config::user_config_dir;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct GamepadBindings {
-    pub buttons: HashMap<Button, ButtonCode>,
+    pub buttons: HashMap<Button, KeyType>,
+    pub analog: AnalogSettings,
+}
+
+/// Returns the GUID of the gamepad as a hex string, which is used
+/// to store a separate profile for each kind of device.
+pub(crate) fn gamepad_guid(gamepad: &Gamepad) -> String {
+    gamepad
+        .uuid()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl GamepadBindings {
+    fn profile_path(guid: &str) -> Option<PathBuf> {
+        user_config_dir(INPUT_PROFILE_DIR).map(|dir| dir.join(format!("gamepad_{}.json", guid)))
+    }
+
+    /// Loads the profile for the gamepad with the passed in GUID,
+    /// or the default bindings if none exists.
+    pub fn load(guid: &str) -> Self {
+        let path = match Self::profile_path(guid) {
+            Some(path) if path.exists() => path,
+            _ => return Self::default(),
+        };
+
+        match std::fs::read(&path) {
+            Ok(file) => match serde_json::from_slice::<Self>(&file) {
+                Ok(bindings) => {
+                    println!("Successfully loaded gamepad bindings from: {:?}", path);
+                    return bindings;
+                }
+                Err(e) => println!("{:?} found, but unable to parse: {}", path, e),
+            },
+            Err(e) => println!("{:?} found, but unable to read: {}", path, e),
+        }
+
+        println!("Using default gamepad bindings.");
+        Self::default()
+    }
+
+    pub fn save(&self, guid: &str) {
+        let path = match Self::profile_path(guid) {
+            Some(path) => path,
+            None => return,
+        };
+        let json = serde_json::to_string_pretty(self).unwrap();
+
+        match std::fs::write(&path, json) {
+            Ok(()) => println!("Successfully saved gamepad bindings to: {:?}", path),
+            Err(e) => println!("Error writing {:?}: {}", path, e),
+        };
+    }
+
+    /// Binds the button to the input. Any other buttons
+    /// bound to the same input are unbound.
+    pub fn rebind(&mut self, button: Button, input: KeyType) {
+        self.buttons.retain(|_, bound| *bound != input);
+        self.buttons.insert(button, input);
+    }
+
+    /// Returns all buttons bound to the input.
+    pub fn buttons_for(&self, input: KeyType) -> Vec<Button> {
+        self.buttons
+            .iter()
+            .filter(|(_, bound)| **bound == input)
+            .map(|(button, _)| *button)
+            .collect()
+    }
 }
 
 impl Default for GamepadBindings {
     fn default() -> Self {
         let buttons = [
-            (Button::DPadUp, ButtonCode::Up),
-            (Button::DPadDown, ButtonCode::Down),
-            (Button::DPadLeft, ButtonCode::Left),
-            (Button::DPadRight, ButtonCode::Right),
-            (Button::East, ButtonCode::ButtonA),
-            (Button::South, ButtonCode::ButtonB),
-            (Button::West, ButtonCode::ButtonC),
-            (Button::North, ButtonCode::ButtonD),
-            (Button::Start, ButtonCode::Start),
-            (Button::Select, ButtonCode::Select),
-            (Button::LeftTrigger, ButtonCode::LeftShoulder),
-            (Button::RightTrigger, ButtonCode::RightShoulder),
-            (Button::LeftThumb, ButtonCode::LeftStick),
-            (Button::RightThumb, ButtonCode::RightStick),
+            (Button::DPadUp, KeyType::Button(ButtonCode::Up)),
+            (Button::DPadDown, KeyType::Button(ButtonCode::Down)),
+            (Button::DPadLeft, KeyType::Button(ButtonCode::Left)),
+            (Button::DPadRight, KeyType::Button(ButtonCode::Right)),
+            (Button::East, KeyType::Button(ButtonCode::ButtonA)),
+            (Button::South, KeyType::Button(ButtonCode::ButtonB)),
+            (Button::West, KeyType::Button(ButtonCode::ButtonC)),
+            (Button::North, KeyType::Button(ButtonCode::ButtonD)),
+            (Button::Start, KeyType::Button(ButtonCode::Start)),
+            (Button::Select, KeyType::Button(ButtonCode::Select)),
+            (
+                Button::LeftTrigger,
+                KeyType::Button(ButtonCode::LeftShoulder),
+            ),
+            (
+                Button::RightTrigger,
+                KeyType::Button(ButtonCode::RightShoulder),
+            ),
+            (Button::LeftThumb, KeyType::Button(ButtonCode::LeftStick)),
+            (Button::RightThumb, KeyType::Button(ButtonCode::RightStick)),
+            (
+                Button::LeftTrigger2,
+                KeyType::Trigger(TriggerSide::LeftTrigger),
+            ),
+            (
+                Button::RightTrigger2,
+                KeyType::Trigger(TriggerSide::RightTrigger),
+            ),
         ]
         .into_iter()
         .collect();
 
-        Self { buttons }
+        Self {
+            buttons,
+            analog: AnalogSettings::default(),
+        }
     }
 }
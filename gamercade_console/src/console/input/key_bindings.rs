@@ -6,51 +6,148 @@ use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 use winit::event::VirtualKeyCode;
 
-use super::key_types::{AnalogStick, KeyType, TriggerSide};
+use super::{
+    key_types::{AnalogStick, KeyType, TriggerSide},
+    keyboard_analog::KeyboardAnalogSettings,
+};
+use crate::console_config::user_config_dir;
 
+pub(crate) const INPUT_PROFILE_DIR: &str = "input";
 const INPUT_FILE_NAME: &str = "keyboardInput.json";
 
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(transparent)]
+#[serde(default)]
 pub(crate) struct KeyBindings {
     pub buttons: Vec<HashMap<VirtualKeyCode, KeyType>>,
+    pub analog: Vec<KeyboardAnalogSettings>,
 }
 
 impl KeyBindings {
+    /// Returns the path of the keyboard profile, inside of the user config directory.
+    /// Falls back to the working directory if the config directory is unavailable.
+    fn profile_path() -> PathBuf {
+        user_config_dir(INPUT_PROFILE_DIR)
+            .map(|dir| dir.join(INPUT_FILE_NAME))
+            .unwrap_or_else(|| PathBuf::from(INPUT_FILE_NAME))
+    }
+
     pub fn load() -> Self {
-        let path = PathBuf::from(INPUT_FILE_NAME);
+        let mut path = Self::profile_path();
+
+        // Older versions stored the bindings in the working directory
+        let legacy_path = PathBuf::from(INPUT_FILE_NAME);
+        if !path.exists() && legacy_path.exists() {
+            path = legacy_path;
+        }
+
         if path.exists() {
-            match std::fs::read(INPUT_FILE_NAME) {
-                Ok(file) => match serde_json::from_slice::<Self>(&file) {
+            match std::fs::read(&path) {
+                Ok(file) => match serde_json::from_slice::<Self>(&file).or_else(|e| {
+                    // Older versions only stored the list of buttons
+                    serde_json::from_slice(&file)
+                        .map(|buttons| Self {
+                            buttons,
+                            ..Self::default()
+                        })
+                        .map_err(|_| e)
+                }) {
                     Ok(key_bindings) => {
-                        println!("Successfully loaded key bindings from: {}", INPUT_FILE_NAME);
+                        println!("Successfully loaded key bindings from: {:?}", path);
                         return key_bindings;
                     }
                     Err(e) => {
-                        println!("{} found, but unable to parse: {}", INPUT_FILE_NAME, e);
+                        println!("{:?} found, but unable to parse: {}", path, e);
                     }
                 },
-                Err(e) => println!("{} found, but unable to read: {}", INPUT_FILE_NAME, e),
+                Err(e) => println!("{:?} found, but unable to read: {}", path, e),
             };
 
             println!("Using default config.");
             Self::default()
         } else {
-            println!(
-                "{} not found. Generating default input file.",
-                INPUT_FILE_NAME
-            );
+            println!("{:?} not found. Generating default input file.", path);
             let bindings = Self::default();
+            bindings.save();
+            bindings
+        }
+    }
 
-            let json = serde_json::to_string_pretty(&bindings).unwrap();
+    pub fn save(&self) {
+        let path = Self::profile_path();
+        let json = serde_json::to_string_pretty(self).unwrap();
 
-            match std::fs::write(path, json) {
-                Ok(()) => println!("Successfully generated default {}", INPUT_FILE_NAME),
-                Err(e) => println!("Error writing {}: {}", INPUT_FILE_NAME, e),
-            };
+        match std::fs::write(&path, json) {
+            Ok(()) => println!("Successfully saved key bindings to: {:?}", path),
+            Err(e) => println!("Error writing {:?}: {}", path, e),
+        };
+    }
 
-            bindings
+    /// Binds the key to the input for the given keyboard. Any other keys
+    /// bound to the same input are unbound.
+    pub fn rebind(&mut self, keyboard_index: usize, key: VirtualKeyCode, input: KeyType) {
+        if let Some(buttons) = self.buttons.get_mut(keyboard_index) {
+            let replaced = buttons
+                .iter()
+                .filter(|(_, bound)| **bound == input)
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>();
+            buttons.retain(|_, bound| *bound != input);
+            buttons.insert(key, input);
+
+            // Keep the magnitude of the replaced key
+            let settings = self.analog_settings_mut(keyboard_index);
+            if let Some(magnitude) = replaced
+                .iter()
+                .find_map(|key| settings.magnitudes.remove(key))
+            {
+                settings.magnitudes.insert(key, magnitude);
+            }
+        }
+    }
+
+    /// Resets the given keyboard to the default bindings. Keys already bound
+    /// on another keyboard are left unbound, so the keyboards don't clash.
+    pub fn reset(&mut self, keyboard_index: usize) {
+        if keyboard_index >= self.buttons.len() {
+            return;
+        }
+
+        let mut defaults = Self::default().buttons.remove(0);
+        self.buttons
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != keyboard_index)
+            .for_each(|(_, other)| defaults.retain(|key, _| !other.contains_key(key)));
+
+        self.buttons[keyboard_index] = defaults;
+        *self.analog_settings_mut(keyboard_index) = KeyboardAnalogSettings::default();
+    }
+
+    /// Returns the analog emulation settings for the given keyboard.
+    pub fn analog_settings(&self, keyboard_index: usize) -> Option<&KeyboardAnalogSettings> {
+        self.analog.get(keyboard_index)
+    }
+
+    pub fn analog_settings_mut(&mut self, keyboard_index: usize) -> &mut KeyboardAnalogSettings {
+        if self.analog.len() <= keyboard_index {
+            self.analog
+                .resize(keyboard_index + 1, KeyboardAnalogSettings::default());
         }
+        &mut self.analog[keyboard_index]
+    }
+
+    /// Returns all keys bound to the input for the given keyboard.
+    pub fn keys_for(&self, keyboard_index: usize, input: KeyType) -> Vec<VirtualKeyCode> {
+        self.buttons
+            .get(keyboard_index)
+            .map(|buttons| {
+                buttons
+                    .iter()
+                    .filter(|(_, bound)| **bound == input)
+                    .map(|(key, _)| *key)
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 }
 
@@ -124,6 +221,50 @@ impl Default for KeyBindings {
         .into_iter()
         .collect::<HashMap<VirtualKeyCode, KeyType>>()];
 
-        Self { buttons }
+        Self {
+            buttons,
+            analog: vec![KeyboardAnalogSettings::default()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset_first_keyboard() {
+        let mut bindings = KeyBindings::default();
+        bindings.buttons[0].clear();
+
+        bindings.reset(0);
+        assert_eq!(bindings.buttons[0], KeyBindings::default().buttons[0]);
+    }
+
+    #[test]
+    fn test_reset_doesnt_clash() {
+        let mut bindings = KeyBindings::default();
+        bindings.buttons.push(HashMap::from([(
+            VirtualKeyCode::Numpad8,
+            KeyType::Button(ButtonCode::Up),
+        )]));
+
+        // Every default key is already used by the first keyboard
+        bindings.reset(1);
+        assert!(bindings.buttons[1].is_empty());
+        assert_eq!(bindings.buttons[0], KeyBindings::default().buttons[0]);
+
+        // Only the keys the other keyboard doesn't use are given back
+        bindings.buttons[1].insert(VirtualKeyCode::W, KeyType::Button(ButtonCode::Up));
+        bindings.reset(0);
+        assert_eq!(
+            bindings.buttons[0].len(),
+            KeyBindings::default().buttons[0].len() - 1
+        );
+        assert!(!bindings.buttons[0].contains_key(&VirtualKeyCode::W));
+        assert_eq!(
+            bindings.buttons[1].get(&VirtualKeyCode::W),
+            Some(&KeyType::Button(ButtonCode::Up))
+        );
     }
 }
@@ -1,7 +1,7 @@
 use gamercade_core::ButtonCode;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(untagged)]
 pub(crate) enum KeyType {
     Button(ButtonCode),
@@ -9,7 +9,55 @@ pub(crate) enum KeyType {
     Trigger(TriggerSide),
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+impl KeyType {
+    /// Every input which can be bound to a key or gamepad button.
+    pub(crate) const ALL: [KeyType; 24] = [
+        KeyType::Button(ButtonCode::Up),
+        KeyType::Button(ButtonCode::Down),
+        KeyType::Button(ButtonCode::Left),
+        KeyType::Button(ButtonCode::Right),
+        KeyType::Button(ButtonCode::ButtonA),
+        KeyType::Button(ButtonCode::ButtonB),
+        KeyType::Button(ButtonCode::ButtonC),
+        KeyType::Button(ButtonCode::ButtonD),
+        KeyType::Button(ButtonCode::Start),
+        KeyType::Button(ButtonCode::Select),
+        KeyType::Button(ButtonCode::LeftShoulder),
+        KeyType::Button(ButtonCode::RightShoulder),
+        KeyType::Button(ButtonCode::LeftStick),
+        KeyType::Button(ButtonCode::RightStick),
+        KeyType::Trigger(TriggerSide::LeftTrigger),
+        KeyType::Trigger(TriggerSide::RightTrigger),
+        KeyType::AnalogStick(AnalogStick::LeftYPositive),
+        KeyType::AnalogStick(AnalogStick::LeftYNegative),
+        KeyType::AnalogStick(AnalogStick::LeftXNegative),
+        KeyType::AnalogStick(AnalogStick::LeftXPositive),
+        KeyType::AnalogStick(AnalogStick::RightYPositive),
+        KeyType::AnalogStick(AnalogStick::RightYNegative),
+        KeyType::AnalogStick(AnalogStick::RightXNegative),
+        KeyType::AnalogStick(AnalogStick::RightXPositive),
+    ];
+
+    /// Returns the console button pressed by this input, if any.
+    pub(crate) fn button_code(&self) -> Option<ButtonCode> {
+        match self {
+            KeyType::Button(code) => Some(*code),
+            KeyType::Trigger(TriggerSide::LeftTrigger) => Some(ButtonCode::LeftTrigger),
+            KeyType::Trigger(TriggerSide::RightTrigger) => Some(ButtonCode::RightTrigger),
+            KeyType::AnalogStick(_) => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> String {
+        match self {
+            KeyType::Button(code) => format!("{:?}", code),
+            KeyType::AnalogStick(stick) => format!("{:?}", stick),
+            KeyType::Trigger(side) => format!("{:?}", side),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub(crate) enum AnalogStick {
     LeftXPositive,
     LeftXNegative,
@@ -21,7 +69,7 @@ pub(crate) enum AnalogStick {
     RightYNegative,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub(crate) enum TriggerSide {
     LeftTrigger,
     RightTrigger,
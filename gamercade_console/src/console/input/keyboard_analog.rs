@@ -0,0 +1,148 @@
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
+
+use super::key_types::{AnalogStick, TriggerSide};
+
+/// Per keyboard settings used to emulate analog inputs with keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct KeyboardAnalogSettings {
+    /// How far each key deflects its stick axis or trigger, in the range [0, 1].
+    /// Keys without an entry use full deflection.
+    pub magnitudes: HashMap<VirtualKeyCode, f32>,
+
+    /// While held, scales all deflection by the walk scale.
+    pub walk_modifier: Option<VirtualKeyCode>,
+    pub walk_scale: f32,
+
+    /// Seconds taken to go from rest to full deflection.
+    pub attack_time: f32,
+
+    /// Seconds taken to go from full deflection back to rest.
+    pub release_time: f32,
+
+    /// Prevents diagonals from deflecting the stick further than a single direction.
+    pub normalize_diagonals: bool,
+}
+
+impl Default for KeyboardAnalogSettings {
+    fn default() -> Self {
+        Self {
+            magnitudes: HashMap::new(),
+            walk_modifier: Some(VirtualKeyCode::LShift),
+            walk_scale: 0.5,
+            attack_time: 0.1,
+            release_time: 0.05,
+            normalize_diagonals: true,
+        }
+    }
+}
+
+impl KeyboardAnalogSettings {
+    pub const MAX_RAMP_TIME: f32 = 1.0;
+
+    pub fn magnitude(&self, key: VirtualKeyCode) -> f32 {
+        self.magnitudes
+            .get(&key)
+            .copied()
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0)
+    }
+
+    /// Moves the current value towards the target, at the attack rate when
+    /// moving away from rest, or the release rate otherwise.
+    fn ramp(&self, current: f32, target: f32, frame_time: f32) -> f32 {
+        let attacking = target.abs() > current.abs() && current * target >= 0.0;
+        let time = if attacking {
+            self.attack_time
+        } else {
+            self.release_time
+        };
+
+        if time <= 0.0 {
+            return target;
+        }
+
+        let step = frame_time / time;
+        if current < target {
+            (current + step).min(target)
+        } else {
+            (current - step).max(target)
+        }
+    }
+}
+
+/// The deflection of the emulated sticks and triggers, each in the range [-1, 1]
+/// for sticks or [0, 1] for triggers.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct EmulatedAnalog {
+    pub left_stick: (f32, f32),
+    pub right_stick: (f32, f32),
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+}
+
+impl EmulatedAnalog {
+    /// Adds the key's deflection to the stick. Opposite directions cancel each other out.
+    pub fn push_stick(&mut self, direction: AnalogStick, magnitude: f32) {
+        let (axis, value) = match direction {
+            AnalogStick::LeftXPositive => (&mut self.left_stick.0, magnitude),
+            AnalogStick::LeftXNegative => (&mut self.left_stick.0, -magnitude),
+            AnalogStick::LeftYPositive => (&mut self.left_stick.1, magnitude),
+            AnalogStick::LeftYNegative => (&mut self.left_stick.1, -magnitude),
+            AnalogStick::RightXPositive => (&mut self.right_stick.0, magnitude),
+            AnalogStick::RightXNegative => (&mut self.right_stick.0, -magnitude),
+            AnalogStick::RightYPositive => (&mut self.right_stick.1, magnitude),
+            AnalogStick::RightYNegative => (&mut self.right_stick.1, -magnitude),
+        };
+
+        *axis = (*axis + value).clamp(-1.0, 1.0);
+    }
+
+    pub fn push_trigger(&mut self, side: TriggerSide, magnitude: f32) {
+        let trigger = match side {
+            TriggerSide::LeftTrigger => &mut self.left_trigger,
+            TriggerSide::RightTrigger => &mut self.right_trigger,
+        };
+
+        *trigger = trigger.max(magnitude);
+    }
+
+    pub fn scale(&mut self, scale: f32) {
+        self.left_stick.0 *= scale;
+        self.left_stick.1 *= scale;
+        self.right_stick.0 *= scale;
+        self.right_stick.1 *= scale;
+        self.left_trigger *= scale;
+        self.right_trigger *= scale;
+    }
+
+    /// Scales diagonals down so they are as long as the largest single axis.
+    pub fn normalize_diagonals(&mut self) {
+        [&mut self.left_stick, &mut self.right_stick]
+            .into_iter()
+            .for_each(|(x, y)| {
+                let length = x.hypot(*y);
+                if length > 0.0 {
+                    let scale = x.abs().max(y.abs()) / length;
+                    *x *= scale;
+                    *y *= scale;
+                }
+            });
+    }
+
+    /// Ramps the current deflection towards the target.
+    pub fn step(&mut self, target: &Self, settings: &KeyboardAnalogSettings, frame_time: f32) {
+        let ramp = |current: &mut f32, target: f32| {
+            *current = settings.ramp(*current, target, frame_time);
+        };
+
+        ramp(&mut self.left_stick.0, target.left_stick.0);
+        ramp(&mut self.left_stick.1, target.left_stick.1);
+        ramp(&mut self.right_stick.0, target.right_stick.0);
+        ramp(&mut self.right_stick.1, target.right_stick.1);
+        ramp(&mut self.left_trigger, target.left_trigger);
+        ramp(&mut self.right_trigger, target.right_trigger);
+    }
+}
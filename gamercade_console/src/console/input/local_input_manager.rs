@@ -1,75 +1,439 @@
-use gamercade_core::{AnalogStick as AS, ButtonCode, InputState, MouseState};
-use gilrs::{Axis, Button, Gamepad, GamepadId, Gilrs};
+use gamercade_core::{
+    AnalogStick as AS, ButtonCode, ControllerStatus, InputAction, InputState, MouseMotion,
+    MouseState,
+};
+use gilrs::{Axis, Button, Event, EventType, Gamepad, GamepadId, Gilrs};
+use hashbrown::HashMap;
 use pixels::Pixels;
+use winit::event::{MouseScrollDelta, VirtualKeyCode};
 use winit_input_helper::WinitInputHelper;
 
 use crate::console::network::NetworkInputState;
 
 use super::{
-    gamepad_bindings::GamepadBindings,
+    action_bindings::ActionBindings,
+    analog_settings::AnalogSettings,
+    gamepad_bindings::{gamepad_guid, GamepadBindings},
     key_types::{AnalogStick, KeyType, TriggerSide},
+    keyboard_analog::{EmulatedAnalog, KeyboardAnalogSettings},
+    rumble_output::RumbleOutput,
+    touch_collector::TouchCollector,
     InputMode, KeyBindings, LocalKeyboardId, LocalPlayerId,
 };
 
 #[derive(Default)]
 pub struct MouseEventCollector {
-    pub wheel_up: bool,
-    pub wheel_down: bool,
-    pub wheel_left: bool,
-    pub wheel_right: bool,
-    pub delta_x: i16,
-    pub delta_y: i16,
+    /// Raw mouse motion, in device units
+    pub delta_x: f64,
+    pub delta_y: f64,
+
+    /// Wheel steps, positive values are up and to the right
+    pub wheel_x: f64,
+    pub wheel_y: f64,
+}
+
+impl MouseEventCollector {
+    /// How many pixels a touchpad needs to scroll to count as a single wheel step.
+    const PIXELS_PER_WHEEL_STEP: f64 = 20.0;
+
+    pub fn handle_motion(&mut self, delta: (f64, f64)) {
+        self.delta_x += delta.0;
+        self.delta_y += delta.1;
+    }
+
+    pub fn handle_wheel(&mut self, delta: MouseScrollDelta) {
+        match delta {
+            MouseScrollDelta::LineDelta(x, y) => {
+                self.wheel_x += x as f64;
+                self.wheel_y += y as f64;
+            }
+            MouseScrollDelta::PixelDelta(d) => {
+                self.wheel_x += d.x / Self::PIXELS_PER_WHEEL_STEP;
+                self.wheel_y += d.y / Self::PIXELS_PER_WHEEL_STEP;
+            }
+        }
+    }
+
+    /// Takes the events collected for a single frame. Anything too small to be
+    /// sent, like partial wheel steps, is kept and added to the next frame.
+    pub fn take_frame(&mut self) -> Self {
+        let frame = Self {
+            delta_x: MouseMotion::quantize(self.delta_x),
+            delta_y: MouseMotion::quantize(self.delta_y),
+            wheel_x: self.wheel_x.trunc(),
+            wheel_y: self.wheel_y.trunc(),
+        };
+
+        self.delta_x -= frame.delta_x;
+        self.delta_y -= frame.delta_y;
+        self.wheel_x -= frame.wheel_x;
+        self.wheel_y -= frame.wheel_y;
+
+        frame
+    }
+}
+
+/// Collects the characters typed during a single frame, for games using text input.
+#[derive(Default, Debug, Clone)]
+pub struct TextInputCollector {
+    pub text: String,
+    pub backspaces: u32,
+    pub enter: bool,
+}
+
+impl TextInputCollector {
+    /// The most UTF-8 bytes of text which can be typed in a single frame.
+    /// Any characters past this limit are dropped.
+    pub const MAX_BYTES: usize = 64;
+
+    pub fn push_char(&mut self, c: char) {
+        match c {
+            // Backspaces remove text typed this frame first
+            '\u{8}' => {
+                if self.text.pop().is_none() {
+                    self.backspaces += 1;
+                }
+            }
+            '\r' | '\n' => self.enter = true,
+            c if c.is_control() => (),
+            c => {
+                if self.text.len() + c.len_utf8() <= Self::MAX_BYTES {
+                    self.text.push(c);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct LocalInputManager {
     pub(crate) keyboard_bindings: KeyBindings,
-    gamepad_binds: GamepadBindings,
+    gamepad_binds: HashMap<String, GamepadBindings>,
+    default_gamepad_binds: GamepadBindings,
     pub(crate) player_bindings: Vec<InputMode>,
+
+    /// The buttons bound to the actions of the current game
+    pub(crate) action_bindings: ActionBindings,
+
+    /// The ramped analog values of players using a keyboard
+    emulated_analog: HashMap<LocalPlayerId, EmulatedAnalog>,
+
+    rumble: RumbleOutput,
 }
 
 impl LocalInputManager {
+    /// The most players which can play on a single console.
+    pub const MAX_LOCAL_PLAYERS: usize = 4;
+
     pub fn new() -> Self {
         Self {
             keyboard_bindings: KeyBindings::load(),
-            gamepad_binds: GamepadBindings::default(),
+            gamepad_binds: HashMap::new(),
+            default_gamepad_binds: GamepadBindings::default(),
             player_bindings: vec![InputMode::Emulated(LocalKeyboardId(0))],
+            action_bindings: ActionBindings::default(),
+            emulated_analog: HashMap::new(),
+            rumble: RumbleOutput::default(),
         }
     }
 
-    pub fn generate_input_state(
+    /// Loads the stored profile for the gamepad, if it hasn't been loaded yet.
+    pub fn load_gamepad_profile(&mut self, gamepad: &Gamepad) {
+        self.gamepad_binds
+            .entry(gamepad_guid(gamepad))
+            .or_insert_with_key(|guid| GamepadBindings::load(guid));
+    }
+
+    /// Loads the stored profiles for all currently connected gamepads.
+    pub fn load_gamepad_profiles(&mut self, gilrs: &Gilrs) {
+        gilrs
+            .gamepads()
+            .for_each(|(_, gamepad)| self.load_gamepad_profile(&gamepad));
+    }
+
+    /// Loads the player's bindings for the actions declared by the game.
+    pub(crate) fn load_actions(&mut self, game: &str, actions: &[InputAction]) {
+        self.action_bindings = ActionBindings::load(game, actions);
+    }
+
+    /// Handles connecting, disconnecting and joining gamepads. Newly connected gamepads
+    /// take over any player whose gamepad has disconnected, and gamepads which aren't
+    /// used by any player can press Start to join.
+    pub(crate) fn handle_gamepad_event(
+        &mut self,
+        event: &Event,
+        gilrs: &Gilrs,
+        can_add_players: bool,
+    ) {
+        match event.event {
+            EventType::Connected => {
+                let gamepad = gilrs.gamepad(event.id);
+                self.load_gamepad_profile(&gamepad);
+
+                if self.player_for_gamepad(event.id).is_none() {
+                    if let Some(player_id) = self.disconnected_players(gilrs).first() {
+                        self.player_bindings[*player_id] = InputMode::Gamepad(event.id);
+                        println!("{} assigned to player {}", gamepad.name(), player_id);
+                    }
+                }
+            }
+            EventType::Disconnected => {
+                if let Some(player_id) = self.player_for_gamepad(event.id) {
+                    println!("Player {}'s controller disconnected", player_id);
+                }
+            }
+            EventType::ButtonPressed(Button::Start, _)
+                if self.player_for_gamepad(event.id).is_none() =>
+            {
+                self.join(event.id, gilrs, can_add_players);
+            }
+            _ => (),
+        }
+    }
+
+    /// Assigns the gamepad to the first player whose gamepad has disconnected,
+    /// or to a new player if the player count can still be changed.
+    fn join(&mut self, gamepad_id: GamepadId, gilrs: &Gilrs, can_add_players: bool) {
+        let player_id = match self.disconnected_players(gilrs).first() {
+            Some(player_id) => {
+                self.player_bindings[*player_id] = InputMode::Gamepad(gamepad_id);
+                *player_id
+            }
+            None if can_add_players && self.player_bindings.len() < Self::MAX_LOCAL_PLAYERS => {
+                self.player_bindings.push(InputMode::Gamepad(gamepad_id));
+                self.player_bindings.len() - 1
+            }
+            None => return,
+        };
+
+        println!(
+            "{} joined as player {}",
+            gilrs.gamepad(gamepad_id).name(),
+            player_id
+        );
+    }
+
+    fn player_for_gamepad(&self, gamepad_id: GamepadId) -> Option<usize> {
+        self.player_bindings
+            .iter()
+            .position(|input_mode| *input_mode == InputMode::Gamepad(gamepad_id))
+    }
+
+    /// Returns the players whose gamepad is no longer connected.
+    pub(crate) fn disconnected_players(&self, gilrs: &Gilrs) -> Vec<usize> {
+        self.player_bindings
+            .iter()
+            .enumerate()
+            .filter_map(|(player_id, input_mode)| match input_mode {
+                InputMode::Gamepad(gamepad_id)
+                    if gilrs.connected_gamepad(*gamepad_id).is_none() =>
+                {
+                    Some(player_id)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Binds the key to the input and saves the keyboard profile.
+    pub(crate) fn rebind_key(
+        &mut self,
+        keyboard_id: LocalKeyboardId,
+        key: VirtualKeyCode,
+        input: KeyType,
+    ) {
+        self.keyboard_bindings.rebind(keyboard_id.0, key, input);
+        self.keyboard_bindings.save();
+    }
+
+    /// Binds the button to the input and saves the profile for the gamepad.
+    pub(crate) fn rebind_gamepad_button(
+        &mut self,
+        gamepad: &Gamepad,
+        button: Button,
+        input: KeyType,
+    ) {
+        let guid = gamepad_guid(gamepad);
+        let binds = self
+            .gamepad_binds
+            .entry(guid.clone())
+            .or_insert_with_key(|guid| GamepadBindings::load(guid));
+
+        binds.rebind(button, input);
+        binds.save(&guid);
+    }
+
+    /// Resets the bindings used by the input mode back to the defaults, and saves them.
+    pub(crate) fn reset_bindings(&mut self, input_mode: &InputMode, gilrs: &Gilrs) {
+        match input_mode {
+            InputMode::Emulated(keyboard_id) => {
+                self.keyboard_bindings.reset(keyboard_id.0);
+                self.keyboard_bindings.save();
+            }
+            InputMode::Gamepad(gamepad_id) => {
+                let guid = gamepad_guid(&gilrs.gamepad(*gamepad_id));
+                let binds = GamepadBindings::default();
+                binds.save(&guid);
+                self.gamepad_binds.insert(guid, binds);
+            }
+        }
+    }
+
+    /// Returns a readable list of the keys or buttons bound to the input.
+    pub(crate) fn bound_inputs_text(
         &self,
+        input_mode: &InputMode,
+        input: KeyType,
+        gilrs: &Gilrs,
+    ) -> String {
+        let bound = match input_mode {
+            InputMode::Emulated(keyboard_id) => self
+                .keyboard_bindings
+                .keys_for(keyboard_id.0, input)
+                .iter()
+                .map(|key| format!("{:?}", key))
+                .collect::<Vec<_>>(),
+            InputMode::Gamepad(gamepad_id) => self
+                .gamepad_bindings(&gilrs.gamepad(*gamepad_id))
+                .buttons_for(input)
+                .iter()
+                .map(|button| format!("{:?}", button))
+                .collect::<Vec<_>>(),
+        };
+
+        if bound.is_empty() {
+            "Unbound".to_string()
+        } else {
+            bound.join(", ")
+        }
+    }
+
+    /// Returns the name of the input, along with any of the game's actions it triggers.
+    pub(crate) fn input_label(&self, input: KeyType) -> String {
+        let actions = input
+            .button_code()
+            .map(|code| self.action_bindings.action_names_for(code))
+            .unwrap_or_default();
+
+        if actions.is_empty() {
+            input.name()
+        } else {
+            format!("{} ({})", input.name(), actions.join(", "))
+        }
+    }
+
+    /// Returns the analog settings for the gamepad, to be adjusted by the calibration view.
+    pub(crate) fn analog_settings_mut(&mut self, gamepad: &Gamepad) -> &mut AnalogSettings {
+        &mut self
+            .gamepad_binds
+            .entry(gamepad_guid(gamepad))
+            .or_insert_with_key(|guid| GamepadBindings::load(guid))
+            .analog
+    }
+
+    pub(crate) fn save_gamepad_profile(&self, gamepad: &Gamepad) {
+        let guid = gamepad_guid(gamepad);
+        if let Some(binds) = self.gamepad_binds.get(&guid) {
+            binds.save(&guid);
+        }
+    }
+
+    fn gamepad_bindings(&self, gamepad: &Gamepad) -> &GamepadBindings {
+        self.gamepad_binds
+            .get(&gamepad_guid(gamepad))
+            .unwrap_or(&self.default_gamepad_binds)
+    }
+
+    /// Plays the rumble requested by the game, if the player is using a gamepad.
+    pub(crate) fn sync_rumble(
+        &mut self,
+        local_player: LocalPlayerId,
+        strength: (f32, f32),
+        gilrs: &mut Gilrs,
+    ) {
+        let gamepad_id = match self.player_bindings.get(local_player.0) {
+            Some(InputMode::Gamepad(gamepad_id)) => Some(*gamepad_id),
+            _ => None,
+        };
+
+        self.rumble.sync(local_player, gamepad_id, strength, gilrs);
+    }
+
+    pub(crate) fn stop_rumble(&mut self) {
+        self.rumble.stop_all();
+    }
+
+    /// Saves the keyboard analog emulation settings, after being adjusted in the gui.
+    pub(crate) fn save_keyboard_profile(&self) {
+        self.keyboard_bindings.save();
+    }
+
+    /// Generates the input state for the local player. Frame time is the
+    /// length of a single game frame in seconds, used to ramp emulated analog inputs.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_input_state(
+        &mut self,
         local_player: LocalPlayerId,
         pixels: &Pixels,
         mouse_events: &MouseEventCollector,
+        touches: &TouchCollector,
         helper: &winit_input_helper::WinitInputHelper,
         gilrs: &Gilrs,
+        frame_time: f32,
     ) -> NetworkInputState {
+        let mut controller_status = ControllerStatus::default();
+
         let input_state = match self.player_bindings.get(local_player.0) {
-            Some(InputMode::Emulated(keyboard_id)) => self.new_emulated_state(*keyboard_id, helper),
-            Some(InputMode::Gamepad(gamepad_id)) => self.new_gamepad_state(*gamepad_id, gilrs),
+            Some(InputMode::Emulated(keyboard_id)) => {
+                let keyboard_id = *keyboard_id;
+                controller_status.set_connected(true);
+                self.new_emulated_state(local_player, keyboard_id, helper, frame_time)
+            }
+            Some(InputMode::Gamepad(gamepad_id)) => {
+                controller_status.set_connected(gilrs.connected_gamepad(*gamepad_id).is_some());
+                self.new_gamepad_state(*gamepad_id, gilrs)
+            }
             None => InputState::default(),
         };
 
+        let action_state = self
+            .action_bindings
+            .generate_action_state(input_state.buttons);
         let mouse_state = generate_mouse_state(pixels, mouse_events, helper);
+        let mut mouse_motion = MouseMotion::default();
+        mouse_motion.set_x(mouse_events.delta_x);
+        mouse_motion.set_y(mouse_events.delta_y);
+        let touch_state = touches.generate_touch_state(pixels);
 
         NetworkInputState {
             input_state,
             mouse_state,
+            mouse_motion,
+            touch_state,
+            controller_status,
+            action_state,
         }
     }
 
     fn new_emulated_state(
-        &self,
+        &mut self,
+        local_player: LocalPlayerId,
         keyboard_id: LocalKeyboardId,
         helper: &winit_input_helper::WinitInputHelper,
+        frame_time: f32,
     ) -> InputState {
-        generate_emulated_state(keyboard_id, &self.keyboard_bindings, helper)
+        generate_emulated_state(
+            keyboard_id,
+            &self.keyboard_bindings,
+            helper,
+            self.emulated_analog.entry(local_player).or_default(),
+            frame_time,
+        )
     }
 
     fn new_gamepad_state(&self, id: GamepadId, gilrs: &Gilrs) -> InputState {
         if let Some(gamepad) = gilrs.connected_gamepad(id) {
-            generate_gamepad_state(&self.gamepad_binds, &gamepad)
+            generate_gamepad_state(self.gamepad_bindings(&gamepad), &gamepad)
         } else {
             InputState::default()
         }
@@ -79,32 +443,64 @@ impl LocalInputManager {
 fn generate_gamepad_state(binds: &GamepadBindings, gamepad: &Gamepad) -> InputState {
     let mut output = InputState::default();
 
+    let axis_value = |axis| {
+        gamepad
+            .axis_data(axis)
+            .map(|data| data.value())
+            .unwrap_or_default()
+    };
+
+    let (x, y) = binds
+        .analog
+        .left_stick
+        .apply(axis_value(Axis::LeftStickX), axis_value(Axis::LeftStickY));
+    output.left_stick.set_x_axis(x);
+    output.left_stick.set_y_axis(y);
+
+    let (x, y) = binds
+        .analog
+        .right_stick
+        .apply(axis_value(Axis::RightStickX), axis_value(Axis::RightStickY));
+    output.right_stick.set_x_axis(x);
+    output.right_stick.set_y_axis(y);
+
+    // Buttons are handled after the axis, so that any buttons bound
+    // to stick directions take priority over the stick itself.
     binds.buttons.iter().for_each(|(button, input)| {
-        if gamepad.is_pressed(*button) {
-            output.buttons.enable_button(*input);
-        }
-    });
+        let pressed = gamepad.is_pressed(*button);
 
-    if let Some(axis) = gamepad.axis_data(Axis::LeftStickX) {
-        output.left_stick.set_x_axis(axis.value())
-    }
-    if let Some(axis) = gamepad.axis_data(Axis::LeftStickY) {
-        output.left_stick.set_y_axis(axis.value())
-    }
+        match input {
+            KeyType::Button(code) => {
+                if pressed {
+                    output.buttons.enable_button(*code)
+                }
+            }
+            KeyType::AnalogStick(analog_stick) => {
+                if pressed {
+                    adjust_input_state(analog_stick, &mut output)
+                }
+            }
+            KeyType::Trigger(side) => {
+                // Keep the analog value, in case the button is an analog trigger
+                let value = gamepad
+                    .button_data(*button)
+                    .map(|data| binds.analog.triggers.apply(data.value()))
+                    .unwrap_or_default();
+                let (trigger, code) = match side {
+                    TriggerSide::LeftTrigger => (&mut output.left_trigger, ButtonCode::LeftTrigger),
+                    TriggerSide::RightTrigger => {
+                        (&mut output.right_trigger, ButtonCode::RightTrigger)
+                    }
+                };
 
-    if let Some(axis) = gamepad.axis_data(Axis::RightStickX) {
-        output.right_stick.set_x_axis(axis.value())
-    }
-    if let Some(axis) = gamepad.axis_data(Axis::RightStickY) {
-        output.right_stick.set_y_axis(axis.value())
-    }
+                trigger.set_value(value.max(trigger.get_value()));
 
-    if let Some(trigger) = gamepad.button_data(Button::LeftTrigger2) {
-        output.left_trigger.set_value(trigger.value())
-    }
-    if let Some(trigger) = gamepad.button_data(Button::RightTrigger2) {
-        output.right_trigger.set_value(trigger.value())
-    }
+                if pressed {
+                    output.buttons.enable_button(code);
+                }
+            }
+        }
+    });
 
     output
 }
@@ -113,8 +509,20 @@ fn generate_emulated_state(
     player_id: LocalKeyboardId,
     binds: &KeyBindings,
     input_helper: &winit_input_helper::WinitInputHelper,
+    analog: &mut EmulatedAnalog,
+    frame_time: f32,
 ) -> InputState {
     let mut output = InputState::default();
+    let mut target = EmulatedAnalog::default();
+
+    let default_settings;
+    let settings = match binds.analog_settings(player_id.0) {
+        Some(settings) => settings,
+        None => {
+            default_settings = KeyboardAnalogSettings::default();
+            &default_settings
+        }
+    };
 
     if let Some(buttons) = binds.buttons.get(player_id.0) {
         buttons.iter().for_each(|(code, input)| {
@@ -122,23 +530,40 @@ fn generate_emulated_state(
                 match input {
                     KeyType::Button(code) => output.buttons.enable_button(*code),
                     KeyType::AnalogStick(analog_stick) => {
-                        adjust_input_state(analog_stick, &mut output)
+                        target.push_stick(*analog_stick, settings.magnitude(*code))
+                    }
+                    KeyType::Trigger(side) => {
+                        let button = match side {
+                            TriggerSide::LeftTrigger => ButtonCode::LeftTrigger,
+                            TriggerSide::RightTrigger => ButtonCode::RightTrigger,
+                        };
+                        output.buttons.enable_button(button);
+                        target.push_trigger(*side, settings.magnitude(*code));
                     }
-                    KeyType::Trigger(side) => match side {
-                        TriggerSide::LeftTrigger => {
-                            output.buttons.enable_button(ButtonCode::LeftTrigger);
-                            output.left_trigger.set_value(1.0);
-                        }
-                        TriggerSide::RightTrigger => {
-                            output.buttons.enable_button(ButtonCode::RightTrigger);
-                            output.right_trigger.set_value(1.0)
-                        }
-                    },
                 }
             }
         });
     }
 
+    if let Some(walk_modifier) = settings.walk_modifier {
+        if input_helper.key_held(walk_modifier) {
+            target.scale(settings.walk_scale.clamp(0.0, 1.0));
+        }
+    }
+
+    if settings.normalize_diagonals {
+        target.normalize_diagonals();
+    }
+
+    analog.step(&target, settings, frame_time);
+
+    output.left_stick.set_x_axis(analog.left_stick.0);
+    output.left_stick.set_y_axis(analog.left_stick.1);
+    output.right_stick.set_x_axis(analog.right_stick.0);
+    output.right_stick.set_y_axis(analog.right_stick.1);
+    output.left_trigger.set_value(analog.left_trigger);
+    output.right_trigger.set_value(analog.right_trigger);
+
     output
 }
 
@@ -167,13 +592,8 @@ fn generate_mouse_state(
     out.set_right_button(helper.mouse_held(1));
     out.set_middle_button(helper.mouse_held(2));
 
-    out.set_x_delta(mouse_events.delta_x as i32);
-    out.set_y_delta(mouse_events.delta_y as i32);
-
-    out.set_wheel_up(mouse_events.wheel_up);
-    out.set_wheel_down(mouse_events.wheel_down);
-    out.set_wheel_left(mouse_events.wheel_left);
-    out.set_wheel_right(mouse_events.wheel_right);
+    out.set_wheel_x(mouse_events.wheel_x as i32);
+    out.set_wheel_y(mouse_events.wheel_y as i32);
 
     out
 }
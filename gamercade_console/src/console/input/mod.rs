@@ -1,13 +1,22 @@
+mod action_bindings;
+mod analog_settings;
 mod gamepad_bindings;
 mod key_bindings;
 mod key_types;
+mod keyboard_analog;
 mod local_input_manager;
 mod player_input_entry;
+mod rumble_output;
+mod touch_collector;
 
+pub(crate) use analog_settings::*;
 use gilrs::GamepadId;
 use key_bindings::*;
+pub(crate) use key_types::KeyType;
+pub(crate) use keyboard_analog::KeyboardAnalogSettings;
 pub use local_input_manager::*;
 pub use player_input_entry::*;
+pub use touch_collector::TouchCollector;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[allow(dead_code)]
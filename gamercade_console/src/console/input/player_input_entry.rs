@@ -1,4 +1,6 @@
-use gamercade_core::{Buttons, InputState, MouseState};
+use gamercade_core::{
+    ActionState, Buttons, ControllerStatus, InputState, MouseMotion, MouseState, TouchState,
+};
 
 #[derive(Debug, Default, Clone)]
 pub struct PlayerInputEntry {
@@ -7,4 +9,13 @@ pub struct PlayerInputEntry {
 
     pub(crate) previous_mouse: MouseState,
     pub(crate) current_mouse: MouseState,
+    pub(crate) current_motion: MouseMotion,
+
+    pub(crate) previous_touch: TouchState,
+    pub(crate) current_touch: TouchState,
+
+    pub(crate) current_status: ControllerStatus,
+
+    pub(crate) previous_actions: ActionState,
+    pub(crate) current_actions: ActionState,
 }
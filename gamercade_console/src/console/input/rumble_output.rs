@@ -0,0 +1,112 @@
+use std::fmt;
+
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Replay, Ticks},
+    GamepadId, Gilrs,
+};
+use hashbrown::HashMap;
+
+use super::LocalPlayerId;
+
+/// How long a single rumble effect plays for. Effects are stopped early
+/// once the game's rumble runs out, so this only needs to be long enough.
+const RUMBLE_EFFECT_MS: u32 = 60_000;
+
+struct PlayingRumble {
+    gamepad_id: GamepadId,
+    magnitudes: (u16, u16),
+    // Dropping the effect stops it
+    _effect: Effect,
+}
+
+impl fmt::Debug for PlayingRumble {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PlayingRumble")
+            .field("gamepad_id", &self.gamepad_id)
+            .field("magnitudes", &self.magnitudes)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Plays the rumble requested by the game on the local players' gamepads.
+#[derive(Debug, Default)]
+pub(crate) struct RumbleOutput {
+    playing: HashMap<LocalPlayerId, PlayingRumble>,
+}
+
+impl RumbleOutput {
+    /// Updates the rumble of the player's gamepad, only restarting
+    /// the effect if the strength or gamepad has changed.
+    pub fn sync(
+        &mut self,
+        local_player: LocalPlayerId,
+        gamepad_id: Option<GamepadId>,
+        (strong, weak): (f32, f32),
+        gilrs: &mut Gilrs,
+    ) {
+        let magnitudes = (
+            (strong * u16::MAX as f32) as u16,
+            (weak * u16::MAX as f32) as u16,
+        );
+        let gamepad_id = gamepad_id.filter(|_| magnitudes != (0, 0));
+
+        if let (Some(gamepad_id), Some(playing)) = (gamepad_id, self.playing.get(&local_player)) {
+            if playing.gamepad_id == gamepad_id && playing.magnitudes == magnitudes {
+                return;
+            }
+        }
+
+        self.playing.remove(&local_player);
+
+        let gamepad_id = match gamepad_id {
+            Some(gamepad_id) if gilrs.gamepad(gamepad_id).is_ff_supported() => gamepad_id,
+            _ => return,
+        };
+
+        match start_effect(gamepad_id, magnitudes, gilrs) {
+            Ok(effect) => {
+                self.playing.insert(
+                    local_player,
+                    PlayingRumble {
+                        gamepad_id,
+                        magnitudes,
+                        _effect: effect,
+                    },
+                );
+            }
+            Err(e) => println!("Unable to rumble gamepad {}: {}", gamepad_id, e),
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        self.playing.clear();
+    }
+}
+
+fn start_effect(
+    gamepad_id: GamepadId,
+    (strong, weak): (u16, u16),
+    gilrs: &mut Gilrs,
+) -> Result<Effect, gilrs::ff::Error> {
+    let scheduling = Replay {
+        play_for: Ticks::from_ms(RUMBLE_EFFECT_MS),
+        ..Default::default()
+    };
+
+    let effect = EffectBuilder::new()
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Strong { magnitude: strong },
+            scheduling,
+            ..Default::default()
+        })
+        .add_effect(BaseEffect {
+            kind: BaseEffectType::Weak { magnitude: weak },
+            scheduling,
+            ..Default::default()
+        })
+        .gamepads(&[gamepad_id])
+        .finish(gilrs)?;
+
+    effect.play()?;
+    Ok(effect)
+}
@@ -0,0 +1,99 @@
+use gamercade_core::{TouchPoint, TouchState, MAX_TOUCH_POINTS};
+use pixels::Pixels;
+use winit::{
+    dpi::PhysicalPosition,
+    event::{Touch, TouchPhase},
+};
+
+#[derive(Debug, Clone, Copy)]
+struct TouchSlot {
+    id: u64,
+    position: PhysicalPosition<f64>,
+    ended: bool,
+    reported: bool,
+}
+
+/// Tracks the fingers touching the window, giving each one a slot which
+/// stays the same until the finger is lifted.
+#[derive(Debug, Default)]
+pub struct TouchCollector {
+    slots: [Option<TouchSlot>; MAX_TOUCH_POINTS],
+}
+
+impl TouchCollector {
+    pub fn handle_touch(&mut self, touch: &Touch) {
+        let existing = self
+            .slots
+            .iter()
+            .position(|slot| matches!(slot, Some(slot) if slot.id == touch.id && !slot.ended));
+
+        match (touch.phase, existing) {
+            (TouchPhase::Started, None) => {
+                // Extra fingers past the limit are ignored
+                if let Some(free) = self.slots.iter_mut().find(|slot| slot.is_none()) {
+                    *free = Some(TouchSlot {
+                        id: touch.id,
+                        position: touch.location,
+                        ended: false,
+                        reported: false,
+                    });
+                }
+            }
+            (phase, Some(index)) => {
+                if let Some(slot) = &mut self.slots[index] {
+                    slot.position = touch.location;
+                    slot.ended = matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled);
+                }
+            }
+            _ => (),
+        }
+
+        self.remove_reported_ended();
+    }
+
+    /// Generates the touch state for this frame, in game pixel coordinates.
+    pub fn generate_touch_state(&self, pixels: &Pixels) -> TouchState {
+        let mut out = TouchState::default();
+
+        self.slots.iter().enumerate().for_each(|(index, slot)| {
+            if let Some(slot) = slot {
+                let position = (slot.position.x as f32, slot.position.y as f32);
+                let (x, y) = pixels
+                    .window_pos_to_pixel(position)
+                    .unwrap_or_else(|position| pixels.clamp_pixel_pos(position));
+
+                let mut point = TouchPoint::default();
+                point.set_active(true);
+                point.set_x_pos(x as u32);
+                point.set_y_pos(y as u32);
+                out.set_point(index, point);
+            }
+        });
+
+        out
+    }
+
+    /// Called after the touch state has been sent for the frame. Touches which
+    /// start and end within a single frame are still seen by the game for one frame.
+    pub fn end_frame(&mut self) {
+        self.slots.iter_mut().flatten().for_each(|slot| {
+            slot.reported = true;
+        });
+        self.remove_reported_ended();
+    }
+
+    fn remove_reported_ended(&mut self) {
+        self.slots.iter_mut().for_each(|slot| {
+            if matches!(
+                slot,
+                Some(TouchSlot {
+                    ended: true,
+                    reported: true,
+                    ..
+                })
+            ) {
+                *slot = None;
+            }
+        });
+    }
+}
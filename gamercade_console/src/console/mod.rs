@@ -1,14 +1,28 @@
 mod bindings;
+mod console_socket;
 mod contexts;
+mod desync_detector;
+mod game_session;
+mod handshake;
 mod input;
 mod network;
+mod network_simulator;
+mod peer_socket;
+mod sync_test;
 mod wasm_console;
 
+pub use console_socket::ConsoleSocket;
 pub use contexts::Contexts;
+pub use desync_detector::DesyncDetector;
+pub use game_session::*;
 use gamercade_fs::Rom;
 use ggrs::{Config, GGRSRequest};
+pub use handshake::*;
 pub use input::*;
-pub use network::{SessionDescriptor, WasmConsoleState};
+pub use network::{order_players, NetworkInstance, SessionDescriptor, WasmConsoleState};
+pub use network_simulator::{NetworkConditions, NetworkSimulator};
+pub use peer_socket::PeerSocket;
+pub use sync_test::run_headless_sync_test;
 pub use wasm_console::WasmConsole;
 
 pub trait Console: Sized + Config {
@@ -1,19 +1,27 @@
 use std::net::SocketAddr;
 
 use bytemuck::{Pod, Zeroable};
-use gamercade_core::{Buttons, InputState, MouseState};
+use gamercade_core::{
+    ActionState, Buttons, ControllerStatus, InputState, MouseMotion, MouseState, TouchState,
+};
+use gamercade_lobby::RelayConfig;
 use gamercade_sound_engine::SoundEngineData;
 use ggrs::{Config, PlayerType};
 use wasmtime::Global;
 
-use super::WasmConsole;
+use super::{contexts::RumbleState, WasmConsole};
 
 #[derive(Clone)]
 pub struct WasmConsoleState {
     pub(crate) previous_buttons: Box<[Buttons]>,
+    pub(crate) previous_mice: Box<[MouseState]>,
+    pub(crate) previous_touches: Box<[TouchState]>,
+    pub(crate) previous_actions: Box<[ActionState]>,
     pub(crate) memories: Vec<Vec<u8>>,
     pub(crate) mutable_globals: Vec<Global>,
     pub(crate) sound_engine_data: SoundEngineData,
+    pub(crate) rumble: Box<[RumbleState]>,
+    pub(crate) rng_state: u64,
 }
 
 pub struct SaveStateDefinition {
@@ -26,6 +34,10 @@ pub struct SaveStateDefinition {
 pub struct NetworkInputState {
     pub input_state: InputState,
     pub mouse_state: MouseState,
+    pub mouse_motion: MouseMotion,
+    pub touch_state: TouchState,
+    pub controller_status: ControllerStatus,
+    pub action_state: ActionState,
 }
 
 impl Config for WasmConsole {
@@ -39,4 +51,182 @@ pub struct SessionDescriptor {
     pub num_players: usize,
     pub player_types: Box<[PlayerType<SocketAddr>]>,
     pub port: u16,
+
+    /// Consoles which are sent the confirmed inputs, so they can watch the match
+    pub spectators: Box<[SocketAddr]>,
+
+    /// Reaches the remote players through a relay server, rather than directly
+    pub relay: Option<RelayConfig>,
+}
+
+/// One of the consoles taking part in a networked session.
+#[derive(Debug, Clone, Copy)]
+pub struct NetworkInstance {
+    /// Must be different for every console in the session
+    pub instance_id: usize,
+
+    /// The console's address, or None for this console
+    pub addr: Option<SocketAddr>,
+    pub player_count: usize,
+}
+
+/// Assigns the players of each instance to player handles, in order of instance id.
+/// As every console is given the same instance ids, they all agree on which player is which.
+pub fn order_players(instances: &[NetworkInstance]) -> Result<Vec<PlayerType<SocketAddr>>, String> {
+    let mut instances = instances.to_vec();
+    instances.sort_by_key(|instance| instance.instance_id);
+
+    if instances
+        .iter()
+        .filter(|instance| instance.addr.is_none())
+        .count()
+        != 1
+    {
+        return Err("Exactly one instance must be this console".to_string());
+    }
+
+    for (index, instance) in instances.iter().enumerate() {
+        let later = &instances[index + 1..];
+
+        if later
+            .iter()
+            .any(|other| other.instance_id == instance.instance_id)
+        {
+            return Err(format!(
+                "Instance Id {} is used more than once",
+                instance.instance_id
+            ));
+        } else if instance.player_count == 0 {
+            return Err(format!(
+                "Instance {} doesn't have any players",
+                instance.instance_id
+            ));
+        }
+
+        if let Some(addr) = instance.addr {
+            if later.iter().any(|other| other.addr == Some(addr)) {
+                return Err(format!("Remote Addr {} is used more than once", addr));
+            }
+        }
+    }
+
+    Ok(instances
+        .iter()
+        .flat_map(|instance| {
+            let player_type = match instance.addr {
+                Some(addr) => PlayerType::Remote(addr),
+                None => PlayerType::Local,
+            };
+            std::iter::repeat(player_type).take(instance.player_count)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn instance(
+        instance_id: usize,
+        addr: Option<SocketAddr>,
+        player_count: usize,
+    ) -> NetworkInstance {
+        NetworkInstance {
+            instance_id,
+            addr,
+            player_count,
+        }
+    }
+
+    #[test]
+    fn test_players_are_ordered_by_instance_id() {
+        let instances = [
+            instance(2, Some(addr(8002)), 1),
+            instance(0, None, 1),
+            instance(1, Some(addr(8001)), 1),
+        ];
+
+        assert_eq!(
+            order_players(&instances).unwrap(),
+            vec![
+                PlayerType::Local,
+                PlayerType::Remote(addr(8001)),
+                PlayerType::Remote(addr(8002)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_several_local_players_per_instance() {
+        let instances = [
+            instance(5, Some(addr(8005)), 3),
+            instance(1, Some(addr(8001)), 2),
+            instance(3, None, 2),
+        ];
+
+        assert_eq!(
+            order_players(&instances).unwrap(),
+            vec![
+                PlayerType::Remote(addr(8001)),
+                PlayerType::Remote(addr(8001)),
+                PlayerType::Local,
+                PlayerType::Local,
+                PlayerType::Remote(addr(8005)),
+                PlayerType::Remote(addr(8005)),
+                PlayerType::Remote(addr(8005)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_console_agrees_on_the_order() {
+        // Each console lists itself as local, and the others in its own order
+        let first = [instance(0, None, 2), instance(1, Some(addr(8001)), 1)];
+        let second = [instance(0, Some(addr(8000)), 2), instance(1, None, 1)];
+
+        let first = order_players(&first).unwrap();
+        let second = order_players(&second).unwrap();
+
+        assert_eq!(first.len(), second.len());
+        first.iter().zip(second.iter()).for_each(|pair| match pair {
+            (PlayerType::Local, PlayerType::Remote(remote)) => {
+                assert_eq!(*remote, addr(8000))
+            }
+            (PlayerType::Remote(remote), PlayerType::Local) => {
+                assert_eq!(*remote, addr(8001))
+            }
+            pair => panic!("Consoles disagree on a player: {:?}", pair),
+        });
+    }
+
+    #[test]
+    fn test_exactly_one_local_instance() {
+        let none_local = [
+            instance(0, Some(addr(8000)), 1),
+            instance(1, Some(addr(8001)), 1),
+        ];
+        let both_local = [instance(0, None, 1), instance(1, None, 1)];
+
+        assert!(order_players(&none_local).is_err());
+        assert!(order_players(&both_local).is_err());
+    }
+
+    #[test]
+    fn test_invalid_instances() {
+        let repeated_id = [instance(0, None, 1), instance(0, Some(addr(8001)), 1)];
+        let repeated_addr = [
+            instance(0, None, 1),
+            instance(1, Some(addr(8001)), 1),
+            instance(2, Some(addr(8001)), 1),
+        ];
+        let no_players = [instance(0, None, 1), instance(1, Some(addr(8001)), 0)];
+
+        assert!(order_players(&repeated_id).is_err());
+        assert!(order_players(&repeated_addr).is_err());
+        assert!(order_players(&no_players).is_err());
+    }
 }
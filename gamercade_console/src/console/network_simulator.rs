@@ -0,0 +1,304 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use fastrand::Rng;
+use ggrs::{Message, NonBlockingSocket};
+
+/// How much longer reordered packets are held, on top of their usual delay.
+const REORDER_DELAY: Duration = Duration::from_millis(30);
+
+/// Bad network conditions, applied to every packet sent and received.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NetworkConditions {
+    /// Added to each packet, in each direction
+    pub latency_ms: u32,
+
+    /// Each packet's latency varies randomly by up to this much
+    pub jitter_ms: u32,
+    pub loss_percent: f32,
+    pub duplicate_percent: f32,
+    pub reorder_percent: f32,
+
+    /// The same seed drops and delays the same packets, so runs can be repeated
+    pub seed: u64,
+}
+
+impl NetworkConditions {
+    /// True if packets are passed through untouched.
+    pub fn is_perfect(&self) -> bool {
+        self.latency_ms == 0
+            && self.jitter_ms == 0
+            && self.loss_percent <= 0.0
+            && self.duplicate_percent <= 0.0
+            && self.reorder_percent <= 0.0
+    }
+}
+
+/// A packet waiting for its simulated delay to pass. Messages are kept
+/// serialized, so they can be duplicated.
+struct DelayedPacket {
+    release_at: Instant,
+    addr: SocketAddr,
+    bytes: Vec<u8>,
+}
+
+/// Wraps a socket, and simulates a bad network by delaying, dropping, duplicating
+/// and reordering GGRS' messages. Used to test rollback with local instances.
+pub struct NetworkSimulator<S> {
+    inner: S,
+    conditions: NetworkConditions,
+    rng: Rng,
+    outgoing: Vec<DelayedPacket>,
+    incoming: Vec<DelayedPacket>,
+}
+
+impl<S: NonBlockingSocket<SocketAddr>> NetworkSimulator<S> {
+    pub fn new(inner: S, conditions: NetworkConditions) -> Self {
+        Self {
+            inner,
+            conditions,
+            rng: Rng::with_seed(conditions.seed),
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+        }
+    }
+
+    fn chance(&self, percent: f32) -> bool {
+        self.rng.f32() * 100.0 < percent
+    }
+
+    /// Queues the packet, unless it's lost. Some packets are queued twice.
+    fn delay(&mut self, queue: Queue, addr: SocketAddr, bytes: Vec<u8>) {
+        if self.chance(self.conditions.loss_percent) {
+            return;
+        }
+
+        let copies = if self.chance(self.conditions.duplicate_percent) {
+            2
+        } else {
+            1
+        };
+
+        let now = Instant::now();
+
+        for _ in 0..copies {
+            let jitter = self.conditions.jitter_ms as i64;
+            let jitter = if jitter > 0 {
+                self.rng.i64(-jitter..=jitter)
+            } else {
+                0
+            };
+            let mut delay =
+                Duration::from_millis((self.conditions.latency_ms as i64 + jitter).max(0) as u64);
+
+            if self.chance(self.conditions.reorder_percent) {
+                delay += REORDER_DELAY;
+            }
+
+            let packet = DelayedPacket {
+                release_at: now + delay,
+                addr,
+                bytes: bytes.clone(),
+            };
+
+            match queue {
+                Queue::Outgoing => self.outgoing.push(packet),
+                Queue::Incoming => self.incoming.push(packet),
+            }
+        }
+    }
+
+    fn send_due(&mut self) {
+        release_due(&mut self.outgoing)
+            .into_iter()
+            .filter_map(deserialize)
+            .for_each(|(addr, msg)| self.inner.send_to(&msg, &addr));
+    }
+}
+
+enum Queue {
+    Outgoing,
+    Incoming,
+}
+
+/// Removes the packets whose delay has passed, in the order they are released.
+fn release_due(queue: &mut Vec<DelayedPacket>) -> Vec<(SocketAddr, Vec<u8>)> {
+    let now = Instant::now();
+    queue.sort_by_key(|packet| packet.release_at);
+
+    let due = queue
+        .iter()
+        .take_while(|packet| packet.release_at <= now)
+        .count();
+
+    queue
+        .drain(..due)
+        .map(|packet| (packet.addr, packet.bytes))
+        .collect()
+}
+
+fn deserialize((addr, bytes): (SocketAddr, Vec<u8>)) -> Option<(SocketAddr, Message)> {
+    bincode::deserialize(&bytes).ok().map(|msg| (addr, msg))
+}
+
+impl<S: NonBlockingSocket<SocketAddr>> NonBlockingSocket<SocketAddr> for NetworkSimulator<S> {
+    fn send_to(&mut self, msg: &Message, addr: &SocketAddr) {
+        if self.conditions.is_perfect() {
+            return self.inner.send_to(msg, addr);
+        }
+
+        self.delay(Queue::Outgoing, *addr, bincode::serialize(msg).unwrap());
+        self.send_due();
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(SocketAddr, Message)> {
+        if self.conditions.is_perfect() {
+            return self.inner.receive_all_messages();
+        }
+
+        // Delayed messages are sent even while GGRS isn't sending anything new
+        self.send_due();
+
+        self.inner
+            .receive_all_messages()
+            .into_iter()
+            .for_each(|(addr, msg)| {
+                self.delay(Queue::Incoming, addr, bincode::serialize(&msg).unwrap())
+            });
+
+        release_due(&mut self.incoming)
+            .into_iter()
+            .filter_map(deserialize)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Longer than any delay the tests use, including reordering.
+    const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+    /// The tests queue their own payloads, so the inner socket is never used.
+    struct NullSocket;
+
+    impl NonBlockingSocket<SocketAddr> for NullSocket {
+        fn send_to(&mut self, _msg: &Message, _addr: &SocketAddr) {}
+
+        fn receive_all_messages(&mut self) -> Vec<(SocketAddr, Message)> {
+            Vec::new()
+        }
+    }
+
+    fn peer() -> SocketAddr {
+        "127.0.0.1:8001".parse().unwrap()
+    }
+
+    fn queue(simulator: &mut NetworkSimulator<NullSocket>, id: u32) {
+        simulator.delay(Queue::Outgoing, peer(), id.to_le_bytes().to_vec());
+    }
+
+    fn release(simulator: &mut NetworkSimulator<NullSocket>) -> Vec<u32> {
+        release_due(&mut simulator.outgoing)
+            .into_iter()
+            .map(|(addr, bytes)| {
+                assert_eq!(addr, peer());
+                u32::from_le_bytes(bytes.try_into().unwrap())
+            })
+            .collect()
+    }
+
+    /// Queues a packet for each id, then waits for all of their delays to pass.
+    /// Returns the ids of the packets which made it, in the order they were released.
+    fn deliver_all(conditions: NetworkConditions, count: u32) -> Vec<u32> {
+        let mut simulator = NetworkSimulator::new(NullSocket, conditions);
+        (0..count).for_each(|id| queue(&mut simulator, id));
+
+        std::thread::sleep(SETTLE_TIME);
+        release(&mut simulator)
+    }
+
+    #[test]
+    fn test_latency() {
+        let conditions = NetworkConditions {
+            latency_ms: 50,
+            ..Default::default()
+        };
+        let mut simulator = NetworkSimulator::new(NullSocket, conditions);
+
+        queue(&mut simulator, 1);
+        assert!(release(&mut simulator).is_empty());
+
+        std::thread::sleep(SETTLE_TIME);
+        assert_eq!(release(&mut simulator), vec![1]);
+    }
+
+    #[test]
+    fn test_loss() {
+        let conditions = NetworkConditions {
+            loss_percent: 25.0,
+            seed: 1234,
+            ..Default::default()
+        };
+
+        let delivered = deliver_all(conditions, 1000);
+        assert!(
+            (650..850).contains(&delivered.len()),
+            "{} arrived",
+            delivered.len()
+        );
+
+        // The same seed loses the same packets
+        assert_eq!(delivered, deliver_all(conditions, 1000));
+
+        let everything_lost = NetworkConditions {
+            loss_percent: 100.0,
+            ..conditions
+        };
+        assert!(deliver_all(everything_lost, 100).is_empty());
+    }
+
+    #[test]
+    fn test_reordering() {
+        let conditions = NetworkConditions {
+            reorder_percent: 20.0,
+            seed: 5678,
+            ..Default::default()
+        };
+
+        let delivered = deliver_all(conditions, 100);
+
+        // Nothing is lost, but some packets arrive late
+        let mut sorted = delivered.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+        assert_ne!(delivered, sorted);
+
+        // The same seed reorders the same packets
+        assert_eq!(delivered, deliver_all(conditions, 100));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let conditions = NetworkConditions {
+            duplicate_percent: 100.0,
+            ..Default::default()
+        };
+
+        let expected = (0..10).flat_map(|id| [id, id]).collect::<Vec<_>>();
+        assert_eq!(deliver_all(conditions, 10), expected);
+    }
+
+    #[test]
+    fn test_perfect_conditions() {
+        assert!(NetworkConditions::default().is_perfect());
+        assert!(!NetworkConditions {
+            jitter_ms: 1,
+            ..Default::default()
+        }
+        .is_perfect());
+    }
+}
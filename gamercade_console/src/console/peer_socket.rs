@@ -0,0 +1,41 @@
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+};
+
+use gamercade_lobby::{RelayConfig, RelaySocket};
+
+/// How packets reach the other consoles. Either directly, or through a relay server
+/// for consoles behind NATs, which also tries to punch a direct path between them.
+pub enum PeerSocket {
+    Direct(UdpSocket),
+    Relay(RelaySocket),
+}
+
+impl PeerSocket {
+    /// Binds a non-blocking socket to the port, using the relay if there is one.
+    pub fn bind(port: u16, relay: Option<&RelayConfig>) -> io::Result<Self> {
+        match relay {
+            Some(config) => RelaySocket::bind(port, config.clone()).map(Self::Relay),
+            None => {
+                let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], port)))?;
+                socket.set_nonblocking(true)?;
+                Ok(Self::Direct(socket))
+            }
+        }
+    }
+
+    pub fn send_to(&self, packet: &[u8], addr: &SocketAddr) -> io::Result<usize> {
+        match self {
+            Self::Direct(socket) => socket.send_to(packet, addr),
+            Self::Relay(socket) => socket.send_to(packet, addr),
+        }
+    }
+
+    pub fn recv_from(&mut self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self {
+            Self::Direct(socket) => socket.recv_from(buffer),
+            Self::Relay(socket) => socket.recv_from(buffer),
+        }
+    }
+}
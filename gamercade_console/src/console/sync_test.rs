@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use fastrand::Rng;
+use gamercade_core::{
+    ActionState, ButtonCode, ControllerStatus, InputState, MouseMotion, MouseState, TouchState,
+};
+use gamercade_fs::Rom;
+use ggrs::{GGRSError, PlayerType};
+use parking_lot::Mutex;
+
+use super::{
+    network::NetworkInputState, rom_hash, Console, DesyncDetector, GameSession, HandshakeMessage,
+    NetworkConditions, SessionDescriptor, SessionMode, SessionSettings, WasmConsole,
+};
+
+/// How many frames each random input is held for, like a player would.
+const INPUT_HOLD_FRAMES: usize = 8;
+
+/// Runs the game in a SyncTest session without a window, giving each player random
+/// inputs. Returns an error describing the first frame which didn't match.
+pub fn run_headless_sync_test(
+    rom: Rom,
+    seed: u64,
+    num_players: usize,
+    check_distance: usize,
+    frames: usize,
+) -> Result<(), String> {
+    let max_players = rom.player_count.1;
+    if num_players == 0 || num_players > max_players {
+        return Err(format!(
+            "Can't run a SyncTest with {} players, this game supports 1 to {}.",
+            num_players, max_players
+        ));
+    }
+
+    let session_descriptor = SessionDescriptor {
+        num_players,
+        player_types: vec![PlayerType::Local; num_players].into_boxed_slice(),
+        port: 0,
+        spectators: Box::new([]),
+        relay: None,
+    };
+
+    // There aren't any other peers to compare with
+    let desync_detector = Arc::new(Mutex::new(DesyncDetector::new(0, Vec::new())));
+
+    let mut session = GameSession::start(
+        rom.frame_rate.frames_per_second(),
+        &session_descriptor,
+        SessionMode::SyncTest { check_distance },
+        desync_detector.clone(),
+        HandshakeMessage::new(rom_hash(&rom), seed),
+        NetworkConditions::default(),
+        SessionSettings::default(),
+    )?;
+
+    let (width, height) = (rom.width() as u32, rom.height() as u32);
+    let (mut console, _) = WasmConsole::new(
+        rom,
+        seed,
+        session_descriptor,
+        SessionMode::SyncTest { check_distance },
+        session.max_prediction(),
+        desync_detector,
+        false,
+    );
+
+    let rng = Rng::with_seed(seed);
+    let mut inputs = vec![random_input(&rng, width, height); num_players];
+
+    for frame in 0..frames {
+        if frame % INPUT_HOLD_FRAMES == 0 {
+            inputs
+                .iter_mut()
+                .for_each(|input| *input = random_input(&rng, width, height));
+        }
+
+        session
+            .local_player_handles()
+            .into_iter()
+            .zip(inputs.iter())
+            .try_for_each(|(handle, input)| session.add_local_input(handle, *input))
+            .map_err(|e| e.to_string())?;
+
+        match session.advance_frame() {
+            Ok(requests) => console.handle_requests(requests),
+            Err(GGRSError::MismatchedChecksum { frame }) => {
+                return Err(format!(
+                "Checksums didn't match after resimulating frame {}. The game isn't rollback safe.",
+                frame
+            ))
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    Ok(())
+}
+
+fn random_input(rng: &Rng, width: u32, height: u32) -> NetworkInputState {
+    let mut input_state = InputState::default();
+    ButtonCode::ALL.iter().for_each(|button| {
+        if rng.bool() {
+            input_state.buttons.enable_button(*button);
+        }
+    });
+    input_state.left_stick.set_x_axis(rng.f32() * 2.0 - 1.0);
+    input_state.left_stick.set_y_axis(rng.f32() * 2.0 - 1.0);
+    input_state.right_stick.set_x_axis(rng.f32() * 2.0 - 1.0);
+    input_state.right_stick.set_y_axis(rng.f32() * 2.0 - 1.0);
+    input_state.left_trigger.set_value(rng.f32());
+    input_state.right_trigger.set_value(rng.f32());
+
+    let mut mouse_state = MouseState::default();
+    mouse_state.set_x_pos(rng.u32(0..width));
+    mouse_state.set_y_pos(rng.u32(0..height));
+    mouse_state.set_left_button(rng.bool());
+    mouse_state.set_right_button(rng.bool());
+    mouse_state.set_middle_button(rng.bool());
+
+    let mut mouse_motion = MouseMotion::default();
+    mouse_motion.set_x(rng.f64() * 20.0 - 10.0);
+    mouse_motion.set_y(rng.f64() * 20.0 - 10.0);
+
+    let mut controller_status = ControllerStatus::default();
+    controller_status.set_connected(true);
+
+    NetworkInputState {
+        input_state,
+        mouse_state,
+        mouse_motion,
+        touch_state: TouchState::default(),
+        controller_status,
+        action_state: ActionState(rng.u32(..)),
+    }
+}
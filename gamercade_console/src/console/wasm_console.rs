@@ -1,7 +1,8 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
 use gamercade_sound_engine::{SoundEngine, SoundEngineData, SoundRomInstance};
-use ggrs::GGRSRequest;
+use ggrs::{Frame, GGRSRequest, InputStatus, PlayerHandle, PlayerType};
+use parking_lot::{Mutex, MutexGuard};
 use wasmtime::{Engine, ExternType, Instance, Linker, Module, Mutability, Store, TypedFunc};
 use winit::{dpi::PhysicalPosition, window::Window};
 
@@ -9,8 +10,9 @@ type GameFunc = TypedFunc<(), ()>;
 
 use super::{
     bindings,
+    desync_detector::{StateHasher, StateSnapshot},
     network::{SaveStateDefinition, WasmConsoleState},
-    Contexts, SessionDescriptor,
+    Contexts, DesyncDetector, PlayerInputEntry, SessionDescriptor, SessionMode, TextInputCollector,
 };
 use crate::Console;
 use gamercade_fs::Rom;
@@ -23,6 +25,13 @@ pub struct WasmConsole {
     pub(crate) state_definition: SaveStateDefinition,
     pub(crate) sound_engine: SoundEngine,
     pub(crate) audio_out: SoundEngineData,
+
+    /// Whether each player's inputs for the latest frame were confirmed or predicted.
+    /// Only used for display, so isn't part of the save state.
+    input_statuses: Vec<InputStatus>,
+
+    /// Shared with the session's socket, which exchanges checksums with the other peers
+    desync_detector: Arc<Mutex<DesyncDetector>>,
 }
 
 #[derive(Clone)]
@@ -73,22 +82,26 @@ impl WasmConsole {
         rom: Rom,
         seed: u64,
         session: SessionDescriptor,
+        mode: SessionMode,
         max_prediction: usize,
+        desync_detector: Arc<Mutex<DesyncDetector>>,
+        audio_output: bool,
     ) -> (Self, WasmConsoleState) {
         // Initialize sound output
 
         let rom = Arc::new(rom);
         let sound_rom = Arc::new(SoundRomInstance::new(&rom.sounds));
 
-        let sound_engine = SoundEngine::new(
-            rom.frame_rate.frames_per_second(),
-            &sound_rom,
-            max_prediction,
-        );
+        let fps = rom.frame_rate.frames_per_second();
+        let sound_engine = if audio_output {
+            SoundEngine::new(fps, &sound_rom, max_prediction)
+        } else {
+            SoundEngine::silent(fps)
+        };
         let output_sample_rate = sound_engine.output_sample_rate();
 
         // Initialize the contexts
-        let contexts = Contexts::new(&rom, seed, session, &sound_rom, output_sample_rate);
+        let contexts = Contexts::new(&rom, seed, session, mode, &sound_rom, output_sample_rate);
         let engine = Engine::default();
         let module = Module::new(&engine, &rom.code).unwrap();
         let mut linker = Linker::new(&engine);
@@ -133,6 +146,8 @@ impl WasmConsole {
             store,
             sound_engine,
             audio_out,
+            input_statuses: Vec::new(),
+            desync_detector,
         };
 
         out.call_init();
@@ -155,6 +170,33 @@ impl WasmConsole {
             .collect::<Vec<_>>()
             .into_boxed_slice();
 
+        let previous_mice = self
+            .store
+            .data()
+            .input_context
+            .input_entries
+            .iter()
+            .map(|input| input.previous_mouse)
+            .collect();
+
+        let previous_touches = self
+            .store
+            .data()
+            .input_context
+            .input_entries
+            .iter()
+            .map(|input| input.previous_touch)
+            .collect();
+
+        let previous_actions = self
+            .store
+            .data()
+            .input_context
+            .input_entries
+            .iter()
+            .map(|input| input.previous_actions)
+            .collect();
+
         let memories = self
             .state_definition
             .memories
@@ -176,23 +218,86 @@ impl WasmConsole {
             .collect();
 
         let sound_engine_data = self.store.data().audio_context.sound_engine_data.clone();
+        let rumble = self.store.data().rumble_context.players.clone();
+        let rng_state = self.store.data().random_context.state();
 
         WasmConsoleState {
             previous_buttons,
+            previous_mice,
+            previous_touches,
+            previous_actions,
             memories,
             mutable_globals,
             sound_engine_data,
+            rumble,
+            rng_state,
+        }
+    }
+
+    /// Hashes the parts of the state which must match between peers.
+    fn checksum(&mut self, state: &WasmConsoleState) -> u64 {
+        let mut hasher = StateHasher::default();
+
+        state
+            .memories
+            .iter()
+            .for_each(|memory| hasher.write_bytes(memory));
+        state
+            .mutable_globals
+            .iter()
+            .for_each(|global| hasher.write_val(&global.get(&mut self.store)));
+
+        // Playback positions depend on each machine's output sample rate,
+        // so only the song chosen by the game is compared
+        let bgm = state.sound_engine_data.bgm.song.map(|song| song.0);
+        hasher.write_u64(bgm.map_or(u64::MAX, |song| song as u64));
+        hasher.write_u64(state.rng_state);
+
+        hasher.finish()
+    }
+
+    fn snapshot(&mut self, state: &WasmConsoleState, checksum: u64) -> StateSnapshot {
+        let memories = self
+            .state_definition
+            .memories
+            .iter()
+            .cloned()
+            .zip(state.memories.iter().cloned())
+            .collect();
+
+        let globals = self
+            .state_definition
+            .mutable_globals
+            .iter()
+            .cloned()
+            .zip(state.mutable_globals.iter())
+            .map(|(name, global)| (name, format!("{:?}", global.get(&mut self.store))))
+            .collect();
+
+        StateSnapshot {
+            checksum,
+            memories,
+            globals,
+            bgm: state.sound_engine_data.bgm.song.map(|song| song.0),
         }
     }
 
     pub fn load_save_state(&mut self, state: WasmConsoleState) {
         let WasmConsoleState {
             previous_buttons,
+            previous_mice,
+            previous_touches,
+            previous_actions,
             memories,
             mutable_globals,
             sound_engine_data,
+            rumble,
+            rng_state,
         } = state;
 
+        self.store.data_mut().rumble_context.players = rumble;
+        self.store.data().random_context.restore_state(rng_state);
+
         let audio_context = &mut self.store.data_mut().audio_context;
         audio_context.sound_engine_data = sound_engine_data;
         audio_context.changed = true;
@@ -204,6 +309,24 @@ impl WasmConsole {
                 self.store.data_mut().input_context.input_entries[index].previous = *prev;
             });
 
+        previous_mice.iter().enumerate().for_each(|(index, prev)| {
+            self.store.data_mut().input_context.input_entries[index].previous_mouse = *prev;
+        });
+
+        previous_touches
+            .iter()
+            .enumerate()
+            .for_each(|(index, prev)| {
+                self.store.data_mut().input_context.input_entries[index].previous_touch = *prev;
+            });
+
+        previous_actions
+            .iter()
+            .enumerate()
+            .for_each(|(index, prev)| {
+                self.store.data_mut().input_context.input_entries[index].previous_actions = *prev;
+            });
+
         self.state_definition
             .memories
             .iter()
@@ -237,6 +360,75 @@ impl WasmConsole {
         }
     }
 
+    /// Returns the strength of the strong and weak rumble motors for the player.
+    pub(crate) fn rumble_strength(&self, player_id: usize) -> (f32, f32) {
+        self.store
+            .data()
+            .rumble_context
+            .players
+            .get(player_id)
+            .map(|player| player.strength())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn input_entries(&self) -> &[PlayerInputEntry] {
+        &self.store.data().input_context.input_entries
+    }
+
+    pub(crate) fn input_statuses(&self) -> &[InputStatus] {
+        &self.input_statuses
+    }
+
+    /// The other consoles in the session, along with a player handle used to
+    /// query the connection to each. Spectators are included after the players.
+    pub(crate) fn remote_peers(&self) -> Vec<(SocketAddr, PlayerHandle)> {
+        let session = &self.store.data().multiplayer_context.session;
+
+        let mut peers: Vec<(SocketAddr, PlayerHandle)> = Vec::new();
+        session
+            .player_types
+            .iter()
+            .enumerate()
+            .for_each(|(handle, player)| {
+                if let PlayerType::Remote(addr) = player {
+                    if peers.iter().all(|(peer, _)| peer != addr) {
+                        peers.push((*addr, handle));
+                    }
+                }
+            });
+
+        peers.extend(
+            session
+                .spectators
+                .iter()
+                .enumerate()
+                .map(|(index, addr)| (*addr, session.num_players + index)),
+        );
+        peers
+    }
+
+    /// Compares the checksums of any newly confirmed frames with the other peers.
+    pub(crate) fn confirm_frame(&mut self, frame: Frame) {
+        self.desync_detector.lock().confirm(frame);
+    }
+
+    /// Holds the detector's lock, so shouldn't be kept around.
+    pub(crate) fn desync_detector(&self) -> MutexGuard<'_, DesyncDetector> {
+        self.desync_detector.lock()
+    }
+
+    pub(crate) fn text_input_active(&self) -> bool {
+        self.store.data().text_input_context.active
+    }
+
+    /// Passes the text typed since the last frame to the game, if it is using text input.
+    pub(crate) fn push_text_input(&mut self, text: TextInputCollector) {
+        let text_input_context = &mut self.store.data_mut().text_input_context;
+        if text_input_context.active {
+            text_input_context.frame = text;
+        }
+    }
+
     pub(crate) fn sync_mouse(&mut self, window: &Window) {
         match self.store.data().input_context.mouse_locked {
             true => {
@@ -282,7 +474,7 @@ impl Console for WasmConsole {
     }
 
     fn blit(&self, buffer: &mut [u8]) {
-        buffer.copy_from_slice(&self.store.data().draw_context.frame_buffer.pixel_buffer);
+        self.store.data().draw_context.blit(buffer);
     }
 
     fn handle_requests(&mut self, requests: Vec<GGRSRequest<Self>>) {
@@ -290,7 +482,14 @@ impl Console for WasmConsole {
             match request {
                 GGRSRequest::SaveGameState { cell, frame } => {
                     let state = self.generate_save_state();
-                    cell.save(frame, Some(state), None);
+                    let checksum = self.checksum(&state);
+
+                    if self.desync_detector.lock().wants_frame(frame) {
+                        let snapshot = self.snapshot(&state, checksum);
+                        self.desync_detector.lock().record(frame, snapshot);
+                    }
+
+                    cell.save(frame, Some(state), Some(checksum as u128));
                 }
                 GGRSRequest::LoadGameState { cell, .. } => {
                     let state = cell.load().expect("Failed to load game state");
@@ -307,8 +506,30 @@ impl Console for WasmConsole {
                         .for_each(|(current, new)| {
                             current.current = new.0.input_state;
                             current.current_mouse = new.0.mouse_state;
+                            current.current_motion = new.0.mouse_motion;
+                            current.current_touch = new.0.touch_state;
+                            current.current_status = new.0.controller_status;
+                            current.current_actions = new.0.action_state;
+                        });
+
+                    self.input_statuses.clear();
+                    self.input_statuses
+                        .extend(inputs.iter().map(|(_, status)| *status));
+
+                    self.store
+                        .data_mut()
+                        .multiplayer_context
+                        .disconnected
+                        .iter_mut()
+                        .zip(inputs.iter())
+                        .for_each(|(disconnected, (_, status))| {
+                            *disconnected = matches!(status, InputStatus::Disconnected)
                         });
 
+                    // Counted down before the update, so that the host sees
+                    // any rumble started this frame for its whole duration
+                    self.store.data_mut().rumble_context.advance();
+
                     // Call update
                     self.call_update();
 
@@ -328,7 +549,12 @@ impl Console for WasmConsole {
                         .for_each(|inputs| {
                             inputs.previous = inputs.current.buttons;
                             inputs.previous_mouse = inputs.current_mouse;
+                            inputs.previous_touch = inputs.current_touch;
+                            inputs.previous_actions = inputs.current_actions;
                         });
+
+                    // Text is only available for the frame it was typed in
+                    self.store.data_mut().text_input_context.frame = TextInputCollector::default();
                 }
             }
         }
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use pixels::wgpu;
+use serde::{Deserialize, Serialize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    window::{Fullscreen, Window},
+};
+
+const CONFIG_FILE_NAME: &str = "consoleConfig.json";
+
+/// Returns a sub directory of the per user config directory for the console,
+/// ie `~/.config/gamercade/input` on Linux, or the directory itself if `sub_dir`
+/// is empty. Creates it if it doesn't exist yet.
+pub(crate) fn user_config_dir(sub_dir: &str) -> Option<PathBuf> {
+    let path = dirs::config_dir()?.join("gamercade").join(sub_dir);
+
+    match std::fs::create_dir_all(&path) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            println!("Unable to create config directory {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ConsoleConfig {
+    pub window: WindowSettings,
+}
+
+impl ConsoleConfig {
+    /// Returns the path of the config file, inside of the user config directory.
+    /// Falls back to the working directory if the config directory is unavailable.
+    fn config_path() -> PathBuf {
+        user_config_dir("")
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME))
+    }
+
+    pub fn load() -> Self {
+        let mut path = Self::config_path();
+
+        // Older versions stored the config in the working directory
+        let legacy_path = PathBuf::from(CONFIG_FILE_NAME);
+        if !path.exists() && legacy_path.exists() {
+            path = legacy_path;
+        }
+
+        if path.exists() {
+            match std::fs::read(&path) {
+                Ok(file) => match serde_json::from_slice::<Self>(&file) {
+                    Ok(config) => {
+                        println!("Successfully loaded config from: {:?}", path);
+                        return config;
+                    }
+                    Err(e) => {
+                        println!("{:?} found, but unable to parse: {}", path, e);
+                    }
+                },
+                Err(e) => println!("{:?} found, but unable to read: {}", path, e),
+            };
+
+            println!("Using default config.");
+            Self::default()
+        } else {
+            println!("{:?} not found. Generating default config file.", path);
+            let config = Self::default();
+            config.save();
+            config
+        }
+    }
+
+    pub fn save(&self) {
+        let path = Self::config_path();
+        let json = serde_json::to_string_pretty(self).unwrap();
+
+        if let Err(e) = std::fs::write(&path, json) {
+            println!("Error writing {:?}: {}", path, e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum FullscreenMode {
+    Windowed,
+    Borderless,
+    Exclusive,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct WindowSettings {
+    pub fullscreen: FullscreenMode,
+    pub integer_scale_lock: bool,
+    pub background_color: [u8; 3],
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(u32, u32)>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: FullscreenMode::Windowed,
+            integer_scale_lock: false,
+            background_color: [0, 0, 0],
+            position: None,
+            size: None,
+        }
+    }
+}
+
+impl WindowSettings {
+    /// Restores the remembered window placement and fullscreen mode.
+    pub fn restore(&self, window: &Window) {
+        if let Some((x, y)) = self.position {
+            window.set_outer_position(PhysicalPosition::new(x, y));
+        }
+
+        if let Some((width, height)) = self.size {
+            window.set_inner_size(PhysicalSize::new(width, height));
+        }
+
+        self.apply_fullscreen(window);
+    }
+
+    pub fn apply_fullscreen(&self, window: &Window) {
+        let fullscreen = match self.fullscreen {
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(None)),
+            FullscreenMode::Exclusive => window
+                .current_monitor()
+                .and_then(|monitor| {
+                    monitor.video_modes().max_by_key(|mode| {
+                        let size = mode.size();
+                        (size.width * size.height, mode.refresh_rate())
+                    })
+                })
+                .map(Fullscreen::Exclusive),
+        };
+
+        window.set_fullscreen(fullscreen);
+    }
+
+    /// Switches between windowed and borderless fullscreen.
+    pub fn toggle_fullscreen(&mut self, window: &Window) {
+        self.fullscreen = match self.fullscreen {
+            FullscreenMode::Windowed => FullscreenMode::Borderless,
+            FullscreenMode::Borderless | FullscreenMode::Exclusive => FullscreenMode::Windowed,
+        };
+        self.apply_fullscreen(window);
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        let [r, g, b] = self.background_color;
+        wgpu::Color {
+            r: r as f64 / 255.0,
+            g: g as f64 / 255.0,
+            b: b as f64 / 255.0,
+            a: 1.0,
+        }
+    }
+
+    /// Tracks the window placement, so it can be restored on the next launch.
+    /// Fullscreen placements are not remembered.
+    pub fn remember_position(&mut self, position: PhysicalPosition<i32>) {
+        if self.fullscreen == FullscreenMode::Windowed {
+            self.position = Some((position.x, position.y));
+        }
+    }
+
+    pub fn remember_size(&mut self, size: PhysicalSize<u32>) {
+        if self.fullscreen == FullscreenMode::Windowed && size.width > 0 && size.height > 0 {
+            self.size = Some((size.width, size.height));
+        }
+    }
+
+    /// Returns the largest whole multiple of the game resolution which fits
+    /// inside of the passed in size. Returns None if the window shouldn't be
+    /// snapped, such as when fullscreen or the integer scale lock is disabled.
+    pub fn integer_scaled_size(
+        &self,
+        size: PhysicalSize<u32>,
+        (width, height): (u32, u32),
+    ) -> Option<PhysicalSize<u32>> {
+        if !self.integer_scale_lock || self.fullscreen != FullscreenMode::Windowed {
+            return None;
+        }
+
+        let scale = (size.width / width).min(size.height / height).max(1);
+        Some(PhysicalSize::new(width * scale, height * scale))
+    }
+}
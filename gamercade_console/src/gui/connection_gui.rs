@@ -0,0 +1,115 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use egui::{Align2, Area, Context, Frame};
+use ggrs::GGRSEvent;
+
+use crate::console::WasmConsole;
+
+/// Tracks the connection to each peer while a session is running, and
+/// tells the players when it's interrupted or lost.
+#[derive(Default)]
+pub struct ConnectionGui {
+    /// Peers which haven't sent anything recently, and when they will be disconnected
+    interrupted: Vec<(SocketAddr, Instant)>,
+    disconnected: Vec<SocketAddr>,
+
+    /// Why the last session ended early, shown until the next game is launched
+    session_ended: Option<String>,
+}
+
+impl ConnectionGui {
+    /// Returns how many frames to skip, to let the other peers catch up.
+    pub(crate) fn handle_event(&mut self, event: GGRSEvent<WasmConsole>) -> u32 {
+        match event {
+            GGRSEvent::Synchronizing { addr, total, count } => {
+                println!("Synchronizing with {}: {}/{}", addr, count, total)
+            }
+            GGRSEvent::Synchronized { addr } => println!("Synchronized with {}", addr),
+            GGRSEvent::NetworkInterrupted {
+                addr,
+                disconnect_timeout,
+            } => {
+                println!(
+                    "Connection to {} interrupted, disconnecting in {}ms",
+                    addr, disconnect_timeout
+                );
+                let disconnect_at =
+                    Instant::now() + Duration::from_millis(disconnect_timeout as u64);
+                self.interrupted.retain(|(peer, _)| *peer != addr);
+                self.interrupted.push((addr, disconnect_at));
+            }
+            GGRSEvent::NetworkResumed { addr } => {
+                println!("Connection to {} resumed", addr);
+                self.interrupted.retain(|(peer, _)| *peer != addr);
+            }
+            GGRSEvent::Disconnected { addr } => {
+                println!("{} disconnected", addr);
+                self.interrupted.retain(|(peer, _)| *peer != addr);
+                if !self.disconnected.contains(&addr) {
+                    self.disconnected.push(addr);
+                }
+            }
+            GGRSEvent::WaitRecommendation { skip_frames } => return skip_frames,
+        }
+
+        0
+    }
+
+    /// Forgets the previous session's peers, and shows why it ended, if it ended early.
+    pub(crate) fn reset(&mut self, session_ended: Option<String>) {
+        self.interrupted.clear();
+        self.disconnected.clear();
+        self.session_ended = session_ended;
+    }
+
+    /// Returns true if the player chose to return to the menu.
+    pub(crate) fn draw(&mut self, ctx: &Context) -> bool {
+        let mut return_to_menu = false;
+
+        if !self.interrupted.is_empty() {
+            Area::new("connection_interrupted_notice")
+                .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.heading("Connection interrupted");
+                        self.interrupted.iter().for_each(|(addr, disconnect_at)| {
+                            let remaining = disconnect_at.saturating_duration_since(Instant::now());
+                            ui.label(format!(
+                                "Waiting for {}, disconnecting in {:.1}s...",
+                                addr,
+                                remaining.as_secs_f32()
+                            ));
+                        });
+                        return_to_menu |= ui.button("Return to Menu").clicked();
+                    });
+                });
+        } else if !self.disconnected.is_empty() {
+            Area::new("disconnected_notice")
+                .anchor(Align2::LEFT_TOP, [8.0, 8.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        self.disconnected.iter().for_each(|addr| {
+                            ui.label(format!("{} disconnected.", addr));
+                        });
+                        return_to_menu |= ui.button("Return to Menu").clicked();
+                    });
+                });
+        }
+
+        if let Some(reason) = &self.session_ended {
+            Area::new("session_ended_notice")
+                .anchor(Align2::CENTER_BOTTOM, [0.0, -8.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label("The session ended:");
+                        ui.label(reason);
+                    });
+                });
+        }
+
+        return_to_menu
+    }
+}
@@ -1,16 +1,44 @@
-use egui::{ComboBox, Slider, Ui};
-use gilrs::Gilrs;
+use egui::{Color32, ComboBox, Grid, ProgressBar, Rect, Sense, Slider, Ui, Vec2};
+use gamercade_core::ButtonCode;
+use gilrs::{Axis, Button, EventType, Gamepad, GamepadId, Gilrs};
+use winit::event::VirtualKeyCode;
 
-use crate::console::{InputMode, LocalInputManager, LocalKeyboardId};
+use crate::console::{
+    DeadzoneShape, InputMode, KeyType, KeyboardAnalogSettings, LocalInputManager, LocalKeyboardId,
+    ResponseCurve, StickSettings, TriggerSettings,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RebindTarget {
+    Input(KeyType),
+    /// The key which scales down emulated analog inputs while held
+    WalkModifier,
+}
 
 pub struct ControllerGui {
     pub local_player_count: usize,
+
+    /// The player whose bindings are being edited
+    rebinding_player: Option<usize>,
+
+    /// The input waiting for a key or button press
+    awaiting_input: Option<RebindTarget>,
+
+    /// The player whose gamepad is being calibrated
+    calibrating_player: Option<usize>,
+
+    /// Set while a calibration slider is being dragged, so it's only saved once released
+    calibration_unsaved: bool,
 }
 
 impl Default for ControllerGui {
     fn default() -> Self {
         Self {
             local_player_count: 1,
+            rebinding_player: None,
+            awaiting_input: None,
+            calibrating_player: None,
+            calibration_unsaved: false,
         }
     }
 }
@@ -21,15 +49,22 @@ impl ControllerGui {
         ui: &mut Ui,
         can_adjust_player_count: bool,
         input: &mut LocalInputManager,
-        gilrs: &Gilrs,
+        gilrs: &mut Gilrs,
     ) {
         ui.group(|ui| {
             ui.label("Controller Settings:");
 
+            // Players can also be added by pressing Start on an unused gamepad
+            self.local_player_count = input.player_bindings.len();
+
             if ui
                 .add_enabled(
                     can_adjust_player_count,
-                    Slider::new(&mut self.local_player_count, 1..=4).text("Local Player Count"),
+                    Slider::new(
+                        &mut self.local_player_count,
+                        1..=LocalInputManager::MAX_LOCAL_PLAYERS,
+                    )
+                    .text("Local Player Count"),
                 )
                 .changed()
             {
@@ -39,6 +74,11 @@ impl ControllerGui {
                 );
             };
 
+            let keyboard_count = input.keyboard_bindings.buttons.len();
+            let rebinding_player = &mut self.rebinding_player;
+            let awaiting_input = &mut self.awaiting_input;
+            let calibrating_player = &mut self.calibrating_player;
+
             input
                 .player_bindings
                 .iter_mut()
@@ -49,30 +89,530 @@ impl ControllerGui {
                             format!("Keyboard {}", keyboard_index.0)
                         }
                         InputMode::Gamepad(gamepad_id) => {
-                            format!("{} [{}]", gilrs.gamepad(*gamepad_id).name(), gamepad_id)
+                            let gamepad = gilrs.gamepad(*gamepad_id);
+                            if gamepad.is_connected() {
+                                format!("{} [{}]", gamepad.name(), gamepad_id)
+                            } else {
+                                format!("{} [{}] (Disconnected)", gamepad.name(), gamepad_id)
+                            }
                         }
                     };
 
-                    ComboBox::from_label(format!("Player {} Settings:", player_id))
-                        .selected_text(combo_text)
-                        .show_ui(ui, |ui| {
-                            (0..input.keyboard_bindings.buttons.len()).for_each(|keyboard_index| {
-                                ui.selectable_value(
-                                    input_mode,
-                                    InputMode::Emulated(LocalKeyboardId(keyboard_index)),
-                                    format!("Keyboard {}", keyboard_index),
-                                );
-                            });
+                    ui.horizontal(|ui| {
+                        ComboBox::from_label(format!("Player {} Settings:", player_id))
+                            .selected_text(combo_text)
+                            .show_ui(ui, |ui| {
+                                (0..keyboard_count).for_each(|keyboard_index| {
+                                    ui.selectable_value(
+                                        input_mode,
+                                        InputMode::Emulated(LocalKeyboardId(keyboard_index)),
+                                        format!("Keyboard {}", keyboard_index),
+                                    );
+                                });
 
-                            gilrs.gamepads().for_each(|(id, gamepad)| {
-                                ui.selectable_value(
-                                    input_mode,
-                                    InputMode::Gamepad(id),
-                                    format!("{} [{}]", gamepad.name(), id),
-                                );
+                                gilrs.gamepads().for_each(|(id, gamepad)| {
+                                    ui.selectable_value(
+                                        input_mode,
+                                        InputMode::Gamepad(id),
+                                        format!("{} [{}]", gamepad.name(), id),
+                                    );
+                                });
                             });
-                        });
+
+                        if ui.button("Rebind").clicked() {
+                            *rebinding_player = Some(player_id);
+                            *awaiting_input = None;
+                        }
+
+                        if let InputMode::Gamepad(_) = input_mode {
+                            if ui.button("Calibrate").clicked() {
+                                *calibrating_player = Some(player_id);
+                            }
+                        }
+                    });
+                });
+
+            ui.label("Press Start on an unused gamepad to join.");
+
+            if let Some(player_id) = self.rebinding_player {
+                match input.player_bindings.get(player_id).cloned() {
+                    Some(input_mode) => {
+                        ui.separator();
+                        self.draw_rebinding(
+                            ui,
+                            player_id,
+                            &input_mode,
+                            input,
+                            gilrs,
+                            can_adjust_player_count,
+                        );
+                    }
+                    None => self.rebinding_player = None,
+                }
+            }
+
+            if let Some(player_id) = self.calibrating_player {
+                match input.player_bindings.get(player_id) {
+                    Some(InputMode::Gamepad(gamepad_id)) => {
+                        let gamepad_id = *gamepad_id;
+                        ui.separator();
+                        self.draw_calibration(ui, player_id, gamepad_id, input, gilrs);
+                    }
+                    _ => self.calibrating_player = None,
+                }
+            }
+
+            if !input.action_bindings.actions().is_empty() {
+                ui.separator();
+                draw_action_bindings(ui, input);
+            }
+        });
+    }
+
+    fn draw_calibration(
+        &mut self,
+        ui: &mut Ui,
+        player_id: usize,
+        gamepad_id: GamepadId,
+        input: &mut LocalInputManager,
+        gilrs: &Gilrs,
+    ) {
+        ui.label(format!("Player {} Calibration:", player_id));
+
+        match gilrs.connected_gamepad(gamepad_id) {
+            Some(gamepad) => {
+                let axis = |axis| {
+                    gamepad
+                        .axis_data(axis)
+                        .map(|data| data.value())
+                        .unwrap_or_default()
+                };
+                let left_stick = (axis(Axis::LeftStickX), axis(Axis::LeftStickY));
+                let right_stick = (axis(Axis::RightStickX), axis(Axis::RightStickY));
+                let triggers = (
+                    trigger_value(&gamepad, Button::LeftTrigger2),
+                    trigger_value(&gamepad, Button::RightTrigger2),
+                );
+
+                let settings = input.analog_settings_mut(&gamepad);
+                let mut changed = self.calibration_unsaved;
+
+                ui.horizontal(|ui| {
+                    changed |= draw_stick_calibration(
+                        ui,
+                        "Left Stick",
+                        &mut settings.left_stick,
+                        left_stick,
+                    );
+                    changed |= draw_stick_calibration(
+                        ui,
+                        "Right Stick",
+                        &mut settings.right_stick,
+                        right_stick,
+                    );
                 });
+
+                changed |= draw_trigger_calibration(ui, &mut settings.triggers, triggers);
+
+                self.calibration_unsaved = changed && ui.input().pointer.any_down();
+                if changed && !self.calibration_unsaved {
+                    input.save_gamepad_profile(&gamepad);
+                }
+            }
+            None => {
+                ui.label("Gamepad is disconnected.");
+            }
+        }
+
+        if ui.button("Done").clicked() {
+            self.calibrating_player = None;
+        }
+    }
+
+    fn draw_rebinding(
+        &mut self,
+        ui: &mut Ui,
+        player_id: usize,
+        input_mode: &InputMode,
+        input: &mut LocalInputManager,
+        gilrs: &mut Gilrs,
+        can_add_players: bool,
+    ) {
+        // Gamepad buttons are captured here, keys are captured from
+        // the window events via try_capture_key
+        if let (Some(RebindTarget::Input(target)), InputMode::Gamepad(gamepad_id)) =
+            (self.awaiting_input, input_mode)
+        {
+            while let Some(event) = gilrs.next_event() {
+                match event.event {
+                    EventType::ButtonPressed(button, _)
+                        if event.id == *gamepad_id && button != Button::Unknown =>
+                    {
+                        input.rebind_gamepad_button(&gilrs.gamepad(event.id), button, target);
+                        self.awaiting_input = None;
+                        break;
+                    }
+                    _ => input.handle_gamepad_event(&event, gilrs, can_add_players),
+                }
+            }
+        }
+
+        ui.label(format!("Player {} Bindings:", player_id));
+
+        Grid::new("rebinding_grid").striped(true).show(ui, |ui| {
+            KeyType::ALL.iter().for_each(|target| {
+                ui.label(input.input_label(*target));
+                ui.label(input.bound_inputs_text(input_mode, *target, gilrs));
+
+                let button_text = if self.awaiting_input == Some(RebindTarget::Input(*target)) {
+                    "Press any key... (Esc to cancel)"
+                } else {
+                    "Set"
+                };
+
+                if ui.button(button_text).clicked() {
+                    self.awaiting_input = Some(RebindTarget::Input(*target));
+                }
+
+                // Keys bound to analog inputs can deflect them partially
+                if let (
+                    InputMode::Emulated(keyboard_id),
+                    KeyType::AnalogStick(_) | KeyType::Trigger(_),
+                ) = (input_mode, target)
+                {
+                    let keys = input.keyboard_bindings.keys_for(keyboard_id.0, *target);
+                    if let Some(key) = keys.first() {
+                        let settings = input.keyboard_bindings.analog_settings_mut(keyboard_id.0);
+                        let mut magnitude = settings.magnitude(*key);
+
+                        if ui
+                            .add(Slider::new(&mut magnitude, 0.0..=1.0).text("Magnitude"))
+                            .changed()
+                        {
+                            settings.magnitudes.insert(*key, magnitude);
+                            input.save_keyboard_profile();
+                        }
+                    }
+                }
+                ui.end_row();
+            });
+        });
+
+        if let InputMode::Emulated(keyboard_id) = input_mode {
+            self.draw_analog_emulation(ui, *keyboard_id, input);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Reset to Defaults").clicked() {
+                input.reset_bindings(input_mode, gilrs);
+                self.awaiting_input = None;
+            }
+
+            if ui.button("Done").clicked() {
+                self.rebinding_player = None;
+                self.awaiting_input = None;
+            }
         });
     }
+
+    fn draw_analog_emulation(
+        &mut self,
+        ui: &mut Ui,
+        keyboard_id: LocalKeyboardId,
+        input: &mut LocalInputManager,
+    ) {
+        ui.label("Analog Emulation:");
+
+        let settings = input.keyboard_bindings.analog_settings_mut(keyboard_id.0);
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            let modifier_text = match settings.walk_modifier {
+                Some(key) => format!("{:?}", key),
+                None => "Unbound".to_string(),
+            };
+            ui.label(format!("Walk Modifier: {}", modifier_text));
+
+            let button_text = if self.awaiting_input == Some(RebindTarget::WalkModifier) {
+                "Press any key... (Esc to cancel)"
+            } else {
+                "Set"
+            };
+
+            if ui.button(button_text).clicked() {
+                self.awaiting_input = Some(RebindTarget::WalkModifier);
+            }
+
+            if ui.button("Clear").clicked() {
+                settings.walk_modifier = None;
+                changed = true;
+            }
+        });
+
+        changed |= ui
+            .add(Slider::new(&mut settings.walk_scale, 0.0..=1.0).text("Walk Scale"))
+            .changed();
+        changed |= ui
+            .add(
+                Slider::new(
+                    &mut settings.attack_time,
+                    0.0..=KeyboardAnalogSettings::MAX_RAMP_TIME,
+                )
+                .text("Attack Time (s)"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                Slider::new(
+                    &mut settings.release_time,
+                    0.0..=KeyboardAnalogSettings::MAX_RAMP_TIME,
+                )
+                .text("Release Time (s)"),
+            )
+            .changed();
+        changed |= ui
+            .checkbox(&mut settings.normalize_diagonals, "Normalize Diagonals")
+            .changed();
+
+        if changed {
+            input.save_keyboard_profile();
+        }
+    }
+
+    /// Assigns the pressed key to the input waiting to be rebound.
+    /// Returns true if the key press was consumed.
+    pub(crate) fn try_capture_key(
+        &mut self,
+        key: VirtualKeyCode,
+        input: &mut LocalInputManager,
+    ) -> bool {
+        let (target, input_mode) = match (
+            self.awaiting_input,
+            self.rebinding_player
+                .and_then(|player_id| input.player_bindings.get(player_id)),
+        ) {
+            (Some(target), Some(input_mode)) => (target, input_mode.clone()),
+            _ => return false,
+        };
+
+        if key == VirtualKeyCode::Escape {
+            self.awaiting_input = None;
+            return true;
+        }
+
+        match (target, input_mode) {
+            (RebindTarget::Input(target), InputMode::Emulated(keyboard_id)) => {
+                input.rebind_key(keyboard_id, key, target);
+            }
+            (RebindTarget::WalkModifier, InputMode::Emulated(keyboard_id)) => {
+                input
+                    .keyboard_bindings
+                    .analog_settings_mut(keyboard_id.0)
+                    .walk_modifier = Some(key);
+                input.save_keyboard_profile();
+            }
+            // Keep waiting for a gamepad button
+            (_, InputMode::Gamepad(_)) => return true,
+        }
+
+        self.awaiting_input = None;
+        true
+    }
+}
+
+/// Lets players choose which console buttons trigger each of the game's actions.
+fn draw_action_bindings(ui: &mut Ui, input: &mut LocalInputManager) {
+    ui.label("Game Actions:");
+
+    let bindings = &mut input.action_bindings;
+    let mut changed = false;
+    let mut toggled = None;
+
+    Grid::new("action_grid").striped(true).show(ui, |ui| {
+        bindings
+            .actions()
+            .iter()
+            .enumerate()
+            .for_each(|(action_id, action)| {
+                let bound = bindings.buttons_for(action);
+                let bound_text = if bound.is_empty() {
+                    "Unbound".to_string()
+                } else {
+                    bound
+                        .iter()
+                        .map(|button| format!("{:?}", button))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+
+                ui.label(&action.name);
+                ComboBox::from_id_source(("action_buttons", action_id))
+                    .selected_text(bound_text)
+                    .show_ui(ui, |ui| {
+                        ButtonCode::ALL.iter().for_each(|button| {
+                            if ui
+                                .selectable_label(bound.contains(button), format!("{:?}", button))
+                                .clicked()
+                            {
+                                toggled = Some((action_id, *button));
+                            }
+                        });
+                    });
+                ui.end_row();
+            });
+    });
+
+    if let Some((action_id, button)) = toggled {
+        bindings.toggle(action_id, button);
+        changed = true;
+    }
+
+    if ui.button("Reset Actions to Defaults").clicked() {
+        bindings.reset();
+        changed = true;
+    }
+
+    if changed {
+        bindings.save();
+    }
+}
+
+fn trigger_value(gamepad: &Gamepad, button: Button) -> f32 {
+    gamepad
+        .button_data(button)
+        .map(|data| data.value())
+        .unwrap_or_default()
+}
+
+fn draw_curve_combo(ui: &mut Ui, label: &str, curve: &mut ResponseCurve) -> bool {
+    let mut changed = false;
+
+    ComboBox::from_label(label)
+        .selected_text(format!("{:?}", curve))
+        .show_ui(ui, |ui| {
+            ResponseCurve::ALL.iter().for_each(|option| {
+                changed |= ui
+                    .selectable_value(curve, *option, format!("{:?}", option))
+                    .changed();
+            });
+        });
+
+    changed
+}
+
+/// Draws the live stick position along with its settings. The gray dot is the
+/// raw value reported by the device, and the green dot is the value the game receives.
+/// Returns true if any of the settings were changed.
+fn draw_stick_calibration(
+    ui: &mut Ui,
+    label: &str,
+    settings: &mut StickSettings,
+    (raw_x, raw_y): (f32, f32),
+) -> bool {
+    ui.vertical(|ui| {
+        let (x, y) = settings.apply(raw_x, raw_y);
+
+        ui.label(label);
+
+        let size = 100.0;
+        let (response, painter) = ui.allocate_painter(Vec2::splat(size), Sense::hover());
+        let center = response.rect.center();
+        let radius = size / 2.0 - 4.0;
+        let stroke = ui.visuals().widgets.noninteractive.fg_stroke;
+        let deadzone_radius = radius * settings.deadzone;
+
+        painter.circle_stroke(center, radius, stroke);
+        match settings.deadzone_shape {
+            DeadzoneShape::Radial => {
+                painter.circle_stroke(center, deadzone_radius, (1.0, Color32::DARK_GRAY))
+            }
+            DeadzoneShape::Axial => {
+                painter.rect_stroke(
+                    Rect::from_center_size(center, Vec2::new(deadzone_radius * 2.0, size)),
+                    0.0,
+                    (1.0, Color32::DARK_GRAY),
+                );
+                painter.rect_stroke(
+                    Rect::from_center_size(center, Vec2::new(size, deadzone_radius * 2.0)),
+                    0.0,
+                    (1.0, Color32::DARK_GRAY),
+                );
+            }
+        }
+
+        // Screen space y points down, but stick y points up
+        painter.circle_filled(
+            center + Vec2::new(raw_x, -raw_y).clamp(Vec2::splat(-1.0), Vec2::splat(1.0)) * radius,
+            3.0,
+            Color32::GRAY,
+        );
+        painter.circle_filled(
+            center + Vec2::new(x, -y) * radius,
+            4.0,
+            Color32::LIGHT_GREEN,
+        );
+
+        ui.label(format!("Raw: {:.2}, {:.2}", raw_x, raw_y));
+        ui.label(format!("Output: {:.2}, {:.2}", x, y));
+
+        let mut changed = ui
+            .add(
+                Slider::new(&mut settings.deadzone, 0.0..=StickSettings::MAX_DEADZONE)
+                    .text("Deadzone"),
+            )
+            .changed();
+
+        ComboBox::from_label(format!("{} Deadzone Shape", label))
+            .selected_text(format!("{:?}", settings.deadzone_shape))
+            .show_ui(ui, |ui| {
+                [DeadzoneShape::Radial, DeadzoneShape::Axial]
+                    .iter()
+                    .for_each(|shape| {
+                        changed |= ui
+                            .selectable_value(
+                                &mut settings.deadzone_shape,
+                                *shape,
+                                format!("{:?}", shape),
+                            )
+                            .changed();
+                    });
+            });
+
+        changed |= draw_curve_combo(ui, &format!("{} Curve", label), &mut settings.curve);
+        changed |= ui.checkbox(&mut settings.invert_x, "Invert X").changed();
+        changed |= ui.checkbox(&mut settings.invert_y, "Invert Y").changed();
+
+        changed
+    })
+    .inner
+}
+
+/// Draws the live trigger values along with their settings.
+/// Returns true if any of the settings were changed.
+fn draw_trigger_calibration(
+    ui: &mut Ui,
+    settings: &mut TriggerSettings,
+    (raw_left, raw_right): (f32, f32),
+) -> bool {
+    ui.label("Triggers");
+
+    [("Left", raw_left), ("Right", raw_right)]
+        .iter()
+        .for_each(|(side, raw)| {
+            let value = settings.apply(*raw);
+            ui.add(
+                ProgressBar::new(value)
+                    .text(format!("{} Trigger: {:.2} (Raw: {:.2})", side, value, raw)),
+            );
+        });
+
+    let mut changed = ui
+        .add(
+            Slider::new(&mut settings.deadzone, 0.0..=TriggerSettings::MAX_DEADZONE)
+                .text("Trigger Deadzone"),
+        )
+        .changed();
+    changed |= draw_curve_combo(ui, "Trigger Curve", &mut settings.curve);
+
+    changed
 }
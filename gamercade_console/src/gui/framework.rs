@@ -1,11 +1,10 @@
 use egui::{ClippedMesh, Context, TexturesDelta};
 use egui_wgpu_backend::{BackendError, RenderPass, ScreenDescriptor};
-use ggrs::P2PSession;
 use gilrs::Gilrs;
 use pixels::{wgpu, Pixels, PixelsContext};
 use winit::window::Window;
 
-use crate::console::{LocalInputManager, WasmConsole};
+use crate::console::{GameSession, LocalInputManager};
 
 use super::Gui;
 
@@ -77,7 +76,7 @@ impl Framework {
     pub(crate) fn prepare(
         &mut self,
         pixels: &mut Pixels,
-        session: &mut Option<P2PSession<WasmConsole>>,
+        session: &mut Option<GameSession>,
         window: &Window,
         input: &mut LocalInputManager,
         gilrs: &mut Gilrs,
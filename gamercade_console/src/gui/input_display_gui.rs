@@ -0,0 +1,366 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    time::{Duration, Instant},
+};
+
+use egui::{Color32, Context, Painter, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+use gamercade_core::{AnalogStick, ButtonCode, InputState};
+use ggrs::InputStatus;
+
+use crate::console::{PlayerInputEntry, WasmConsole};
+
+/// The number of latency results which are averaged together.
+const LATENCY_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Copy)]
+enum LatencyTestState {
+    Idle,
+    /// Waiting for the next button press.
+    Armed,
+    /// Waiting for the screen to change from how it looked when the button was pressed.
+    Waiting {
+        pressed_at: Instant,
+        baseline: u64,
+    },
+    /// The screen changed, waiting for the frame to be presented.
+    Presenting {
+        pressed_at: Instant,
+    },
+}
+
+/// Measures the time from pressing a local button until the changed frame is
+/// presented. Only meaningful with a game which draws the same frame until a button
+/// is pressed, such as one which flashes the screen while A is held. The display's
+/// own latency, after the frame is handed off to it, isn't included.
+#[derive(Debug)]
+pub struct LatencyTest {
+    state: LatencyTestState,
+    held: bool,
+    last_frame: u64,
+    results: Vec<Duration>,
+}
+
+impl Default for LatencyTest {
+    fn default() -> Self {
+        Self {
+            state: LatencyTestState::Idle,
+            held: false,
+            last_frame: 0,
+            results: Vec::new(),
+        }
+    }
+}
+
+impl LatencyTest {
+    /// Called when any key or gamepad button is pressed. Key repeats
+    /// are ignored until the button is released.
+    pub fn input_pressed(&mut self) {
+        if let (LatencyTestState::Armed, false) = (self.state, self.held) {
+            self.state = LatencyTestState::Waiting {
+                pressed_at: Instant::now(),
+                baseline: self.last_frame,
+            };
+        }
+        self.held = true;
+    }
+
+    pub fn input_released(&mut self) {
+        self.held = false;
+    }
+
+    /// Called each time the game is drawn into the frame buffer.
+    pub fn frame_drawn(&mut self, frame: &[u8]) {
+        if let LatencyTestState::Idle = self.state {
+            return;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        frame.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if let LatencyTestState::Waiting {
+            pressed_at,
+            baseline,
+        } = self.state
+        {
+            if hash != baseline {
+                self.state = LatencyTestState::Presenting { pressed_at };
+            }
+        }
+
+        self.last_frame = hash;
+    }
+
+    /// Called once the frame has been rendered and presented to the window.
+    pub fn frame_presented(&mut self) {
+        if let LatencyTestState::Presenting { pressed_at } = self.state {
+            if self.results.len() == LATENCY_SAMPLES {
+                self.results.remove(0);
+            }
+            self.results.push(pressed_at.elapsed());
+            self.state = LatencyTestState::Armed;
+        }
+    }
+
+    fn average(&self) -> Option<Duration> {
+        let total = self.results.iter().sum::<Duration>();
+        Some(
+            total
+                / u32::try_from(self.results.len())
+                    .ok()
+                    .filter(|len| *len > 0)?,
+        )
+    }
+
+    fn draw(&mut self, ui: &mut Ui) {
+        ui.group(|ui| {
+            ui.label("Input Latency Test:");
+            ui.label("Launch a game which only changes the screen when a button is pressed, then press any button.");
+
+            ui.horizontal(|ui| match self.state {
+                LatencyTestState::Idle => {
+                    if ui.button("Start").clicked() {
+                        self.state = LatencyTestState::Armed;
+                    }
+                }
+                LatencyTestState::Armed
+                | LatencyTestState::Waiting { .. }
+                | LatencyTestState::Presenting { .. } => {
+                    if ui.button("Stop").clicked() {
+                        self.state = LatencyTestState::Idle;
+                    }
+                    if let LatencyTestState::Armed = self.state {
+                        ui.label("Waiting for a button press...");
+                    } else {
+                        ui.label("Waiting for the screen to change...");
+                    }
+                }
+            });
+
+            if let (Some(last), Some(average)) = (self.results.last(), self.average()) {
+                ui.label(format!(
+                    "Last: {:.1}ms, Average: {:.1}ms over {} presses",
+                    last.as_secs_f64() * 1000.0,
+                    average.as_secs_f64() * 1000.0,
+                    self.results.len()
+                ));
+
+                if ui.button("Clear Results").clicked() {
+                    self.results.clear();
+                }
+            }
+        });
+    }
+}
+
+/// Shows each player's inputs as a virtual controller, for diagnosing netplay issues.
+#[derive(Debug, Default)]
+pub struct InputDisplayGui {
+    pub open: bool,
+    pub latency_test: LatencyTest,
+}
+
+impl InputDisplayGui {
+    pub(crate) fn draw(&mut self, ctx: &Context, console: Option<&WasmConsole>) {
+        let mut open = self.open;
+
+        egui::Window::new("Input Display")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                match console {
+                    Some(console) => {
+                        let statuses = console.input_statuses();
+                        console.input_entries().iter().enumerate().for_each(
+                            |(player_id, entry)| {
+                                let status = statuses
+                                    .get(player_id)
+                                    .copied()
+                                    .unwrap_or(InputStatus::Confirmed);
+                                draw_player(ui, player_id, entry, status);
+                            },
+                        );
+                    }
+                    None => {
+                        ui.label("No game is running.");
+                    }
+                }
+
+                self.latency_test.draw(ui);
+            });
+
+        self.open = open;
+    }
+}
+
+fn draw_player(ui: &mut Ui, player_id: usize, entry: &PlayerInputEntry, status: InputStatus) {
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!("Player {}", player_id));
+
+            let (text, color) = match status {
+                InputStatus::Confirmed => ("Confirmed", Color32::LIGHT_GREEN),
+                InputStatus::Predicted => ("Predicted", Color32::YELLOW),
+                InputStatus::Disconnected => ("Disconnected", Color32::LIGHT_RED),
+            };
+            ui.colored_label(color, text);
+        });
+
+        // Predicted inputs are drawn faded, as they may be rolled back
+        let tint = match status {
+            InputStatus::Confirmed => Color32::LIGHT_GREEN,
+            _ => Color32::from_rgb(128, 128, 64),
+        };
+
+        draw_controller(ui, entry.current, tint);
+        draw_mouse(ui, entry);
+    });
+}
+
+/// Draws a button as a circle, filled while it's held.
+fn draw_button(painter: &Painter, center: Pos2, radius: f32, held: bool, tint: Color32) {
+    if held {
+        painter.circle_filled(center, radius, tint);
+    }
+    painter.circle_stroke(center, radius, Stroke::new(1.0, Color32::GRAY));
+}
+
+fn draw_stick(painter: &Painter, center: Pos2, stick: AnalogStick, held: bool, tint: Color32) {
+    let radius = 16.0;
+    painter.circle_stroke(center, radius, Stroke::new(1.0, Color32::GRAY));
+
+    // Screen space y points down, but stick y points up
+    let position = center + Vec2::new(stick.get_x_axis(), -stick.get_y_axis()) * radius;
+    draw_button(painter, position, 5.0, held, tint);
+}
+
+fn draw_trigger(painter: &Painter, rect: Rect, value: f32, tint: Color32) {
+    let top = rect.bottom() - rect.height() * value.clamp(0.0, 1.0);
+    let filled = Rect::from_min_max(Pos2::new(rect.left(), top), rect.max);
+    painter.rect_filled(filled, 0.0, tint);
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::GRAY));
+}
+
+fn draw_controller(ui: &mut Ui, input: InputState, tint: Color32) {
+    let (response, painter) = ui.allocate_painter(Vec2::new(220.0, 100.0), Sense::hover());
+    let origin = response.rect.min;
+    let at = |x: f32, y: f32| origin + Vec2::new(x, y);
+    let held = |code: ButtonCode| input.buttons.get_button_state(code);
+
+    // Triggers and shoulders
+    draw_trigger(
+        &painter,
+        Rect::from_min_size(at(4.0, 4.0), Vec2::new(12.0, 24.0)),
+        input.left_trigger.get_value(),
+        tint,
+    );
+    draw_trigger(
+        &painter,
+        Rect::from_min_size(at(204.0, 4.0), Vec2::new(12.0, 24.0)),
+        input.right_trigger.get_value(),
+        tint,
+    );
+    draw_button(
+        &painter,
+        at(32.0, 12.0),
+        6.0,
+        held(ButtonCode::LeftShoulder),
+        tint,
+    );
+    draw_button(
+        &painter,
+        at(188.0, 12.0),
+        6.0,
+        held(ButtonCode::RightShoulder),
+        tint,
+    );
+
+    // DPad
+    draw_button(&painter, at(40.0, 40.0), 6.0, held(ButtonCode::Up), tint);
+    draw_button(&painter, at(40.0, 64.0), 6.0, held(ButtonCode::Down), tint);
+    draw_button(&painter, at(28.0, 52.0), 6.0, held(ButtonCode::Left), tint);
+    draw_button(&painter, at(52.0, 52.0), 6.0, held(ButtonCode::Right), tint);
+
+    // Face buttons
+    draw_button(
+        &painter,
+        at(180.0, 64.0),
+        7.0,
+        held(ButtonCode::ButtonA),
+        tint,
+    );
+    draw_button(
+        &painter,
+        at(196.0, 50.0),
+        7.0,
+        held(ButtonCode::ButtonB),
+        tint,
+    );
+    draw_button(
+        &painter,
+        at(164.0, 50.0),
+        7.0,
+        held(ButtonCode::ButtonC),
+        tint,
+    );
+    draw_button(
+        &painter,
+        at(180.0, 36.0),
+        7.0,
+        held(ButtonCode::ButtonD),
+        tint,
+    );
+
+    // Start and select
+    draw_button(
+        &painter,
+        at(96.0, 30.0),
+        5.0,
+        held(ButtonCode::Select),
+        tint,
+    );
+    draw_button(
+        &painter,
+        at(124.0, 30.0),
+        5.0,
+        held(ButtonCode::Start),
+        tint,
+    );
+
+    // Sticks
+    draw_stick(
+        &painter,
+        at(80.0, 74.0),
+        input.left_stick,
+        held(ButtonCode::LeftStick),
+        tint,
+    );
+    draw_stick(
+        &painter,
+        at(140.0, 74.0),
+        input.right_stick,
+        held(ButtonCode::RightStick),
+        tint,
+    );
+}
+
+fn draw_mouse(ui: &mut Ui, entry: &PlayerInputEntry) {
+    let mouse = entry.current_mouse;
+    let button = |held: bool| if held { "X" } else { "-" };
+
+    ui.label(format!(
+        "Mouse: ({}, {}) Delta: ({:.2}, {:.2}) Wheel: ({}, {})",
+        mouse.get_x_pos(),
+        mouse.get_y_pos(),
+        entry.current_motion.get_x(),
+        entry.current_motion.get_y(),
+        mouse.get_wheel_x(),
+        mouse.get_wheel_y(),
+    ));
+    ui.label(format!(
+        "Left: {} Middle: {} Right: {}",
+        button(mouse.get_left_button_down()),
+        button(mouse.get_middle_button_down()),
+        button(mouse.get_right_button_down()),
+    ));
+}
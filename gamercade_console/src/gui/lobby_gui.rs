@@ -0,0 +1,315 @@
+use std::net::{SocketAddr, ToSocketAddrs};
+
+use egui::{Color32, Slider, Ui};
+use gamercade_lobby::{
+    ClientMessage, GameStart, LobbyClient, PlayerInfo, RoomInfo, ServerMessage, DEFAULT_LOBBY_PORT,
+    MAX_ROOM_PLAYERS,
+};
+use ggrs::PlayerType;
+
+use crate::console::{order_players, NetworkInstance};
+
+/// Finds other consoles through a lobby server. Hosts create rooms, which
+/// others can join, and the server assigns the player order and seed.
+pub struct LobbyGui {
+    pub(crate) server_addr: String,
+    pub(crate) player_name: String,
+    pub(crate) port: String,
+    pub(crate) room_name: String,
+    pub(crate) max_players: usize,
+
+    client: Option<LobbyClient>,
+    rooms: Vec<RoomInfo>,
+
+    /// The room we are a member of
+    room: Option<RoomInfo>,
+    hosting: bool,
+
+    /// The most recent game started by the lobby
+    game_start: Option<GameStart>,
+    launch_pending: bool,
+
+    /// The last error, shown until the next action
+    status: Option<String>,
+}
+
+impl Default for LobbyGui {
+    fn default() -> Self {
+        Self {
+            server_addr: format!("127.0.0.1:{}", DEFAULT_LOBBY_PORT),
+            player_name: "Player".to_string(),
+            port: Default::default(),
+            room_name: Default::default(),
+            max_players: 2,
+            client: None,
+            rooms: Vec::new(),
+            room: None,
+            hosting: false,
+            game_start: None,
+            launch_pending: false,
+            status: None,
+        }
+    }
+}
+
+impl LobbyGui {
+    pub(crate) fn draw(&mut self, ui: &mut Ui, game_name: &str, local_player_count: usize) {
+        self.poll();
+
+        if self.client.is_none() {
+            ui.horizontal(|ui| {
+                ui.label("Lobby Server:");
+                ui.text_edit_singleline(&mut self.server_addr);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Player Name:");
+                ui.text_edit_singleline(&mut self.player_name);
+            });
+
+            if ui.button("Connect").clicked() {
+                self.connect();
+            }
+        } else if let Some(room) = self.room.clone() {
+            self.draw_room(ui, &room, game_name);
+        } else {
+            self.draw_browser(ui, game_name, local_player_count);
+        }
+
+        if let Some(status) = &self.status {
+            ui.colored_label(Color32::RED, status);
+        }
+    }
+
+    fn draw_browser(&mut self, ui: &mut Ui, game_name: &str, local_player_count: usize) {
+        ui.horizontal(|ui| {
+            ui.label("Local Port: ");
+            ui.text_edit_singleline(&mut self.port);
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Rooms:");
+            if ui.button("Refresh").clicked() {
+                self.send(ClientMessage::ListRooms);
+            }
+            if ui.button("Disconnect").clicked() {
+                self.client = None;
+            }
+        });
+
+        if self.rooms.is_empty() {
+            ui.label("No rooms are open.");
+        }
+
+        let mut join = None;
+        self.rooms.iter().for_each(|room| {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} - {} ({}/{})",
+                    room.name,
+                    room.game,
+                    room.player_count(),
+                    room.max_players
+                ));
+                if ui.button("Join").clicked() {
+                    join = Some(room.id);
+                }
+                if room.game != game_name {
+                    ui.colored_label(Color32::YELLOW, "Different game");
+                }
+            });
+        });
+
+        if let Some(room) = join {
+            if let Some(player) = self.player_info(local_player_count) {
+                self.hosting = false;
+                self.send(ClientMessage::JoinRoom { room, player });
+            }
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("Room Name:");
+            ui.text_edit_singleline(&mut self.room_name);
+        });
+        ui.add(Slider::new(&mut self.max_players, 2..=MAX_ROOM_PLAYERS).text("Max Players"));
+
+        if ui
+            .add_enabled(!game_name.is_empty(), egui::Button::new("Create Room"))
+            .clicked()
+        {
+            if let Some(player) = self.player_info(local_player_count) {
+                self.hosting = true;
+                self.send(ClientMessage::CreateRoom {
+                    name: self.room_name.clone(),
+                    game: game_name.to_string(),
+                    max_players: self.max_players,
+                    player,
+                });
+            }
+        }
+    }
+
+    fn draw_room(&mut self, ui: &mut Ui, room: &RoomInfo, game_name: &str) {
+        ui.label(format!(
+            "Room: {} - {} ({}/{})",
+            room.name,
+            room.game,
+            room.player_count(),
+            room.max_players
+        ));
+        if room.game != game_name {
+            ui.colored_label(
+                Color32::YELLOW,
+                "Select the same game as the host before the game starts.",
+            );
+        }
+
+        room.members.iter().enumerate().for_each(|(index, member)| {
+            let host = if index == 0 { " (Host)" } else { "" };
+            ui.label(format!(
+                "{}{}: {} player(s)",
+                member.name, host, member.local_players
+            ));
+        });
+
+        ui.horizontal(|ui| {
+            if self.hosting
+                && ui
+                    .add_enabled(room.members.len() > 1, egui::Button::new("Start Game"))
+                    .clicked()
+            {
+                self.send(ClientMessage::StartGame);
+            }
+
+            if ui.button("Leave Room").clicked() {
+                self.send(ClientMessage::LeaveRoom);
+            }
+        });
+
+        if !self.hosting {
+            ui.label("Waiting for the host to start the game...");
+        }
+    }
+
+    fn connect(&mut self) {
+        let addr = match self
+            .server_addr
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+        {
+            Some(addr) => addr,
+            None => {
+                self.status = Some("Lobby Server address is invalid".to_string());
+                return;
+            }
+        };
+
+        match LobbyClient::connect(addr) {
+            Ok(client) => {
+                println!("Connected to lobby server at {}", addr);
+                self.client = Some(client);
+                self.status = None;
+                self.send(ClientMessage::ListRooms);
+            }
+            Err(e) => self.status = Some(format!("Unable to connect to {}: {}", addr, e)),
+        }
+    }
+
+    fn player_info(&mut self, local_player_count: usize) -> Option<PlayerInfo> {
+        match self.port.parse::<u16>() {
+            Ok(game_port) => Some(PlayerInfo {
+                name: self.player_name.clone(),
+                game_port,
+                local_players: local_player_count,
+            }),
+            Err(_) => {
+                self.status = Some("Port is invalid".to_string());
+                None
+            }
+        }
+    }
+
+    fn send(&mut self, message: ClientMessage) {
+        self.status = None;
+        if let Some(client) = &mut self.client {
+            if let Err(e) = client.send(&message) {
+                self.status = Some(format!("Lost connection to the lobby server: {}", e));
+            }
+        }
+    }
+
+    /// Handles any messages from the lobby server.
+    fn poll(&mut self) {
+        let client = match &mut self.client {
+            Some(client) => client,
+            None => return,
+        };
+
+        for message in client.poll() {
+            match message {
+                ServerMessage::Welcome => (),
+                ServerMessage::RoomList(rooms) => self.rooms = rooms,
+                ServerMessage::RoomUpdated(room) => self.room = Some(room),
+                ServerMessage::RoomClosed => {
+                    self.room = None;
+                    self.send(ClientMessage::ListRooms);
+                }
+                ServerMessage::GameStarted(game_start) => {
+                    println!("Lobby started the game with seed {:x}", game_start.seed);
+                    self.room = None;
+                    self.game_start = Some(game_start);
+                    self.launch_pending = true;
+                }
+                ServerMessage::Error(error) => self.status = Some(error),
+            }
+        }
+
+        if !self.client.as_ref().unwrap().is_connected() {
+            self.client = None;
+            self.room = None;
+            self.status = Some("Disconnected from the lobby server".to_string());
+        }
+    }
+
+    /// Returns the seed of a newly started game, once.
+    pub(crate) fn take_launch(&mut self) -> Option<u64> {
+        if std::mem::take(&mut self.launch_pending) {
+            self.game_start.as_ref().map(|game_start| game_start.seed)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the player types in the order assigned by the
+    /// lobby, along with the local port to play on.
+    pub(crate) fn players(&self) -> Option<(Vec<PlayerType<SocketAddr>>, u16)> {
+        let game_start = self.game_start.as_ref()?;
+
+        // The lobby's order is used as the instance ids
+        let instances = game_start
+            .peers
+            .iter()
+            .enumerate()
+            .map(|(index, peer)| NetworkInstance {
+                instance_id: index,
+                addr: (index != game_start.local_index).then_some(peer.addr),
+                player_count: peer.local_players,
+            })
+            .collect::<Vec<_>>();
+
+        match order_players(&instances) {
+            Ok(player_types) => {
+                let port = game_start.peers[game_start.local_index].addr.port();
+                Some((player_types, port))
+            }
+            Err(e) => {
+                println!("{}", e);
+                None
+            }
+        }
+    }
+}
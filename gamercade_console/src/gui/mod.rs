@@ -1,23 +1,38 @@
-use std::{net::SocketAddr, path::PathBuf};
+use std::{path::PathBuf, sync::Arc};
 
-use egui::{Button, Context};
+use egui::{Align2, Area, Button, Context, Frame};
 
 use gamercade_fs::Rom;
-use ggrs::{P2PSession, PlayerType, SessionBuilder, SessionState, UdpNonBlockingSocket};
+use ggrs::{PlayerType, SessionState};
 use gilrs::Gilrs;
+use parking_lot::Mutex;
 use pixels::Pixels;
 use rfd::FileDialog;
 use winit::{dpi::PhysicalSize, window::Window};
 
 use crate::{
-    console::{LocalInputManager, SessionDescriptor, WasmConsole, WasmConsoleState},
+    console::{
+        rom_hash, DesyncDetector, GameSession, Handshake, HandshakeMessage, HandshakeStatus,
+        LocalInputManager, SessionDescriptor, SessionMode, WasmConsole, WasmConsoleState,
+    },
+    console_config::ConsoleConfig,
     DEFAULT_WINDOW_RESOLUTION,
 };
 
-use self::{controller_gui::ControllerGui, play_mode_gui::PlayModeGui};
+use self::{
+    connection_gui::ConnectionGui, controller_gui::ControllerGui,
+    input_display_gui::InputDisplayGui, network_simulator_gui::NetworkSimulatorGui,
+    network_stats_gui::NetworkStatsGui, play_mode_gui::PlayModeGui, window_gui::WindowGui,
+};
+pub mod connection_gui;
 pub mod controller_gui;
 pub mod framework;
+pub mod input_display_gui;
+pub mod lobby_gui;
+pub mod network_simulator_gui;
+pub mod network_stats_gui;
 pub mod play_mode_gui;
+pub mod window_gui;
 
 pub struct Gui {
     pub window_open: bool,
@@ -28,12 +43,36 @@ pub struct Gui {
     pub wasm_console: Option<WasmConsole>,
     pub initial_state: Option<WasmConsoleState>,
 
+    /// The first frame which didn't match when resimulated, during a SyncTest session
+    pub sync_test_failure: Option<ggrs::Frame>,
+
+    /// A session waiting for the handshake with its peers to complete
+    pub(crate) pending_launch: Option<PendingLaunch>,
+
+    /// Why the last session couldn't be started
+    pub launch_error: Option<String>,
+
     pub play_mode_gui: PlayModeGui,
     pub controller_gui: ControllerGui,
+    pub window_gui: WindowGui,
+    pub input_display_gui: InputDisplayGui,
+    pub network_simulator_gui: NetworkSimulatorGui,
+    pub network_stats_gui: NetworkStatsGui,
+    pub connection_gui: ConnectionGui,
+
+    pub config: ConsoleConfig,
 }
 
 const DEFAULT_SEED: &str = "a12cade";
 
+/// Everything needed to start the session, once the handshake completes.
+pub(crate) struct PendingLaunch {
+    handshake: Handshake,
+    rom: Rom,
+    session_descriptor: SessionDescriptor,
+    mode: SessionMode,
+}
+
 impl Default for Gui {
     fn default() -> Self {
         Self {
@@ -43,9 +82,19 @@ impl Default for Gui {
 
             wasm_console: None,
             initial_state: None,
+            sync_test_failure: None,
+            pending_launch: None,
+            launch_error: None,
 
             play_mode_gui: PlayModeGui::default(),
             controller_gui: ControllerGui::default(),
+            window_gui: WindowGui::default(),
+            input_display_gui: InputDisplayGui::default(),
+            network_simulator_gui: NetworkSimulatorGui::default(),
+            network_stats_gui: NetworkStatsGui::default(),
+            connection_gui: ConnectionGui::default(),
+
+            config: ConsoleConfig::default(),
         }
     }
 }
@@ -55,11 +104,17 @@ impl Gui {
         &mut self,
         pixels: &mut Pixels,
         window: &Window,
-        session: &mut Option<P2PSession<WasmConsole>>,
+        session: &mut Option<GameSession>,
         ctx: &Context,
         input: &mut LocalInputManager,
         gilrs: &mut Gilrs,
     ) {
+        if session.is_none() {
+            if let Some(new_session) = self.poll_pending_launch(pixels, window, input) {
+                *session = Some(new_session);
+            }
+        }
+
         let mut is_open = self.window_open;
         egui::Window::new("Main Menu")
             .open(&mut is_open)
@@ -95,14 +150,50 @@ impl Gui {
                 self.controller_gui
                     .draw(ui, session.is_none(), input, gilrs);
 
-                self.play_mode_gui.draw(ui);
+                let game_name = self.game_name();
+                self.play_mode_gui
+                    .draw(ui, &game_name, self.controller_gui.local_player_count);
+
+                // Games started by the lobby launch straight away, with the lobby's seed
+                if let Some(seed) = self.play_mode_gui.lobby_gui.take_launch() {
+                    if self.game_file.is_none() {
+                        println!("Unable to launch the lobby's game: no game selected");
+                    } else if session.is_none() {
+                        self.seed = format!("{:x}", seed);
+                        *session = self.try_launch_game(pixels, window, input);
+                    }
+                }
+
+                ui.checkbox(&mut self.input_display_gui.open, "Show Input Display");
+                ui.checkbox(&mut self.network_stats_gui.open, "Show Network Stats");
+                self.network_simulator_gui.draw(ui);
+
+                let game_resolution = self
+                    .wasm_console
+                    .as_ref()
+                    .map(|console| (console.rom.width() as u32, console.rom.height() as u32));
+                if self.window_gui.draw(
+                    ui,
+                    &mut self.config.window,
+                    window,
+                    pixels,
+                    game_resolution,
+                ) {
+                    self.config.save();
+                }
 
                 let launch_game_text = if let Some(session) = session {
-                    if session.current_state() == SessionState::Synchronizing {
+                    if session.is_sync_test() {
+                        "Running SyncTest..."
+                    } else if session.current_state() == SessionState::Synchronizing {
                         "Waiting to establish connection..."
+                    } else if session.is_spectator() {
+                        "Spectating!"
                     } else {
                         "Connected!"
                     }
+                } else if self.pending_launch.is_some() {
+                    "Waiting for peers..."
                 } else {
                     "Launch Game"
                 };
@@ -111,11 +202,11 @@ impl Gui {
 
                 ui.horizontal(|ui| {
                     let launch_game = egui::Button::new(launch_game_text);
-                    if ui
-                        .add_enabled(self.game_file.is_some() && session.is_none(), launch_game)
-                        .clicked()
-                    {
-                        *session = self.try_launch_game(pixels, window);
+                    let can_launch = self.game_file.is_some()
+                        && session.is_none()
+                        && self.pending_launch.is_none();
+                    if ui.add_enabled(can_launch, launch_game).clicked() {
+                        *session = self.try_launch_game(pixels, window, input);
                     }
 
                     let buttons_enabled = self.game_file.is_some() && session.is_some();
@@ -129,14 +220,137 @@ impl Gui {
                     }
 
                     if ui
-                        .add_enabled(buttons_enabled, Button::new("Quit Game"))
+                        .add_enabled(
+                            buttons_enabled || self.pending_launch.is_some(),
+                            Button::new("Quit Game"),
+                        )
                         .clicked()
                     {
-                        self.wasm_console = None;
-                        *session = None;
+                        self.end_session(session, window, input, None);
                     }
                 });
+
+                if let Some(pending) = &self.pending_launch {
+                    let waiting_for = pending
+                        .handshake
+                        .waiting_for()
+                        .iter()
+                        .map(|addr| addr.to_string())
+                        .collect::<Vec<_>>();
+                    if !waiting_for.is_empty() {
+                        ui.label(format!("Waiting for: {}", waiting_for.join(", ")));
+                    }
+                }
             });
+
+        self.input_display_gui.draw(ctx, self.wasm_console.as_ref());
+        self.network_stats_gui
+            .draw(ctx, session.as_ref(), self.wasm_console.as_ref());
+
+        if self.connection_gui.draw(ctx) {
+            self.end_session(session, window, input, None);
+        }
+
+        if let Some(frame) = self.sync_test_failure {
+            Area::new("sync_test_notice")
+                .anchor(Align2::CENTER_BOTTOM, [0.0, -8.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(format!(
+                            "SyncTest failed: checksums didn't match after resimulating frame {}.",
+                            frame
+                        ));
+                        ui.label("The game isn't rollback safe. Check for state kept outside of exported memory.");
+                    });
+                });
+        }
+
+        if let Some(error) = &self.launch_error {
+            Area::new("launch_error_notice")
+                .anchor(Align2::CENTER_BOTTOM, [0.0, -8.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label("Unable to start the session:");
+                        ui.label(error);
+                    });
+                });
+        }
+
+        // Only the latest desync is shown, the rest are in the log
+        if let Some(desync) = self
+            .wasm_console
+            .as_ref()
+            .and_then(|console| console.desync_detector().desyncs().last().cloned())
+        {
+            Area::new("desync_notice")
+                .anchor(Align2::CENTER_BOTTOM, [0.0, -8.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(format!(
+                            "Desync detected with {} at frame {}.",
+                            desync.addr, desync.frame
+                        ));
+                        ui.label(format!(
+                            "Local checksum: {:016x}, Remote checksum: {:016x}",
+                            desync.local_checksum, desync.remote_checksum
+                        ));
+                        if let Some(dump_dir) = &desync.dump_dir {
+                            ui.label(format!("This machine's state was saved to {:?}.", dump_dir));
+                        }
+                    });
+                });
+        }
+
+        // Shown even while the menu is closed, so players notice mid game
+        let disconnected_players = input.disconnected_players(gilrs);
+        if !disconnected_players.is_empty() {
+            Area::new("controller_notice")
+                .anchor(Align2::CENTER_TOP, [0.0, 8.0])
+                .show(ctx, |ui| {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        disconnected_players.iter().for_each(|player_id| {
+                            ui.label(format!(
+                                "Player {}'s controller is disconnected. Reconnect it, or press Start on another gamepad.",
+                                player_id
+                            ));
+                        });
+                    });
+                });
+        }
+    }
+
+    /// Stops the running or pending session, and returns to the menu. The reason
+    /// is shown to the player, if the session ended because something went wrong.
+    pub(crate) fn end_session(
+        &mut self,
+        session: &mut Option<GameSession>,
+        window: &Window,
+        input: &mut LocalInputManager,
+        reason: Option<String>,
+    ) {
+        if let Some(reason) = &reason {
+            println!("Session ended: {}", reason);
+        }
+
+        self.wasm_console = None;
+        self.pending_launch = None;
+        *session = None;
+        input.stop_rumble();
+        self.connection_gui.reset(reason);
+        self.window_open = true;
+
+        // The game may have locked the mouse
+        let _ = window.set_cursor_grab(false);
+        window.set_cursor_visible(true);
+    }
+
+    /// The selected game's file name, without the extension.
+    fn game_name(&self) -> String {
+        self.game_file
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
     }
 
     /// Quickly launch a single player session, usually from the command line
@@ -146,7 +360,9 @@ impl Gui {
         seed: u64,
         pixels: &mut Pixels,
         window: &Window,
-    ) -> Option<P2PSession<WasmConsole>> {
+        input: &mut LocalInputManager,
+        mode: SessionMode,
+    ) -> Option<GameSession> {
         let rom = match Rom::try_load(&game_path) {
             Err(e) => {
                 println!("{}", e);
@@ -161,54 +377,184 @@ impl Gui {
             num_players: 1,
             player_types: vec![PlayerType::Local].into_boxed_slice(),
             port: 8000,
+            spectators: Box::new([]),
+            relay: None,
         };
 
-        Some(self.init_with_console(seed, rom, pixels, window, session_descriptor))
+        self.launch(seed, rom, pixels, window, input, session_descriptor, mode)
     }
 
-    fn init_with_console(
+    /// Starts a handshake with the session's peers. Local sessions don't have
+    /// any peers to wait for, so are started straight away.
+    #[allow(clippy::too_many_arguments)]
+    fn launch(
         &mut self,
         seed: u64,
         rom: Rom,
         pixels: &mut Pixels,
         window: &Window,
+        input: &mut LocalInputManager,
         session_descriptor: SessionDescriptor,
-    ) -> P2PSession<WasmConsole> {
-        pixels.resize_buffer(rom.width() as u32, rom.height() as u32);
-        window.set_inner_size(PhysicalSize::new(
-            rom.width().max(DEFAULT_WINDOW_RESOLUTION.width()),
-            rom.height().max(DEFAULT_WINDOW_RESOLUTION.height()),
-        ));
-
-        let (max_prediction, new_session) = {
-            let new_session = init_session(
-                &rom,
-                session_descriptor.port,
-                &session_descriptor.player_types,
+        mode: SessionMode,
+    ) -> Option<GameSession> {
+        self.launch_error = None;
+        self.connection_gui.reset(None);
+        let local = HandshakeMessage::new(rom_hash(&rom), seed);
+
+        // SyncTest sessions don't use the network at all
+        if let SessionMode::SyncTest { .. } = mode {
+            return self.init_with_console(
+                local,
+                rom,
+                pixels,
+                window,
+                input,
+                session_descriptor,
+                mode,
             );
-            (new_session.max_prediction(), new_session)
+        }
+
+        match Handshake::start(&session_descriptor, mode, local) {
+            Ok(handshake) => {
+                self.pending_launch = Some(PendingLaunch {
+                    handshake,
+                    rom,
+                    session_descriptor,
+                    mode,
+                });
+                self.poll_pending_launch(pixels, window, input)
+            }
+            Err(e) => {
+                println!("Unable to start session: {}", e);
+                self.launch_error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Starts the pending session, once every peer has agreed on the game and seed.
+    fn poll_pending_launch(
+        &mut self,
+        pixels: &mut Pixels,
+        window: &Window,
+        input: &mut LocalInputManager,
+    ) -> Option<GameSession> {
+        let status = self.pending_launch.as_mut()?.handshake.poll();
+
+        match status {
+            HandshakeStatus::Waiting => None,
+            HandshakeStatus::Failed(e) => {
+                println!("Handshake failed: {}", e);
+                self.pending_launch = None;
+                self.launch_error = Some(e);
+                None
+            }
+            HandshakeStatus::Complete(handshake) => {
+                // Releases the port, so the session can bind to it
+                let pending = self.pending_launch.take().unwrap();
+                drop(pending.handshake);
+
+                self.seed = format!("{:x}", handshake.seed);
+                self.init_with_console(
+                    handshake,
+                    pending.rom,
+                    pixels,
+                    window,
+                    input,
+                    pending.session_descriptor,
+                    pending.mode,
+                )
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn init_with_console(
+        &mut self,
+        handshake: HandshakeMessage,
+        rom: Rom,
+        pixels: &mut Pixels,
+        window: &Window,
+        input: &mut LocalInputManager,
+        session_descriptor: SessionDescriptor,
+        mode: SessionMode,
+    ) -> Option<GameSession> {
+        let remotes = session_descriptor
+            .player_types
+            .iter()
+            .filter_map(|player| match player {
+                PlayerType::Remote(addr) => Some(*addr),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let desync_detector = Arc::new(Mutex::new(DesyncDetector::new(
+            session_descriptor.port,
+            remotes,
+        )));
+
+        let new_session = match GameSession::start(
+            rom.frame_rate.frames_per_second(),
+            &session_descriptor,
+            mode,
+            desync_detector.clone(),
+            handshake,
+            self.network_simulator_gui.conditions,
+            self.play_mode_gui.session_settings,
+        ) {
+            Ok(new_session) => new_session,
+            Err(e) => {
+                println!("Unable to start session: {}", e);
+                self.launch_error = Some(e);
+                return None;
+            }
         };
 
-        self.window_open = false;
+        pixels.resize_buffer(rom.width() as u32, rom.height() as u32);
 
-        let (mut console, reset) = WasmConsole::new(rom, seed, session_descriptor, max_prediction);
+        // Each game stores the player's action bindings separately
+        input.load_actions(&self.game_name(), &rom.input_actions);
+
+        // Scale the window by the largest whole number which still fits within
+        // the default window size along both axes, regardless of aspect ratio.
+        let scale = (DEFAULT_WINDOW_RESOLUTION.width() / rom.width())
+            .min(DEFAULT_WINDOW_RESOLUTION.height() / rom.height())
+            .max(1);
+        window.set_min_inner_size(Some(PhysicalSize::new(
+            rom.width().min(DEFAULT_WINDOW_RESOLUTION.width()),
+            rom.height().min(DEFAULT_WINDOW_RESOLUTION.height()),
+        )));
+        window.set_inner_size(PhysicalSize::new(rom.width() * scale, rom.height() * scale));
+
+        self.window_open = false;
+        self.sync_test_failure = None;
+
+        let (mut console, reset) = WasmConsole::new(
+            rom,
+            handshake.seed,
+            session_descriptor,
+            mode,
+            new_session.max_prediction(),
+            desync_detector,
+            true,
+        );
         console.sync_mouse(window);
 
         self.wasm_console = Some(console);
         self.initial_state = Some(reset);
-        new_session
+        Some(new_session)
     }
 
     pub(crate) fn try_launch_game(
         &mut self,
         pixels: &mut Pixels,
         window: &Window,
-    ) -> Option<P2PSession<WasmConsole>> {
+        input: &mut LocalInputManager,
+    ) -> Option<GameSession> {
         let path = self.game_file.as_ref().unwrap();
 
-        let session_descriptor = self
+        let (session_descriptor, mode) = self
             .play_mode_gui
-            .generate_session_descriptor(self.controller_gui.local_player_count)?;
+            .generate_session(self.controller_gui.local_player_count)?;
 
         let rom = match Rom::try_load(path) {
             Err(e) => {
@@ -220,24 +566,6 @@ impl Gui {
 
         let seed = u64::from_str_radix(&self.seed, 16).unwrap();
 
-        Some(self.init_with_console(seed, rom, pixels, window, session_descriptor))
+        self.launch(seed, rom, pixels, window, input, session_descriptor, mode)
     }
 }
-
-fn init_session(
-    rom: &Rom,
-    port: u16,
-    players: &[PlayerType<SocketAddr>],
-) -> P2PSession<WasmConsole> {
-    let mut sess_builder = SessionBuilder::new()
-        .with_num_players(players.len())
-        .with_fps(rom.frame_rate.frames_per_second())
-        .unwrap();
-
-    for (id, address) in players.iter().enumerate() {
-        sess_builder = sess_builder.add_player(*address, id).unwrap();
-    }
-
-    let socket = UdpNonBlockingSocket::bind_to_port(port).unwrap();
-    sess_builder.start_p2p_session(socket).unwrap()
-}
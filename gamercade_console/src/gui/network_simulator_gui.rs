@@ -0,0 +1,36 @@
+use egui::{CollapsingHeader, DragValue, Slider, Ui};
+
+use crate::console::NetworkConditions;
+
+/// Simulates a bad network for the next networked session, to test how games handle rollback.
+#[derive(Default)]
+pub struct NetworkSimulatorGui {
+    pub conditions: NetworkConditions,
+}
+
+impl NetworkSimulatorGui {
+    pub(crate) fn draw(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("Network Simulator").show(ui, |ui| {
+            let conditions = &mut self.conditions;
+
+            ui.label("Applied to packets sent and received by this console, once the next networked session starts.");
+            ui.add(Slider::new(&mut conditions.latency_ms, 0..=500).text("Latency (ms)"));
+            ui.add(Slider::new(&mut conditions.jitter_ms, 0..=200).text("Jitter (ms)"));
+            ui.add(Slider::new(&mut conditions.loss_percent, 0.0..=50.0).text("Packet Loss (%)"));
+            ui.add(
+                Slider::new(&mut conditions.duplicate_percent, 0.0..=50.0)
+                    .text("Duplication (%)"),
+            );
+            ui.add(Slider::new(&mut conditions.reorder_percent, 0.0..=50.0).text("Reordering (%)"));
+
+            ui.horizontal(|ui| {
+                ui.label("Seed:");
+                ui.add(DragValue::new(&mut conditions.seed));
+            });
+
+            if ui.button("Reset").clicked() {
+                *conditions = NetworkConditions::default();
+            }
+        });
+    }
+}
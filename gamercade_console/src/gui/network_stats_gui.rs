@@ -0,0 +1,75 @@
+use egui::{Context, Grid};
+
+use crate::console::{GameSession, WasmConsole};
+
+/// Shows the connection to each remote peer, to help players pick their session settings.
+#[derive(Default)]
+pub struct NetworkStatsGui {
+    pub open: bool,
+}
+
+impl NetworkStatsGui {
+    pub(crate) fn draw(
+        &mut self,
+        ctx: &Context,
+        session: Option<&GameSession>,
+        console: Option<&WasmConsole>,
+    ) {
+        let mut open = self.open;
+
+        egui::Window::new("Network Stats")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let (session, console) = match (session, console) {
+                    (Some(session), Some(console)) => (session, console),
+                    _ => {
+                        ui.label("No game is running.");
+                        return;
+                    }
+                };
+
+                let peers = console.remote_peers();
+                if peers.is_empty() {
+                    ui.label("There aren't any remote peers.");
+                    return;
+                }
+
+                Grid::new("network_stats_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Peer");
+                        ui.label("Ping");
+                        ui.label("Send Queue");
+                        ui.label("Kbps Sent");
+                        ui.label("Local Frames Behind");
+                        ui.label("Remote Frames Behind");
+                        ui.end_row();
+
+                        peers.iter().for_each(|(addr, handle)| {
+                            ui.label(addr.to_string());
+
+                            // Stats aren't available until the peers have synchronized
+                            match session.network_stats(*handle) {
+                                Ok(stats) => {
+                                    ui.label(format!("{} ms", stats.ping));
+                                    ui.label(stats.send_queue_len.to_string());
+                                    ui.label(stats.kbps_sent.to_string());
+                                    ui.label(stats.local_frames_behind.to_string());
+                                    ui.label(stats.remote_frames_behind.to_string());
+                                }
+                                Err(_) => {
+                                    ui.label("-");
+                                    ui.label("-");
+                                    ui.label("-");
+                                    ui.label("-");
+                                    ui.label("-");
+                                }
+                            }
+                            ui.end_row();
+                        });
+                    });
+            });
+
+        self.open = open;
+    }
+}
@@ -1,31 +1,102 @@
-use std::net::SocketAddr;
+use std::net::{SocketAddr, ToSocketAddrs};
 
-use egui::{Slider, Ui};
+use egui::{Button, CollapsingHeader, Slider, Ui};
+use gamercade_lobby::{member_addr, MemberId, RelayConfig, DEFAULT_RELAY_PORT};
 use ggrs::PlayerType;
 
-use crate::console::SessionDescriptor;
+use super::lobby_gui::LobbyGui;
+use crate::console::{
+    order_players, NetworkInstance, SessionDescriptor, SessionMode, SessionSettings,
+    DEFAULT_CATCHUP_SPEED, DEFAULT_CHECK_DISTANCE, DEFAULT_MAX_FRAMES_BEHIND, MAX_CHECK_DISTANCE,
+};
 
 #[derive(Eq, PartialEq)]
 pub(crate) enum PlayMode {
     Local,
     Networked(Networked),
+    SyncTest(SyncTest),
+    Spectate(Spectate),
+    Lobby,
+}
+
+/// Rolls back every frame to check that the game is deterministic.
+#[derive(PartialEq, Eq)]
+pub(crate) struct SyncTest {
+    pub(crate) check_distance: usize,
+}
+
+impl Default for SyncTest {
+    fn default() -> Self {
+        Self {
+            check_distance: DEFAULT_CHECK_DISTANCE,
+        }
+    }
+}
+
+/// Watches a match hosted by another console.
+#[derive(PartialEq, Eq)]
+pub(crate) struct Spectate {
+    pub(crate) host_addr: String,
+    pub(crate) port: String,
+    pub(crate) player_count: usize,
+    pub(crate) max_frames_behind: usize,
+    pub(crate) catchup_speed: usize,
+}
+
+impl Default for Spectate {
+    fn default() -> Self {
+        Self {
+            host_addr: Default::default(),
+            port: Default::default(),
+            player_count: 2,
+            max_frames_behind: DEFAULT_MAX_FRAMES_BEHIND,
+            catchup_speed: DEFAULT_CATCHUP_SPEED,
+        }
+    }
+}
+
+/// The most consoles which can take part in a networked session.
+const MAX_INSTANCES: usize = 8;
+
+/// Another console taking part in a networked session.
+#[derive(PartialEq, Eq)]
+pub(crate) struct RemotePeer {
+    pub(crate) addr: String,
+    pub(crate) instance_id: usize,
+    pub(crate) player_count: usize,
 }
 
 #[derive(PartialEq, Eq)]
 pub(crate) struct Networked {
-    pub(crate) remote_addr: String,
     pub(crate) instance_id: usize,
-    pub(crate) remote_player_count: usize,
     pub(crate) port: String,
+    pub(crate) peers: Vec<RemotePeer>,
+
+    /// A comma separated list of the spectators' addresses
+    pub(crate) spectator_addrs: String,
+
+    /// Finds the peers through a relay server, instead of by their addresses
+    pub(crate) use_relay: bool,
+    pub(crate) relay_addr: String,
+    pub(crate) session_code: String,
+    pub(crate) relay_only: bool,
 }
 
 impl Default for Networked {
     fn default() -> Self {
         Self {
-            remote_addr: Default::default(),
             instance_id: 1,
-            remote_player_count: 1,
             port: Default::default(),
+            peers: vec![RemotePeer {
+                addr: Default::default(),
+                instance_id: 2,
+                player_count: 1,
+            }],
+            spectator_addrs: Default::default(),
+            use_relay: false,
+            relay_addr: format!("127.0.0.1:{}", DEFAULT_RELAY_PORT),
+            session_code: Default::default(),
+            relay_only: false,
         }
     }
 }
@@ -34,16 +105,22 @@ impl Default for PlayModeGui {
     fn default() -> Self {
         Self {
             play_mode: PlayMode::Local,
+            lobby_gui: LobbyGui::default(),
+            session_settings: SessionSettings::default(),
         }
     }
 }
 
 pub struct PlayModeGui {
     pub(crate) play_mode: PlayMode,
+
+    /// Kept outside of the play mode, so switching modes doesn't drop the lobby connection
+    pub(crate) lobby_gui: LobbyGui,
+    pub(crate) session_settings: SessionSettings,
 }
 
 impl PlayModeGui {
-    pub(crate) fn draw(&mut self, ui: &mut Ui) {
+    pub(crate) fn draw(&mut self, ui: &mut Ui, game_name: &str, local_player_count: usize) {
         ui.group(|ui| {
             ui.label("Play Mode:");
             ui.horizontal(|ui| {
@@ -62,86 +139,338 @@ impl PlayModeGui {
                 {
                     self.play_mode = PlayMode::Networked(Networked::default())
                 };
+                if ui
+                    .selectable_label(matches!(self.play_mode, PlayMode::SyncTest(..)), "SyncTest")
+                    .clicked()
+                {
+                    self.play_mode = PlayMode::SyncTest(SyncTest::default())
+                };
+                if ui
+                    .selectable_label(matches!(self.play_mode, PlayMode::Spectate(..)), "Spectate")
+                    .clicked()
+                {
+                    self.play_mode = PlayMode::Spectate(Spectate::default())
+                };
+                if ui
+                    .selectable_label(matches!(self.play_mode, PlayMode::Lobby), "Lobby")
+                    .clicked()
+                {
+                    self.play_mode = PlayMode::Lobby
+                };
             });
 
+            if matches!(self.play_mode, PlayMode::Lobby) {
+                self.lobby_gui.draw(ui, game_name, local_player_count);
+            }
+
+            if let PlayMode::SyncTest(sync_test) = &mut self.play_mode {
+                ui.label("Rolls back every frame, and checks the game's state matches after resimulating.");
+                ui.add(
+                    Slider::new(&mut sync_test.check_distance, 1..=MAX_CHECK_DISTANCE)
+                        .text("Check Distance"),
+                );
+            }
+
             if let PlayMode::Networked(networked) = &mut self.play_mode {
                 ui.horizontal(|ui| {
                     ui.label("Local Port: ");
                     ui.text_edit_singleline(&mut networked.port);
                 });
 
-                ui.add(Slider::new(&mut networked.instance_id, 1..=2).text("Unique Instance Id"));
+                ui.add(
+                    Slider::new(&mut networked.instance_id, 1..=MAX_INSTANCES)
+                        .text("Unique Instance Id"),
+                );
+                ui.label("Every console needs a different instance id. Players are ordered by instance id, so each console must enter the same ids.");
 
                 ui.separator();
 
+                ui.checkbox(&mut networked.use_relay, "Connect Through a Relay");
+                if networked.use_relay {
+                    ui.horizontal(|ui| {
+                        ui.label("Relay Server:");
+                        ui.text_edit_singleline(&mut networked.relay_addr);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Session Code:");
+                        ui.text_edit_singleline(&mut networked.session_code);
+                    });
+                    ui.checkbox(&mut networked.relay_only, "Relay Only");
+                    ui.label("Consoles with the same session code find each other through the relay, and connect directly if their NATs allow it. Relay Only always uses the relay.");
+
+                    ui.separator();
+                }
+
+                ui.label("Remote Peers:");
+                let can_remove = networked.peers.len() > 1;
+                let use_relay = networked.use_relay;
+                let mut removed = None;
+                networked.peers.iter_mut().enumerate().for_each(|(index, peer)| {
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            // Peers are found by the relay instead
+                            if !use_relay {
+                                ui.label("Remote Address:");
+                                ui.text_edit_singleline(&mut peer.addr);
+                            }
+                            if ui.add_enabled(can_remove, Button::new("Remove")).clicked() {
+                                removed = Some(index);
+                            }
+                        });
+                        ui.add(
+                            Slider::new(&mut peer.instance_id, 1..=MAX_INSTANCES)
+                                .text("Instance Id"),
+                        );
+                        ui.add(Slider::new(&mut peer.player_count, 1..=4).text("Player Count"));
+                    });
+                });
+
+                if let Some(index) = removed {
+                    networked.peers.remove(index);
+                }
+
+                if ui
+                    .add_enabled(
+                        networked.peers.len() < MAX_INSTANCES - 1,
+                        Button::new("Add Peer"),
+                    )
+                    .clicked()
+                {
+                    // Pick the lowest instance id which isn't used yet
+                    let instance_id = (1..=MAX_INSTANCES)
+                        .find(|id| {
+                            *id != networked.instance_id
+                                && networked.peers.iter().all(|peer| peer.instance_id != *id)
+                        })
+                        .unwrap_or(MAX_INSTANCES);
+                    networked.peers.push(RemotePeer {
+                        addr: Default::default(),
+                        instance_id,
+                        player_count: 1,
+                    });
+                }
+
+                if !networked.use_relay {
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.label("Spectator Addresses:");
+                        ui.text_edit_singleline(&mut networked.spectator_addrs);
+                    });
+                    ui.label("Separate each spectator's address with a comma.");
+                }
+            }
+
+            if let PlayMode::Spectate(spectate) = &mut self.play_mode {
+                ui.horizontal(|ui| {
+                    ui.label("Local Port: ");
+                    ui.text_edit_singleline(&mut spectate.port);
+                });
+
                 ui.horizontal(|ui| {
-                    ui.label("Remote Address:");
-                    ui.text_edit_singleline(&mut networked.remote_addr);
+                    ui.label("Host Address:");
+                    ui.text_edit_singleline(&mut spectate.host_addr);
                 });
 
                 ui.add(
-                    Slider::new(&mut networked.remote_player_count, 1..=4)
-                        .text("Remote Player Count"),
+                    Slider::new(&mut spectate.player_count, 1..=8)
+                        .text("Player Count in Match"),
                 );
+                ui.add(
+                    Slider::new(&mut spectate.max_frames_behind, 1..=60)
+                        .text("Max Frames Behind"),
+                );
+                ui.add(Slider::new(&mut spectate.catchup_speed, 1..=4).text("Catch-up Speed"));
+                ui.label("Once too far behind the host, several frames are simulated each frame to catch up.");
             }
+
+            CollapsingHeader::new("Session Settings").show(ui, |ui| {
+                let settings = &mut self.session_settings;
+
+                ui.add(Slider::new(&mut settings.input_delay, 0..=10).text("Input Delay (frames)"));
+                ui.label("Delaying inputs hides latency, but makes the game less responsive.");
+                ui.add(
+                    Slider::new(&mut settings.max_prediction, 1..=16)
+                        .text("Max Prediction (frames)"),
+                );
+                ui.label("Predicting further ahead stalls less on bad connections, but rolls back further.");
+                ui.add(
+                    Slider::new(&mut settings.disconnect_timeout_ms, 500..=10000)
+                        .text("Disconnect Timeout (ms)"),
+                );
+                ui.add(
+                    Slider::new(&mut settings.disconnect_notify_ms, 100..=5000)
+                        .text("Disconnect Warning (ms)"),
+                );
+
+                if ui.button("Reset").clicked() {
+                    *settings = SessionSettings::default();
+                }
+            });
         });
     }
 
-    pub(crate) fn generate_session_descriptor(
+    /// Generates the session to start. Returns None if any of the settings are invalid.
+    pub(crate) fn generate_session(
         &self,
         local_player_count: usize,
-    ) -> Option<SessionDescriptor> {
+    ) -> Option<(SessionDescriptor, SessionMode)> {
         let mut player_types = Vec::new();
+        let mut spectators = Vec::new();
+        let mut relay = None;
+        let mut mode = SessionMode::P2P;
 
         let port = match &self.play_mode {
             PlayMode::Local => {
                 player_types.extend(std::iter::repeat(PlayerType::Local).take(local_player_count));
                 8000
             }
+            PlayMode::SyncTest(sync_test) => {
+                player_types.extend(std::iter::repeat(PlayerType::Local).take(local_player_count));
+                mode = SessionMode::SyncTest {
+                    check_distance: sync_test.check_distance,
+                };
+                8000
+            }
+            PlayMode::Lobby => match self.lobby_gui.players() {
+                Some((players, port)) => {
+                    player_types = players;
+                    port
+                }
+                None => {
+                    println!("Waiting for the lobby host to start the game");
+                    return None;
+                }
+            },
+            PlayMode::Spectate(spectate) => {
+                let host = match spectate.host_addr.parse::<SocketAddr>() {
+                    Ok(host) => host,
+                    Err(_) => {
+                        println!("Host Addr is invalid");
+                        return None;
+                    }
+                };
+                let port = match spectate.port.parse::<u16>() {
+                    Ok(port) => port,
+                    Err(_) => {
+                        println!("Port is invalid");
+                        return None;
+                    }
+                };
+
+                // Every player is remote from the spectator's point of view
+                player_types.extend(
+                    std::iter::repeat(PlayerType::Remote(host)).take(spectate.player_count),
+                );
+                mode = SessionMode::Spectator {
+                    host,
+                    max_frames_behind: spectate.max_frames_behind,
+                    catchup_speed: spectate.catchup_speed,
+                };
+                port
+            }
             PlayMode::Networked(networked) => {
-                let remote_addr = networked.remote_addr.parse::<SocketAddr>();
-                let port = networked.port.parse::<u16>();
+                let port = match networked.port.parse::<u16>() {
+                    Ok(port) => port,
+                    Err(_) => {
+                        println!("Port is invalid");
+                        return None;
+                    }
+                };
 
-                if remote_addr.is_err() {
-                    println!("Remote Addr is invalid");
-                    return None;
-                } else if port.is_err() {
-                    println!("Port is invalid");
-                    return None;
+                if networked.use_relay {
+                    let server = match networked
+                        .relay_addr
+                        .to_socket_addrs()
+                        .ok()
+                        .and_then(|mut addrs| addrs.next())
+                    {
+                        Some(server) => server,
+                        None => {
+                            println!("Relay Server address is invalid");
+                            return None;
+                        }
+                    };
+
+                    if networked.session_code.is_empty() {
+                        println!("Session Code is required to use a relay");
+                        return None;
+                    }
+
+                    relay = Some(RelayConfig {
+                        server,
+                        code: networked.session_code.clone(),
+                        member: networked.instance_id as MemberId,
+                        relay_only: networked.relay_only,
+                    });
                 }
 
-                let remote_addr = remote_addr.unwrap();
-                let port = port.unwrap();
-
-                if networked.instance_id == 1 {
-                    player_types
-                        .extend(std::iter::repeat(PlayerType::Local).take(local_player_count));
-                    player_types.extend(
-                        std::iter::repeat(PlayerType::Remote(remote_addr))
-                            .take(networked.remote_player_count),
-                    );
-                } else if networked.instance_id == 2 {
-                    player_types.extend(
-                        std::iter::repeat(PlayerType::Remote(remote_addr))
-                            .take(networked.remote_player_count),
-                    );
-                    player_types
-                        .extend(std::iter::repeat(PlayerType::Local).take(local_player_count));
-                } else {
-                    println!("Player # should be 1 or 2");
-                    return None;
+                let mut instances = vec![NetworkInstance {
+                    instance_id: networked.instance_id,
+                    addr: None,
+                    player_count: local_player_count,
+                }];
+
+                for peer in networked.peers.iter() {
+                    // Peers reached through the relay are known by their instance id
+                    let addr = if relay.is_some() {
+                        Ok(member_addr(peer.instance_id as MemberId))
+                    } else {
+                        peer.addr.parse::<SocketAddr>()
+                    };
+
+                    match addr {
+                        Ok(addr) => instances.push(NetworkInstance {
+                            instance_id: peer.instance_id,
+                            addr: Some(addr),
+                            player_count: peer.player_count,
+                        }),
+                        Err(_) => {
+                            println!("Remote Addr {} is invalid", peer.addr);
+                            return None;
+                        }
+                    }
+                }
+
+                player_types = match order_players(&instances) {
+                    Ok(player_types) => player_types,
+                    Err(e) => {
+                        println!("{}", e);
+                        return None;
+                    }
                 };
 
+                // Spectators can't be reached through the relay
+                for addr in networked
+                    .spectator_addrs
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|addr| relay.is_none() && !addr.is_empty())
+                {
+                    match addr.parse::<SocketAddr>() {
+                        Ok(addr) => spectators.push(addr),
+                        Err(_) => {
+                            println!("Spectator Addr {} is invalid", addr);
+                            return None;
+                        }
+                    }
+                }
+
                 port
             }
         };
 
         let player_types = player_types.into_boxed_slice();
 
-        Some(SessionDescriptor {
-            num_players: player_types.len(),
-            player_types,
-            port,
-        })
+        Some((
+            SessionDescriptor {
+                num_players: player_types.len(),
+                player_types,
+                port,
+                spectators: spectators.into_boxed_slice(),
+                relay,
+            },
+            mode,
+        ))
     }
 }
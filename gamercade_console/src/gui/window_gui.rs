@@ -0,0 +1,83 @@
+use egui::Ui;
+use pixels::Pixels;
+use winit::window::Window;
+
+use crate::console_config::{FullscreenMode, WindowSettings};
+
+#[derive(Default)]
+pub struct WindowGui {
+    /// Set while the background color is being picked, so it's only saved once released.
+    color_unsaved: bool,
+}
+
+impl WindowGui {
+    /// Draws the window settings. Returns true if any of them were changed, and should be saved.
+    pub(crate) fn draw(
+        &mut self,
+        ui: &mut Ui,
+        settings: &mut WindowSettings,
+        window: &Window,
+        pixels: &mut Pixels,
+        game_resolution: Option<(u32, u32)>,
+    ) -> bool {
+        let mut changed = false;
+
+        ui.group(|ui| {
+            ui.label("Window Settings:");
+
+            ui.horizontal(|ui| {
+                let previous = settings.fullscreen;
+                ui.selectable_value(
+                    &mut settings.fullscreen,
+                    FullscreenMode::Windowed,
+                    "Windowed",
+                );
+                ui.selectable_value(
+                    &mut settings.fullscreen,
+                    FullscreenMode::Borderless,
+                    "Borderless",
+                );
+                ui.selectable_value(
+                    &mut settings.fullscreen,
+                    FullscreenMode::Exclusive,
+                    "Exclusive",
+                );
+
+                if previous != settings.fullscreen {
+                    settings.apply_fullscreen(window);
+                    changed = true;
+                }
+            });
+
+            if ui
+                .checkbox(&mut settings.integer_scale_lock, "Integer Scale Lock")
+                .changed()
+            {
+                if let Some(snapped) = game_resolution.and_then(|resolution| {
+                    settings.integer_scaled_size(window.inner_size(), resolution)
+                }) {
+                    window.set_inner_size(snapped);
+                }
+                changed = true;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Background Color:");
+                if ui
+                    .color_edit_button_srgb(&mut settings.background_color)
+                    .changed()
+                {
+                    pixels.set_clear_color(settings.clear_color());
+                    self.color_unsaved = true;
+                }
+            });
+        });
+
+        if self.color_unsaved && !ui.input().pointer.any_down() {
+            self.color_unsaved = false;
+            changed = true;
+        }
+
+        changed
+    }
+}
@@ -1,5 +1,6 @@
 mod api;
 mod console;
+mod console_config;
 mod gui;
 mod pixel_buffer;
 
@@ -10,104 +11,268 @@ use std::{
 
 use clap::Parser;
 use gamercade_core::Resolution;
-use ggrs::{GGRSError, P2PSession, SessionState};
-use gilrs::Gilrs;
+use gamercade_fs::Rom;
+use ggrs::{GGRSError, GGRSEvent, SessionState};
+use gilrs::{EventType, Gilrs};
 use pixels::{Pixels, SurfaceTexture};
 use winit::{
     dpi::LogicalSize,
-    event::{DeviceEvent, Event, MouseScrollDelta, VirtualKeyCode},
+    event::{DeviceEvent, ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::{Window, WindowBuilder},
 };
 use winit_input_helper::WinitInputHelper;
 
 use crate::{
-    console::LocalInputManager,
-    gui::{framework::Framework, Gui},
+    console::{LocalInputManager, SessionMode},
+    console_config::ConsoleConfig,
+    gui::{
+        framework::Framework, network_simulator_gui::NetworkSimulatorGui,
+        network_stats_gui::NetworkStatsGui, play_mode_gui::PlayModeGui, Gui,
+    },
+};
+use console::{
+    run_headless_sync_test, Console, GameSession, LocalPlayerId, MouseEventCollector,
+    NetworkConditions, SessionSettings, TextInputCollector, TouchCollector, DEFAULT_CHECK_DISTANCE,
+    DEFAULT_DISCONNECT_NOTIFY_MS, DEFAULT_DISCONNECT_TIMEOUT_MS, DEFAULT_MAX_PREDICTION,
 };
-use console::{Console, LocalPlayerId, MouseEventCollector, WasmConsole};
 
 #[derive(Parser, Debug)]
 struct Cli {
     /// Path to .gcrom to load.
     #[clap(short, long, value_parser)]
     game: Option<PathBuf>,
+
+    /// Runs the game in a SyncTest session, which rolls back every frame
+    /// to check that the game is deterministic.
+    #[clap(long, action, requires = "game")]
+    sync_test: bool,
+
+    /// How many frames each SyncTest rollback goes back.
+    #[clap(long, value_parser, default_value_t = DEFAULT_CHECK_DISTANCE)]
+    check_distance: usize,
+
+    /// Runs the SyncTest without a window, using random inputs, then exits.
+    /// Exits with an error if the game isn't deterministic.
+    #[clap(long, action, requires = "game")]
+    headless: bool,
+
+    /// How many frames the headless SyncTest runs for.
+    #[clap(long, value_parser, default_value_t = 600)]
+    frames: usize,
+
+    /// How many players the headless SyncTest has.
+    #[clap(long, value_parser, default_value_t = 1)]
+    players: usize,
+
+    /// Seed for the game's random numbers, and the headless SyncTest's inputs.
+    /// A random seed is used if none is given.
+    #[clap(long, value_parser)]
+    seed: Option<u64>,
+
+    /// Simulated latency added to each networked packet, in milliseconds.
+    #[clap(long, value_parser, default_value_t = 0)]
+    latency: u32,
+
+    /// How much the simulated latency randomly varies by, in milliseconds.
+    #[clap(long, value_parser, default_value_t = 0)]
+    jitter: u32,
+
+    /// The percentage of networked packets which are dropped.
+    #[clap(long, value_parser, default_value_t = 0.0)]
+    packet_loss: f32,
+
+    /// The percentage of networked packets which are sent twice.
+    #[clap(long, value_parser, default_value_t = 0.0)]
+    duplicate: f32,
+
+    /// The percentage of networked packets which are held back, so they arrive out of order.
+    #[clap(long, value_parser, default_value_t = 0.0)]
+    reorder: f32,
+
+    /// Seeds the network simulator, so the same packets are affected each run.
+    #[clap(long, value_parser, default_value_t = 0)]
+    net_sim_seed: u64,
+
+    /// Frames each local input is delayed by in networked sessions.
+    #[clap(long, value_parser, default_value_t = 0)]
+    input_delay: usize,
+
+    /// How many frames can be predicted before waiting for the remote inputs.
+    #[clap(long, value_parser, default_value_t = DEFAULT_MAX_PREDICTION)]
+    max_prediction: usize,
+
+    /// How long a peer can go without sending anything before it's disconnected, in milliseconds.
+    #[clap(long, value_parser, default_value_t = DEFAULT_DISCONNECT_TIMEOUT_MS)]
+    disconnect_timeout: u64,
+
+    /// How long a peer can go without sending anything before the players are warned, in milliseconds.
+    #[clap(long, value_parser, default_value_t = DEFAULT_DISCONNECT_NOTIFY_MS)]
+    disconnect_notify: u64,
+
+    /// Shows the network stats of each remote peer.
+    #[clap(long, action)]
+    net_stats: bool,
+}
+
+impl Cli {
+    fn network_conditions(&self) -> NetworkConditions {
+        NetworkConditions {
+            latency_ms: self.latency,
+            jitter_ms: self.jitter,
+            loss_percent: self.packet_loss,
+            duplicate_percent: self.duplicate,
+            reorder_percent: self.reorder,
+            seed: self.net_sim_seed,
+        }
+    }
+
+    fn session_settings(&self) -> SessionSettings {
+        SessionSettings {
+            input_delay: self.input_delay,
+            max_prediction: self.max_prediction,
+            disconnect_timeout_ms: self.disconnect_timeout,
+            disconnect_notify_ms: self.disconnect_notify,
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
+    if cli.headless {
+        return run_headless(&cli);
+    }
+
     let event_loop = EventLoop::new();
+    let config = ConsoleConfig::load();
 
     let window = init_window(&event_loop);
+    config.window.restore(&window);
     let window_size = window.inner_size();
     let scale_factor = window.scale_factor() as f32;
 
-    let mut session: Option<P2PSession<WasmConsole>> = None;
+    let mut session: Option<GameSession> = None;
     let mut pixels = init_pixels(&window);
+    pixels.set_clear_color(config.window.clear_color());
 
     let mut gilrs = Gilrs::new().unwrap();
 
     let mut input = WinitInputHelper::new();
     let mut input_manager = LocalInputManager::new();
+    input_manager.load_gamepad_profiles(&gilrs);
     let mut last_update = Instant::now();
     let mut accumulator = Duration::ZERO;
 
+    // Frames to wait for, when GGRS recommends letting the other peers catch up
+    let mut skip_frames = 0;
+
     let mut framework = Framework::new(
         window_size.width,
         window_size.height,
         scale_factor,
         &pixels,
-        Gui::default(),
+        Gui {
+            config,
+            network_simulator_gui: NetworkSimulatorGui {
+                conditions: cli.network_conditions(),
+            },
+            network_stats_gui: NetworkStatsGui {
+                open: cli.net_stats,
+            },
+            play_mode_gui: PlayModeGui {
+                session_settings: cli.session_settings(),
+                ..PlayModeGui::default()
+            },
+            ..Gui::default()
+        },
     );
 
     if let Some(game_path) = &cli.game {
-        let seed = fastrand::u64(0..u64::MAX);
-        session = framework
-            .gui
-            .fast_launch_game(game_path.clone(), seed, &mut pixels, &window);
+        let seed = cli.seed.unwrap_or_else(|| fastrand::u64(0..u64::MAX));
+        session = framework.gui.fast_launch_game(
+            game_path.clone(),
+            seed,
+            &mut pixels,
+            &window,
+            &mut input_manager,
+            if cli.sync_test {
+                SessionMode::SyncTest {
+                    check_distance: cli.check_distance,
+                }
+            } else {
+                SessionMode::P2P
+            },
+        );
     }
 
     let mut mouse_events = MouseEventCollector::default();
+    let mut text_input = TextInputCollector::default();
+    let mut touches = TouchCollector::default();
+    let mut key_captured = false;
 
     event_loop.run(move |event, _, control_flow| {
         if let Event::WindowEvent { event, .. } = &event {
             framework.handle_event(event);
+
+            if let WindowEvent::Moved(position) = event {
+                framework.gui.config.window.remember_position(*position);
+            }
+
+            // Keys pressed while rebinding shouldn't trigger any other actions
+            if let WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } = event
+            {
+                key_captured |= framework
+                    .gui
+                    .controller_gui
+                    .try_capture_key(*key, &mut input_manager);
+            }
+
+            if let WindowEvent::KeyboardInput {
+                input: KeyboardInput { state, .. },
+                ..
+            } = event
+            {
+                let latency_test = &mut framework.gui.input_display_gui.latency_test;
+                match state {
+                    ElementState::Pressed => latency_test.input_pressed(),
+                    ElementState::Released => latency_test.input_released(),
+                }
+            }
         }
 
         if session.is_some() {
+            if let Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(c),
+                ..
+            } = &event
+            {
+                text_input.push_char(*c);
+            }
+
+            if let Event::WindowEvent {
+                event: WindowEvent::Touch(touch),
+                ..
+            } = &event
+            {
+                touches.handle_touch(touch);
+            }
+
             if let Event::DeviceEvent { event, .. } = &event {
                 if let DeviceEvent::MouseMotion { delta } = event {
-                    mouse_events.delta_x += delta.0 as i16;
-                    mouse_events.delta_y += delta.1 as i16;
+                    mouse_events.handle_motion(*delta);
                 }
 
                 if let DeviceEvent::MouseWheel { delta } = event {
-                    let mut out_x = 0.0;
-                    let mut out_y = 0.0;
-
-                    match delta {
-                        MouseScrollDelta::LineDelta(x, y) => {
-                            out_x += x;
-                            out_y += y;
-                        }
-                        MouseScrollDelta::PixelDelta(d) => {
-                            out_x += d.x as f32;
-                            out_y += d.y as f32
-                        }
-                    }
-
-                    if out_y > 0.0 {
-                        mouse_events.wheel_down = true
-                    } else if out_y < 0.0 {
-                        mouse_events.wheel_up = true
-                    }
-
-                    if out_x > 0.0 {
-                        mouse_events.wheel_right = true
-                    } else if out_x < 0.0 {
-                        mouse_events.wheel_left = true
-                    }
+                    mouse_events.handle_wheel(*delta);
                 }
             }
         }
@@ -120,18 +285,44 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             &mut gilrs,
         );
 
+        // Process all the gamepad events, players can only be added before a game starts
+        while let Some(event) = gilrs.next_event() {
+            input_manager.handle_gamepad_event(&event, &gilrs, session.is_none());
+
+            let latency_test = &mut framework.gui.input_display_gui.latency_test;
+            match event.event {
+                EventType::ButtonPressed(..) => latency_test.input_pressed(),
+                EventType::ButtonReleased(..) => latency_test.input_released(),
+                _ => (),
+            }
+        }
+
         // Handle input events
         if input.update(&event) {
             // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+            let hotkeys_enabled = !std::mem::take(&mut key_captured);
+
+            if (hotkeys_enabled && input.key_pressed(VirtualKeyCode::Escape)) || input.quit() {
+                framework.gui.config.save();
                 *control_flow = ControlFlow::Exit;
                 return;
             }
 
-            if input.key_pressed(VirtualKeyCode::Space) {
+            // Space is typed as text while the game is using text input
+            let typing = matches!(
+                &framework.gui.wasm_console,
+                Some(console) if console.text_input_active()
+            );
+
+            if hotkeys_enabled && !typing && input.key_pressed(VirtualKeyCode::Space) {
                 framework.gui.window_open = !framework.gui.window_open;
             }
 
+            if hotkeys_enabled && input.key_pressed(VirtualKeyCode::F11) {
+                framework.gui.config.window.toggle_fullscreen(&window);
+                framework.gui.config.save();
+            }
+
             // Update the scale factor
             if let Some(scale_factor) = input.scale_factor() {
                 framework.scale_factor(scale_factor);
@@ -139,19 +330,51 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             // Resize the window
             if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
-                framework.resize(size.width, size.height);
+                let window_settings = &mut framework.gui.config.window;
+
+                let snapped = framework.gui.wasm_console.as_ref().and_then(|console| {
+                    window_settings.integer_scaled_size(
+                        size,
+                        (console.rom.width() as u32, console.rom.height() as u32),
+                    )
+                });
+
+                match snapped {
+                    // Snapping the window will trigger another resize event
+                    Some(snapped) if snapped != size => window.set_inner_size(snapped),
+                    _ => {
+                        window_settings.remember_size(size);
+                        pixels.resize_surface(size.width, size.height);
+                        framework.resize(size.width, size.height);
+                    }
+                }
             }
 
+            // Set when the session can't continue, and the players are returned to the menu
+            let mut session_error = None;
+
             if let Some(console) = &mut framework.gui.wasm_console {
                 // Handle GGRS packets
                 let session = session.as_mut().unwrap();
                 session.poll_remote_clients();
 
+                for event in session.events() {
+                    // Spectators can't continue without the host
+                    if let GGRSEvent::Disconnected { addr } = event {
+                        if session.is_spectator() {
+                            session_error =
+                                Some(format!("Lost the connection to the host at {}", addr));
+                        }
+                    }
+
+                    skip_frames += framework.gui.connection_gui.handle_event(event);
+                }
+
                 if session.current_state() == SessionState::Running {
                     // this is to keep ticks between clients synchronized.
                     // if a client is ahead, it will run frames slightly slower to allow catching up
                     let mut fps_delta = 1. / console.rom.frame_rate.frames_per_second() as f64;
+                    let frame_time = fps_delta as f32;
                     if session.frames_ahead() > 0 {
                         fps_delta *= 1.1;
                     }
@@ -161,55 +384,110 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     accumulator = accumulator.saturating_add(delta);
                     last_update = Instant::now();
 
-                    while accumulator.as_secs_f64() > fps_delta {
+                    // Only taken once a frame has advanced, as polling can confirm frames
+                    // before the rollback which corrects their mispredicted states
+                    let mut confirmed_frame = None;
+
+                    while session_error.is_none() && accumulator.as_secs_f64() > fps_delta {
                         accumulator =
                             accumulator.saturating_sub(Duration::from_secs_f64(fps_delta));
 
-                        // Process all the gamepad events
-                        while gilrs.next_event().is_some() {}
+                        if skip_frames > 0 {
+                            skip_frames -= 1;
+                            continue;
+                        }
 
-                        let shared_mouse = std::mem::take(&mut mouse_events);
+                        let shared_mouse = mouse_events.take_frame();
+                        console.push_text_input(std::mem::take(&mut text_input));
 
                         // Generate all local inputs
                         let mut local_player_id = LocalPlayerId(0);
                         for handle in session.local_player_handles() {
-                            session
-                                .add_local_input(
-                                    handle,
-                                    input_manager.generate_input_state(
-                                        local_player_id,
-                                        &pixels,
-                                        &shared_mouse,
-                                        &input,
-                                        &gilrs,
-                                    ),
-                                )
-                                .unwrap();
+                            let input_state = input_manager.generate_input_state(
+                                local_player_id,
+                                &pixels,
+                                &shared_mouse,
+                                &touches,
+                                &input,
+                                &gilrs,
+                                frame_time,
+                            );
+                            if let Err(e) = session.add_local_input(handle, input_state) {
+                                session_error = Some(e.to_string());
+                            }
                             local_player_id.0 += 1;
                         }
+                        touches.end_frame();
+
+                        if session_error.is_some() {
+                            break;
+                        }
 
                         // Update internal state
                         match session.advance_frame() {
                             Ok(requests) => {
                                 console.handle_requests(requests);
+                                confirmed_frame = Some(session.confirmed_frame());
                             }
                             Err(GGRSError::PredictionThreshold) => (),
-                            Err(e) => panic!("{}", e),
+                            Err(GGRSError::MismatchedChecksum { frame }) => {
+                                if framework.gui.sync_test_failure.is_none() {
+                                    println!(
+                                        "SyncTest failed: checksums didn't match after resimulating frame {}",
+                                        frame
+                                    );
+                                    framework.gui.sync_test_failure = Some(frame);
+                                }
+                            }
+                            Err(e) => session_error = Some(e.to_string()),
                         }
                     }
 
+                    // Compare the newly confirmed frames with the other peers
+                    if let Some(frame) = confirmed_frame {
+                        console.confirm_frame(frame);
+                    }
+
                     // If sound changed, update the output
                     console.sync_audio();
 
+                    // Rumble is synced after any rollbacks, so resimulated frames don't rumble again
+                    session
+                        .local_player_handles()
+                        .into_iter()
+                        .enumerate()
+                        .for_each(|(local_player, handle)| {
+                            input_manager.sync_rumble(
+                                LocalPlayerId(local_player),
+                                console.rumble_strength(handle),
+                                &mut gilrs,
+                            )
+                        });
+
                     // Sync the mouse lock state
                     console.sync_mouse(&window);
 
                     // Render the game
                     console.call_draw();
                     console.blit(pixels.get_frame());
+                    framework
+                        .gui
+                        .input_display_gui
+                        .latency_test
+                        .frame_drawn(pixels.get_frame());
                 };
             };
 
+            if let Some(error) = session_error {
+                skip_frames = 0;
+                framework.gui.end_session(
+                    &mut session,
+                    &window,
+                    &mut input_manager,
+                    Some(error),
+                );
+            }
+
             let render_result = pixels.render_with(|encoder, render_target, context| {
                 //TODO: Handle this correctly
                 context.scaling_renderer.render(encoder, render_target);
@@ -223,6 +501,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
+            framework
+                .gui
+                .input_display_gui
+                .latency_test
+                .frame_presented();
             window.request_redraw();
         }
     });
@@ -230,6 +513,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 const DEFAULT_WINDOW_RESOLUTION: Resolution = Resolution::High;
 
+/// Runs a SyncTest without creating a window or any graphics.
+fn run_headless(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let rom = Rom::try_load(cli.game.as_ref().unwrap())?;
+    let seed = cli.seed.unwrap_or_else(|| fastrand::u64(0..u64::MAX));
+
+    println!(
+        "Running SyncTest for {} frames, with {} players, a check distance of {} and seed {}",
+        cli.frames, cli.players, cli.check_distance, seed
+    );
+    run_headless_sync_test(rom, seed, cli.players, cli.check_distance, cli.frames)
+        .map_err(|e| format!("{} Run again with --seed {} to reproduce it.", e, seed))?;
+    println!("SyncTest passed");
+
+    Ok(())
+}
+
 fn init_window(event_loop: &EventLoop<()>) -> Window {
     let size = LogicalSize::new(
         DEFAULT_WINDOW_RESOLUTION.width() as f64,
@@ -11,6 +11,9 @@ pub enum Resolution {
     High,      // 640 x 360
     VeryHigh,  // 1280 x 720
     UltraHigh, // 1920 x 1080
+    // Must stay the last variant, so that previously bundled
+    // ROMs and editor JSON files still deserialize correctly.
+    Custom { width: i32, height: i32 },
 }
 
 impl Resolution {
@@ -23,6 +26,43 @@ impl Resolution {
     pub const VERYHIGH: (i32, i32) = (1280, 720);
     pub const ULTRAHIGH: (i32, i32) = (1920, 1080);
 
+    // Bounds for custom resolutions. Mouse positions are packed into 11 bits,
+    // so neither side may exceed 2047.
+    pub const MIN_DIMENSION: i32 = 16;
+    pub const MAX_DIMENSION: i32 = 1920;
+    pub const MAX_TOTAL_PIXELS: i32 = Self::ULTRAHIGH.0 * Self::ULTRAHIGH.1;
+
+    /// Creates a custom resolution, returning an error if it is out of bounds.
+    pub fn custom(width: i32, height: i32) -> Result<Self, String> {
+        let out = Self::Custom { width, height };
+
+        if out.is_valid() {
+            Ok(out)
+        } else {
+            Err(format!(
+                "Invalid resolution {} x {}: each side must be within {}..={} and the total pixels must not exceed {}.",
+                width,
+                height,
+                Self::MIN_DIMENSION,
+                Self::MAX_DIMENSION,
+                Self::MAX_TOTAL_PIXELS
+            ))
+        }
+    }
+
+    /// Returns true if the resolution is within the supported bounds.
+    /// The presets are always valid.
+    pub const fn is_valid(&self) -> bool {
+        let width = self.width();
+        let height = self.height();
+
+        width >= Self::MIN_DIMENSION
+            && width <= Self::MAX_DIMENSION
+            && height >= Self::MIN_DIMENSION
+            && height <= Self::MAX_DIMENSION
+            && width * height <= Self::MAX_TOTAL_PIXELS
+    }
+
     pub const fn width(&self) -> i32 {
         match self {
             Self::UltraLow => Self::ULTRALOW.0,
@@ -32,6 +72,7 @@ impl Resolution {
             Self::High => Self::HIGH.0,
             Self::VeryHigh => Self::VERYHIGH.0,
             Self::UltraHigh => Self::ULTRAHIGH.0,
+            Self::Custom { width, .. } => *width,
         }
     }
 
@@ -44,6 +85,7 @@ impl Resolution {
             Self::High => Self::HIGH.1,
             Self::VeryHigh => Self::VERYHIGH.1,
             Self::UltraHigh => Self::ULTRAHIGH.1,
+            Self::Custom { height, .. } => *height,
         }
     }
 
@@ -51,6 +93,24 @@ impl Resolution {
         self.width() * self.height()
     }
 
+    /// Returns the aspect ratio reduced to its simplest form, ie (16, 9) or (3, 4).
+    pub const fn aspect_ratio(&self) -> (i32, i32) {
+        let (width, height) = (self.width(), self.height());
+        let (mut a, mut b) = (width, height);
+
+        while b != 0 {
+            let t = b;
+            b = a % b;
+            a = t;
+        }
+
+        if a == 0 {
+            (width, height)
+        } else {
+            (width / a, height / a)
+        }
+    }
+
     pub fn try_get_xcord<T: Into<i32>>(&self, value: T) -> Option<XCord> {
         match value.try_into() {
             Ok(v) => match 0 <= v && v < self.width() {
@@ -105,3 +165,27 @@ impl YCord {
         self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Resolution;
+
+    #[test]
+    fn test_custom_resolution_bounds() {
+        assert!(Resolution::custom(240, 320).is_ok());
+        assert!(Resolution::custom(256, 256).is_ok());
+        assert!(Resolution::custom(1080, 1920).is_ok());
+
+        assert!(Resolution::custom(0, 320).is_err());
+        assert!(Resolution::custom(240, 4000).is_err());
+        assert!(Resolution::custom(1920, 1920).is_err());
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        assert_eq!(Resolution::Low.aspect_ratio(), (16, 9));
+        assert_eq!(Resolution::custom(320, 240).unwrap().aspect_ratio(), (4, 3));
+        assert_eq!(Resolution::custom(240, 320).unwrap().aspect_ratio(), (3, 4));
+        assert_eq!(Resolution::custom(128, 128).unwrap().aspect_ratio(), (1, 1));
+    }
+}
@@ -160,6 +160,31 @@ impl SpriteSheet {
         self.count -= 1;
     }
 
+    /// Returns the color index of a single pixel of a sprite, where (x, y) is
+    /// relative to the top left of the sprite after flipping is applied.
+    /// Returns None if the sprite or position is out of bounds.
+    pub fn pixel(
+        &self,
+        index: SpriteIndex,
+        (x, y): (i32, i32),
+        (flip_x, flip_y): (bool, bool),
+    ) -> Option<ColorIndex> {
+        if index.0 >= self.count
+            || x < 0
+            || y < 0
+            || x as usize >= self.width
+            || y as usize >= self.height
+        {
+            return None;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        let x = if flip_x { self.width - x - 1 } else { x };
+        let y = if flip_y { self.height - y - 1 } else { y };
+
+        self[index].get(x + (y * self.width)).copied()
+    }
+
     pub fn get_indices(&self, index: SpriteIndex) -> Range<usize> {
         let step = self.step();
         let index = index.0 as usize;
@@ -0,0 +1,21 @@
+use bytemuck::{Pod, Zeroable};
+
+// Uses 1/32 bits, the rest are reserved
+const CONNECTED_SHIFT: u32 = 0;
+
+/// The status of the device a player is using. This is sent along with the
+/// player's inputs, so every peer sees the same status on the same frame.
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable, PartialEq, Eq)]
+#[repr(C)]
+pub struct ControllerStatus(pub u32);
+
+impl ControllerStatus {
+    pub fn get_connected(self) -> bool {
+        self.0 & 1 << CONNECTED_SHIFT != 0
+    }
+
+    pub fn set_connected(&mut self, value: bool) {
+        self.0 &= !(1 << CONNECTED_SHIFT);
+        self.0 |= (value as u32) << CONNECTED_SHIFT;
+    }
+}
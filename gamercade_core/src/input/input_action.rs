@@ -0,0 +1,61 @@
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+use super::ButtonCode;
+
+/// The most actions a single game can declare.
+pub const MAX_INPUT_ACTIONS: usize = 32;
+
+/// A named action declared by a game, like "Jump" or "Fire". Players see
+/// these names, and can change which console buttons trigger each action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputAction {
+    pub name: String,
+
+    /// The console buttons which trigger the action, unless changed by the player.
+    pub default_buttons: Vec<ButtonCode>,
+}
+
+/// Which of the game's actions are held, using one bit for each action id.
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable, PartialEq, Eq)]
+#[repr(C)]
+pub struct ActionState(pub u32);
+
+impl ActionState {
+    /// Returns false for action ids past the maximum.
+    pub fn get_action(self, action_id: usize) -> bool {
+        action_id < MAX_INPUT_ACTIONS && self.0 & 1 << action_id != 0
+    }
+
+    /// Does nothing for action ids past the maximum.
+    pub fn set_action(&mut self, action_id: usize, value: bool) {
+        if action_id < MAX_INPUT_ACTIONS {
+            self.0 &= !(1 << action_id);
+            self.0 |= (value as u32) << action_id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_state() {
+        let mut state = ActionState::default();
+        state.set_action(0, true);
+        state.set_action(MAX_INPUT_ACTIONS - 1, true);
+
+        assert!(state.get_action(0));
+        assert!(!state.get_action(1));
+        assert!(state.get_action(MAX_INPUT_ACTIONS - 1));
+
+        // Out of range actions are ignored
+        state.set_action(MAX_INPUT_ACTIONS, true);
+        assert!(!state.get_action(MAX_INPUT_ACTIONS));
+
+        state.set_action(0, false);
+        state.set_action(MAX_INPUT_ACTIONS - 1, false);
+        assert_eq!(state.0, 0);
+    }
+}
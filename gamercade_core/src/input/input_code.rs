@@ -29,6 +29,25 @@ pub enum ButtonCode {
 }
 
 impl ButtonCode {
+    pub const ALL: [Self; 16] = [
+        Self::Up,
+        Self::Down,
+        Self::Left,
+        Self::Right,
+        Self::ButtonA,
+        Self::ButtonB,
+        Self::ButtonC,
+        Self::ButtonD,
+        Self::Start,
+        Self::Select,
+        Self::LeftShoulder,
+        Self::RightShoulder,
+        Self::LeftStick,
+        Self::RightStick,
+        Self::LeftTrigger,
+        Self::RightTrigger,
+    ];
+
     const API_UP: u8 = 0;
     const API_DOWN: u8 = 1;
     const API_LEFT: u8 = 2;
@@ -49,16 +49,16 @@ pub struct AnalogStick {
 }
 
 impl AnalogStick {
+    /// Sets the axis value. Values outside of [-1, 1] are clamped,
+    /// as some drivers report slightly out of range values.
     pub fn set_x_axis(&mut self, value: f32) {
-        assert!(value <= 1.0);
-        assert!(value >= -1.0);
-        self.x_axis = (value * i8::MAX as f32) as i8;
+        self.x_axis = (value.clamp(-1.0, 1.0) * i8::MAX as f32) as i8;
     }
 
+    /// Sets the axis value. Values outside of [-1, 1] are clamped,
+    /// as some drivers report slightly out of range values.
     pub fn set_y_axis(&mut self, value: f32) {
-        assert!(value <= 1.0);
-        assert!(value >= -1.0);
-        self.y_axis = (value * i8::MAX as f32) as i8;
+        self.y_axis = (value.clamp(-1.0, 1.0) * i8::MAX as f32) as i8;
     }
 
     pub fn get_x_axis(&self) -> f32 {
@@ -85,10 +85,9 @@ impl AnalogTrigger {
         (self.state & Self::MASK) as f32 / Self::MASK as f32
     }
 
+    /// Sets the trigger value. Values outside of [0, 1] are clamped.
     pub fn set_value(&mut self, value: f32) {
-        assert!(value <= 1.0);
-        assert!(value >= 0.0);
-        self.state = (value * i8::MAX as f32) as i8;
+        self.state = (value.clamp(0.0, 1.0) * i8::MAX as f32) as i8;
     }
 }
 
@@ -119,3 +118,23 @@ unsafe impl Zeroable for Buttons {}
 unsafe impl Zeroable for AnalogTrigger {}
 unsafe impl Zeroable for AnalogStick {}
 unsafe impl Zeroable for InputState {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_of_range_values_are_clamped() {
+        let mut stick = AnalogStick::default();
+        stick.set_x_axis(1.2);
+        stick.set_y_axis(-1.5);
+        assert_eq!(stick.get_x_axis(), 1.0);
+        assert_eq!(stick.get_y_axis(), -1.0);
+
+        let mut trigger = AnalogTrigger::default();
+        trigger.set_value(-0.1);
+        assert_eq!(trigger.get_value(), 0.0);
+        trigger.set_value(1.1);
+        assert_eq!(trigger.get_value(), 1.0);
+    }
+}
@@ -1,10 +1,16 @@
+mod controller_status;
+mod input_action;
 mod input_code;
 mod input_state;
 mod mouse_state;
+mod touch_state;
 
+pub use controller_status::*;
+pub use input_action::*;
 pub use input_code::*;
 pub use input_state::*;
 pub use mouse_state::*;
+pub use touch_state::*;
 
 pub trait AsApiCode: Sized {
     fn to_api_code(&self) -> u8;
@@ -4,24 +4,20 @@ use bytemuck::{Pod, Zeroable};
 #[repr(C)]
 pub struct MouseState(pub u64);
 
-// Uses 29/32 bits
+// Uses 25/32 bits
 const MASK: u64 = 0b111_1111_1111; // 11 bits
 const X_SHIFT: u64 = 0;
 const Y_SHIFT: u64 = 11;
 const LEFT_BUTTON_SHIFT: u64 = 22;
 const RIGHT_BUTTON_SHIFT: u64 = 23;
 const MIDDLE_BUTTON_SHIFT: u64 = 24;
-const WHEEL_UP: u64 = 25;
-const WHEEL_DOWN: u64 = 26;
-const WHEEL_LEFT: u64 = 27;
-const WHEEL_RIGHT: u64 = 28;
 
 pub const MOUSE_INVALID_BIT: u64 = 31;
 
 // Uses 32/32 bits
-const DELTA_MASK: u64 = u16::MAX as u64;
-const X_DELTA_SHIFT: u64 = 32;
-const Y_DELTA_SHIFT: u64 = 32 + u16::BITS as u64;
+const WHEEL_MASK: u64 = u16::MAX as u64;
+const WHEEL_X_SHIFT: u64 = 32;
+const WHEEL_Y_SHIFT: u64 = 32 + u16::BITS as u64;
 
 impl MouseState {
     pub fn get_x_pos(self) -> u32 {
@@ -44,96 +40,116 @@ impl MouseState {
         self.0 & 1 << MIDDLE_BUTTON_SHIFT != 0
     }
 
-    pub fn get_wheel_up(self) -> bool {
-        self.0 & 1 << WHEEL_UP != 0
+    /// Returns the number of steps the wheel was scrolled horizontally.
+    /// Positive values are to the right.
+    pub fn get_wheel_x(self) -> i32 {
+        ((self.0 & WHEEL_MASK << WHEEL_X_SHIFT) >> WHEEL_X_SHIFT) as i16 as i32
     }
 
-    pub fn get_wheel_down(self) -> bool {
-        self.0 & 1 << WHEEL_DOWN != 0
+    /// Returns the number of steps the wheel was scrolled vertically.
+    /// Positive values are away from the user, or up.
+    pub fn get_wheel_y(self) -> i32 {
+        ((self.0 & WHEEL_MASK << WHEEL_Y_SHIFT) >> WHEEL_Y_SHIFT) as i16 as i32
     }
 
-    pub fn get_wheel_left(self) -> bool {
-        self.0 & 1 << WHEEL_LEFT != 0
+    pub fn get_wheel_up(self) -> bool {
+        self.get_wheel_y() > 0
     }
 
-    pub fn get_wheel_right(self) -> bool {
-        self.0 & 1 << WHEEL_RIGHT != 0
+    pub fn get_wheel_down(self) -> bool {
+        self.get_wheel_y() < 0
     }
 
-    pub fn get_x_delta(self) -> i32 {
-        ((self.0 & DELTA_MASK << X_DELTA_SHIFT) >> X_DELTA_SHIFT) as i16 as i32
+    pub fn get_wheel_left(self) -> bool {
+        self.get_wheel_x() < 0
     }
 
-    pub fn get_y_delta(self) -> i32 {
-        ((self.0 & DELTA_MASK << Y_DELTA_SHIFT) >> Y_DELTA_SHIFT) as i16 as i32
+    pub fn get_wheel_right(self) -> bool {
+        self.get_wheel_x() > 0
     }
 
     pub fn set_x_pos(&mut self, value: u32) {
         self.0 &= !(MASK << X_SHIFT);
-        self.0 |= (value as u64) << X_SHIFT;
+        self.0 |= (value as u64 & MASK) << X_SHIFT;
     }
 
     pub fn set_y_pos(&mut self, value: u32) {
         self.0 &= !(MASK << Y_SHIFT);
-        self.0 |= (value as u64) << Y_SHIFT;
+        self.0 |= (value as u64 & MASK) << Y_SHIFT;
     }
 
     pub fn set_left_button(&mut self, value: bool) {
-        let value = value as u64;
-        self.0 &= !(value << LEFT_BUTTON_SHIFT);
-        self.0 |= value << LEFT_BUTTON_SHIFT;
+        self.0 &= !(1 << LEFT_BUTTON_SHIFT);
+        self.0 |= (value as u64) << LEFT_BUTTON_SHIFT;
     }
 
     pub fn set_middle_button(&mut self, value: bool) {
-        let value = value as u64;
-        self.0 &= !(value << MIDDLE_BUTTON_SHIFT);
-        self.0 |= value << MIDDLE_BUTTON_SHIFT;
+        self.0 &= !(1 << MIDDLE_BUTTON_SHIFT);
+        self.0 |= (value as u64) << MIDDLE_BUTTON_SHIFT;
     }
 
     pub fn set_right_button(&mut self, value: bool) {
-        let value = value as u64;
-        self.0 &= !(value << RIGHT_BUTTON_SHIFT);
-        self.0 |= value << RIGHT_BUTTON_SHIFT;
+        self.0 &= !(1 << RIGHT_BUTTON_SHIFT);
+        self.0 |= (value as u64) << RIGHT_BUTTON_SHIFT;
+    }
+
+    /// Sets the horizontal wheel steps. Values past the range of an i16 are clamped.
+    pub fn set_wheel_x(&mut self, value: i32) {
+        let value = value.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.0 &= !(WHEEL_MASK << WHEEL_X_SHIFT);
+        self.0 |= (value as u16 as u64) << WHEEL_X_SHIFT;
     }
 
-    pub fn set_wheel_up(&mut self, value: bool) {
-        let value = value as u64;
-        self.0 &= !(value << WHEEL_LEFT);
-        self.0 |= value << WHEEL_LEFT;
+    /// Sets the vertical wheel steps. Values past the range of an i16 are clamped.
+    pub fn set_wheel_y(&mut self, value: i32) {
+        let value = value.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.0 &= !(WHEEL_MASK << WHEEL_Y_SHIFT);
+        self.0 |= (value as u16 as u64) << WHEEL_Y_SHIFT;
     }
+}
+
+/// The distance the mouse moved during a frame, stored as fixed point
+/// numbers so that movements smaller than a single unit aren't lost.
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable, PartialEq, Eq)]
+#[repr(C)]
+pub struct MouseMotion {
+    x: i32,
+    y: i32,
+}
 
-    pub fn set_wheel_down(&mut self, value: bool) {
-        let value = value as u64;
-        self.0 &= !(value << WHEEL_DOWN);
-        self.0 |= value << WHEEL_DOWN;
+impl MouseMotion {
+    /// The number of bits used for the fractional part.
+    pub const FRACTION_BITS: u32 = 8;
+
+    const SCALE: f64 = (1 << Self::FRACTION_BITS) as f64;
+
+    /// Rounds the value down to the precision which can be stored.
+    pub fn quantize(value: f64) -> f64 {
+        (value * Self::SCALE).trunc() / Self::SCALE
     }
 
-    pub fn set_wheel_left(&mut self, value: bool) {
-        let value = value as u64;
-        self.0 &= !(value << WHEEL_LEFT);
-        self.0 |= value << WHEEL_LEFT;
+    pub fn get_x(self) -> f32 {
+        (self.x as f64 / Self::SCALE) as f32
     }
 
-    pub fn set_wheel_right(&mut self, value: bool) {
-        let value = value as u64;
-        self.0 &= !(value << WHEEL_RIGHT);
-        self.0 |= value << WHEEL_RIGHT;
+    pub fn get_y(self) -> f32 {
+        (self.y as f64 / Self::SCALE) as f32
     }
 
-    pub fn set_x_delta(&mut self, value: i32) {
-        self.0 &= !(DELTA_MASK << X_DELTA_SHIFT);
-        self.0 |= (value as u32 as u64) << X_DELTA_SHIFT;
+    /// Sets the x motion. Values past the range which can be stored are clamped.
+    pub fn set_x(&mut self, value: f64) {
+        self.x = (value * Self::SCALE) as i32;
     }
 
-    pub fn set_y_delta(&mut self, value: i32) {
-        self.0 &= !(DELTA_MASK << Y_DELTA_SHIFT);
-        self.0 |= (value as u32 as u64) << Y_DELTA_SHIFT;
+    /// Sets the y motion. Values past the range which can be stored are clamped.
+    pub fn set_y(&mut self, value: f64) {
+        self.y = (value * Self::SCALE) as i32;
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::MouseState;
+    use crate::{MouseMotion, MouseState};
 
     #[test]
     fn test_mouse_state() {
@@ -173,31 +189,55 @@ mod tests {
     }
 
     #[test]
-    fn test_mouse_state_deltas() {
-        // Same as above, but with deltas
+    fn test_mouse_state_wheel() {
+        // Same as above, but with the wheel
         let mut out = MouseState::default();
-        let x = -1920;
-        let y = -1080;
-        out.set_x_delta(x);
-        out.set_y_delta(y);
-
-        assert_eq!(out.get_x_delta(), x);
-        assert_eq!(out.get_y_delta(), y);
+        let x = -3;
+        let y = 2;
+        out.set_wheel_x(x);
+        out.set_wheel_y(y);
+
+        assert_eq!(out.get_wheel_x(), x);
+        assert_eq!(out.get_wheel_y(), y);
+        assert!(out.get_wheel_left());
+        assert!(out.get_wheel_up());
+        assert!(!out.get_wheel_right());
+        assert!(!out.get_wheel_down());
 
         assert_eq!(out.get_left_button_down(), false);
         assert_eq!(out.get_middle_button_down(), false);
         assert_eq!(out.get_right_button_down(), false);
 
-        out.set_x_delta(0);
-        out.set_y_delta(0);
+        out.set_wheel_x(0);
+        out.set_wheel_y(0);
         assert_eq!(out.get_x_pos(), 0);
         assert_eq!(out.get_y_pos(), 0);
         assert_eq!(out.0, 0);
 
+        out.set_wheel_y(i32::MIN);
+        assert_eq!(out.get_wheel_y(), i16::MIN as i32);
+        assert_eq!(out.get_wheel_x(), 0);
+
+        // Positions out of range don't overflow into other fields
+        out.set_x_pos(u32::MAX);
+        assert_eq!(out.get_y_pos(), 0);
+        assert_eq!(out.get_left_button_down(), false);
+        assert_eq!(out.get_wheel_y(), i16::MIN as i32);
+    }
+
+    #[test]
+    fn test_mouse_state_buttons() {
+        // The buttons are set and cleared without touching the wheel
+        let mut out = MouseState::default();
+        out.set_wheel_x(i16::MAX as i32);
+        out.set_wheel_y(-1);
+
         out.set_left_button(false);
         out.set_middle_button(false);
         out.set_right_button(false);
-        assert_eq!(out.0, 0);
+        assert_eq!(out.get_left_button_down(), false);
+        assert_eq!(out.get_middle_button_down(), false);
+        assert_eq!(out.get_right_button_down(), false);
 
         out.set_left_button(true);
         out.set_middle_button(true);
@@ -205,8 +245,41 @@ mod tests {
         assert_eq!(out.get_left_button_down(), true);
         assert_eq!(out.get_middle_button_down(), true);
         assert_eq!(out.get_right_button_down(), true);
+        assert_eq!(out.get_wheel_x(), i16::MAX as i32);
+        assert_eq!(out.get_wheel_y(), -1);
+
+        out.set_middle_button(false);
+        assert_eq!(out.get_left_button_down(), true);
+        assert_eq!(out.get_middle_button_down(), false);
+        assert_eq!(out.get_right_button_down(), true);
+
+        out.set_left_button(false);
+        out.set_right_button(false);
+        out.set_wheel_x(0);
+        out.set_wheel_y(0);
+        assert_eq!(out.0, 0);
+
+        // The wheel doesn't touch the buttons
+        out.set_right_button(true);
+        out.set_wheel_y(i32::MAX);
+        out.set_wheel_y(0);
+        assert_eq!(out.get_right_button_down(), true);
+        assert_eq!(out.get_left_button_down(), false);
+    }
+
+    #[test]
+    fn test_mouse_motion() {
+        let mut motion = MouseMotion::default();
+        motion.set_x(-1920.5);
+        motion.set_y(0.25);
+
+        assert_eq!(motion.get_x(), -1920.5);
+        assert_eq!(motion.get_y(), 0.25);
+
+        let quantized = MouseMotion::quantize(0.1);
+        assert!(quantized <= 0.1 && 0.1 - quantized < 1.0 / 256.0);
 
-        out.set_x_delta(123);
-        assert_eq!(out.get_x_delta(), 123);
+        motion.set_x(quantized);
+        assert_eq!(motion.get_x() as f64, quantized);
     }
 }
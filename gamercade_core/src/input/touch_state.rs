@@ -0,0 +1,94 @@
+use bytemuck::{Pod, Zeroable};
+
+/// The most touch points which are tracked at once.
+pub const MAX_TOUCH_POINTS: usize = 4;
+
+// Uses 23/32 bits
+const MASK: u32 = 0b111_1111_1111; // 11 bits
+const X_SHIFT: u32 = 0;
+const Y_SHIFT: u32 = 11;
+const ACTIVE_SHIFT: u32 = 22;
+
+/// A single touch point, packed into 32 bits.
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable, PartialEq, Eq)]
+#[repr(C)]
+pub struct TouchPoint(pub u32);
+
+impl TouchPoint {
+    pub fn get_active(self) -> bool {
+        self.0 & 1 << ACTIVE_SHIFT != 0
+    }
+
+    pub fn get_x_pos(self) -> u32 {
+        (self.0 & MASK << X_SHIFT) >> X_SHIFT
+    }
+
+    pub fn get_y_pos(self) -> u32 {
+        (self.0 & MASK << Y_SHIFT) >> Y_SHIFT
+    }
+
+    pub fn set_active(&mut self, value: bool) {
+        self.0 &= !(1 << ACTIVE_SHIFT);
+        self.0 |= (value as u32) << ACTIVE_SHIFT;
+    }
+
+    pub fn set_x_pos(&mut self, value: u32) {
+        self.0 &= !(MASK << X_SHIFT);
+        self.0 |= (value & MASK) << X_SHIFT;
+    }
+
+    pub fn set_y_pos(&mut self, value: u32) {
+        self.0 &= !(MASK << Y_SHIFT);
+        self.0 |= (value & MASK) << Y_SHIFT;
+    }
+}
+
+/// The state of all touch points. Each point keeps the same index
+/// for as long as the finger stays on the screen.
+#[derive(Debug, Default, Clone, Copy, Pod, Zeroable, PartialEq, Eq)]
+#[repr(C)]
+pub struct TouchState(pub [TouchPoint; MAX_TOUCH_POINTS]);
+
+impl TouchState {
+    pub fn get_point(self, index: usize) -> Option<TouchPoint> {
+        self.0.get(index).copied()
+    }
+
+    pub fn set_point(&mut self, index: usize, point: TouchPoint) {
+        if let Some(target) = self.0.get_mut(index) {
+            *target = point;
+        }
+    }
+
+    /// Returns the number of fingers currently touching the screen.
+    pub fn active_count(self) -> u32 {
+        self.0.iter().filter(|point| point.get_active()).count() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_touch_state() {
+        let mut point = TouchPoint::default();
+        point.set_x_pos(1920 - 1);
+        point.set_y_pos(1080 - 1);
+        point.set_active(true);
+
+        assert_eq!(point.get_x_pos(), 1920 - 1);
+        assert_eq!(point.get_y_pos(), 1080 - 1);
+        assert!(point.get_active());
+
+        let mut state = TouchState::default();
+        state.set_point(2, point);
+        assert_eq!(state.get_point(2), Some(point));
+        assert_eq!(state.active_count(), 1);
+        assert_eq!(state.get_point(MAX_TOUCH_POINTS), None);
+
+        point.set_active(false);
+        assert_eq!(point.get_x_pos(), 1920 - 1);
+        assert!(!point.get_active());
+    }
+}
@@ -1,7 +1,8 @@
 use eframe::egui::{self, Ui};
 use gamercade_core::{
-    FrameRate,
-    Resolution::{High, Low, Medium, UltraHigh, UltraLow, VeryHigh, VeryLow},
+    ButtonCode, FrameRate, InputAction, Resolution,
+    Resolution::{Custom, High, Low, Medium, UltraHigh, UltraLow, VeryHigh, VeryLow},
+    MAX_INPUT_ACTIONS,
 };
 use gamercade_fs::EditorRom;
 
@@ -11,10 +12,13 @@ pub struct RomEditor {}
 impl RomEditor {
     pub fn draw_contents(&self, ui: &mut Ui, rom: &mut EditorRom) {
         ui.group(|ui| {
+            let (aspect_x, aspect_y) = rom.resolution.aspect_ratio();
             ui.label(format!(
-                "Resolution: {} x {}",
+                "Resolution: {} x {} ({}:{})",
                 rom.resolution.width(),
-                rom.resolution.height()
+                rom.resolution.height(),
+                aspect_x,
+                aspect_y
             ));
             ui.horizontal(|ui| {
                 ui.selectable_value(&mut rom.resolution, UltraLow, "Ultra Low");
@@ -24,7 +28,36 @@ impl RomEditor {
                 ui.selectable_value(&mut rom.resolution, High, "High");
                 ui.selectable_value(&mut rom.resolution, VeryHigh, "Very High");
                 ui.selectable_value(&mut rom.resolution, UltraHigh, "Ultra High");
+
+                let is_custom = matches!(rom.resolution, Custom { .. });
+                if ui.selectable_label(is_custom, "Custom").clicked() && !is_custom {
+                    rom.resolution = Custom {
+                        width: rom.resolution.width(),
+                        height: rom.resolution.height(),
+                    };
+                }
             });
+
+            if let Custom { width, height } = &mut rom.resolution {
+                let range = Resolution::MIN_DIMENSION..=Resolution::MAX_DIMENSION;
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::DragValue::new(width)
+                            .clamp_range(range.clone())
+                            .prefix("Width: "),
+                    );
+                    ui.add(
+                        egui::DragValue::new(height)
+                            .clamp_range(range)
+                            .prefix("Height: "),
+                    );
+                });
+
+                // Keep the total pixel count within bounds
+                if *width * *height > Resolution::MAX_TOTAL_PIXELS {
+                    *height = Resolution::MAX_TOTAL_PIXELS / *width;
+                }
+            }
         });
 
         ui.group(|ui| {
@@ -57,5 +90,78 @@ impl RomEditor {
                 );
             });
         });
+
+        ui.group(|ui| {
+            ui.label(format!(
+                "Input Actions: {} / {}",
+                rom.input_actions.len(),
+                MAX_INPUT_ACTIONS
+            ));
+            ui.label("Players see these names, and can change which buttons trigger each action.");
+
+            let mut removed = None;
+            rom.input_actions
+                .iter_mut()
+                .enumerate()
+                .for_each(|(action_id, action)| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}:", action_id));
+                        ui.text_edit_singleline(&mut action.name);
+                        draw_default_buttons_combo(ui, action_id, action);
+
+                        if ui.button("Remove").clicked() {
+                            removed = Some(action_id);
+                        }
+                    });
+                });
+
+            if let Some(action_id) = removed {
+                rom.input_actions.remove(action_id);
+            }
+
+            if ui
+                .add_enabled(
+                    rom.input_actions.len() < MAX_INPUT_ACTIONS,
+                    egui::Button::new("Add Action"),
+                )
+                .clicked()
+            {
+                rom.input_actions.push(InputAction {
+                    name: format!("Action {}", rom.input_actions.len()),
+                    default_buttons: Vec::new(),
+                });
+            }
+        });
     }
 }
+
+fn draw_default_buttons_combo(ui: &mut Ui, action_id: usize, action: &mut InputAction) {
+    let selected_text = if action.default_buttons.is_empty() {
+        "Unbound".to_string()
+    } else {
+        action
+            .default_buttons
+            .iter()
+            .map(|button| format!("{:?}", button))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    egui::ComboBox::from_id_source(("input_action_buttons", action_id))
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            ButtonCode::ALL.iter().for_each(|button| {
+                let selected = action.default_buttons.contains(button);
+                if ui
+                    .selectable_label(selected, format!("{:?}", button))
+                    .clicked()
+                {
+                    if selected {
+                        action.default_buttons.retain(|bound| bound != button);
+                    } else {
+                        action.default_buttons.push(*button);
+                    }
+                }
+            });
+        });
+}
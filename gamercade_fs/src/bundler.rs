@@ -1,5 +1,5 @@
 use gamercade_audio::SoundRom;
-use gamercade_core::{FrameRate, GraphicsData, Resolution};
+use gamercade_core::{FrameRate, GraphicsData, InputAction, Resolution};
 
 use crate::Rom;
 
@@ -24,6 +24,7 @@ pub trait GameAssetProvider {
     fn player_count(&self) -> (usize, usize);
     fn graphics(&self) -> GraphicsData;
     fn sounds(&self) -> SoundRom;
+    fn input_actions(&self) -> Vec<InputAction>;
 }
 
 /// Generates a ready-to-use Rom.
@@ -39,5 +40,6 @@ where
         graphics: asset_provider.graphics(),
         sounds: asset_provider.sounds(),
         code: code_provider.code().into(),
+        input_actions: asset_provider.input_actions(),
     }
 }
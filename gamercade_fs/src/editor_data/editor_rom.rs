@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use gamercade_audio::SoundRom;
-use gamercade_core::{FrameRate, GraphicsData, Resolution};
+use gamercade_core::{FrameRate, GraphicsData, InputAction, Resolution};
 use serde::{Deserialize, Serialize};
 
 use crate::GameAssetProvider;
@@ -15,12 +15,24 @@ pub struct EditorRom {
     pub player_count: (usize, usize),
     pub graphics: EditorGraphicsData,
     pub sounds: EditorSoundData,
+    #[serde(default)]
+    pub input_actions: Vec<InputAction>,
 }
 
 impl EditorRom {
     pub fn try_load(path: &PathBuf) -> Result<EditorRom, String> {
         let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
-        serde_json::from_str::<EditorRom>(&text).map_err(|e| e.to_string())
+        let rom = serde_json::from_str::<EditorRom>(&text).map_err(|e| e.to_string())?;
+
+        if !rom.resolution.is_valid() {
+            return Err(format!(
+                "Editor rom has an invalid resolution: {} x {}",
+                rom.resolution.width(),
+                rom.resolution.height()
+            ));
+        }
+
+        Ok(rom)
     }
 
     pub fn try_save(&self, path: &PathBuf) -> Result<(), String> {
@@ -40,6 +52,7 @@ impl Default for EditorRom {
             frame_rate: FrameRate::default(),
             graphics: EditorGraphicsData::default(),
             sounds: EditorSoundData::default(),
+            input_actions: Vec::new(),
         }
     }
 }
@@ -64,4 +77,8 @@ impl GameAssetProvider for EditorRom {
     fn sounds(&self) -> SoundRom {
         (&self.sounds).into()
     }
+
+    fn input_actions(&self) -> Vec<InputAction> {
+        self.input_actions.clone()
+    }
 }
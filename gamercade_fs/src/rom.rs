@@ -7,7 +7,7 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 use gamercade_audio::SoundRom;
-use gamercade_core::{FrameRate, GraphicsData, Resolution};
+use gamercade_core::{FrameRate, GraphicsData, InputAction, Resolution};
 
 use crate::{bundle, EditorRom, GameAssetProvider, GameCodeProvider};
 
@@ -19,6 +19,32 @@ pub struct Rom {
     pub graphics: GraphicsData,
     pub sounds: SoundRom,
     pub code: Box<[u8]>,
+    pub input_actions: Vec<InputAction>,
+}
+
+/// The layout of roms bundled before input actions were added.
+#[derive(Deserialize)]
+struct LegacyRom {
+    resolution: Resolution,
+    frame_rate: FrameRate,
+    player_count: (usize, usize),
+    graphics: GraphicsData,
+    sounds: SoundRom,
+    code: Box<[u8]>,
+}
+
+impl From<LegacyRom> for Rom {
+    fn from(rom: LegacyRom) -> Self {
+        Self {
+            resolution: rom.resolution,
+            frame_rate: rom.frame_rate,
+            player_count: rom.player_count,
+            graphics: rom.graphics,
+            sounds: rom.sounds,
+            code: rom.code,
+            input_actions: Vec::new(),
+        }
+    }
 }
 
 impl Default for Rom {
@@ -30,6 +56,7 @@ impl Default for Rom {
             sounds: Default::default(),
             code: Default::default(),
             player_count: (1, 1),
+            input_actions: Default::default(),
         }
     }
 }
@@ -55,7 +82,22 @@ impl Rom {
                 // We don't care about how many bytes are read
                 let _ = reader.read_to_end(&mut buffer).map_err(|e| e.to_string());
 
-                bincode::deserialize_from::<_, Rom>(&*buffer).map_err(|e| e.to_string())
+                let rom = match bincode::deserialize_from::<_, Rom>(&*buffer) {
+                    Ok(rom) => rom,
+                    Err(e) => bincode::deserialize_from::<_, LegacyRom>(&*buffer)
+                        .map(Rom::from)
+                        .map_err(|_| e.to_string())?,
+                };
+
+                if !rom.resolution.is_valid() {
+                    return Err(format!(
+                        "Rom has an invalid resolution: {} x {}",
+                        rom.width(),
+                        rom.height()
+                    ));
+                }
+
+                Ok(rom)
             }
             Some("wasm") => {
                 println!("No assets provided. Using default Asset pack.");
@@ -106,4 +148,8 @@ impl GameAssetProvider for Rom {
     fn sounds(&self) -> SoundRom {
         self.sounds.clone()
     }
+
+    fn input_actions(&self) -> Vec<InputAction> {
+        self.input_actions.clone()
+    }
 }
@@ -0,0 +1,31 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use clap::Parser;
+use gamercade_lobby::{LobbyServer, DEFAULT_LOBBY_PORT};
+
+/// A lobby server, where consoles can find each other to play networked games.
+#[derive(Parser, Debug)]
+struct Cli {
+    /// The port to listen on.
+    #[clap(short, long, value_parser, default_value_t = DEFAULT_LOBBY_PORT)]
+    port: u16,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, cli.port));
+    let server = match LobbyServer::bind(addr) {
+        Ok(server) => server,
+        Err(e) => {
+            println!("Unable to listen on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Lobby server listening on {}", addr);
+    if let Err(e) = server.run() {
+        println!("Lobby server stopped: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,31 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use clap::Parser;
+use gamercade_lobby::{RelayServer, DEFAULT_RELAY_PORT};
+
+/// A relay server, which passes packets between consoles that can't reach each other directly.
+#[derive(Parser, Debug)]
+struct Cli {
+    /// The UDP port to listen on.
+    #[clap(short, long, value_parser, default_value_t = DEFAULT_RELAY_PORT)]
+    port: u16,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, cli.port));
+    let server = match RelayServer::bind(addr) {
+        Ok(server) => server,
+        Err(e) => {
+            println!("Unable to listen on {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Relay server listening on {}", addr);
+    if let Err(e) = server.run() {
+        println!("Relay server stopped: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    io,
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    time::Duration,
+};
+
+use crate::protocol::{
+    read_message, write_message, ClientMessage, ServerMessage, PROTOCOL_VERSION,
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connection to a lobby server. Messages from the server are read on
+/// a background thread, so polling never blocks.
+pub struct LobbyClient {
+    stream: TcpStream,
+    receiver: Receiver<ServerMessage>,
+    connected: bool,
+}
+
+impl LobbyClient {
+    pub fn connect(addr: SocketAddr) -> io::Result<Self> {
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        write_message(
+            &mut stream,
+            &ClientMessage::Hello {
+                version: PROTOCOL_VERSION,
+            },
+        )?;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut reader = stream.try_clone()?;
+        std::thread::spawn(move || {
+            while let Ok(message) = read_message(&mut reader) {
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Ok(Self {
+            stream,
+            receiver,
+            connected: true,
+        })
+    }
+
+    /// False once the server has closed the connection.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        let result = write_message(&mut self.stream, message);
+        if result.is_err() {
+            self.connected = false;
+        }
+        result
+    }
+
+    /// Returns every message received since the last poll.
+    pub fn poll(&mut self) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(message) => messages.push(message),
+                Err(TryRecvError::Empty) => return messages,
+                Err(TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    return messages;
+                }
+            }
+        }
+    }
+
+    /// Blocks until the next message arrives, or the timeout passes.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<ServerMessage> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => {
+                self.connected = false;
+                None
+            }
+        }
+    }
+}
+
+impl Drop for LobbyClient {
+    fn drop(&mut self) {
+        // Also stops the reading thread
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
@@ -0,0 +1,13 @@
+mod client;
+mod protocol;
+mod relay;
+mod relay_server;
+mod relay_socket;
+mod server;
+
+pub use client::*;
+pub use protocol::*;
+pub use relay::*;
+pub use relay_server::*;
+pub use relay_socket::*;
+pub use server::*;
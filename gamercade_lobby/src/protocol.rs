@@ -0,0 +1,156 @@
+use std::{
+    io::{self, Read, Write},
+    net::SocketAddr,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Bumped whenever a message changes, so mismatched clients and servers can tell.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The port the lobby server listens on, unless told otherwise.
+pub const DEFAULT_LOBBY_PORT: u16 = 7000;
+
+/// The most players a single room can hold.
+pub const MAX_ROOM_PLAYERS: usize = 8;
+
+/// Longer room names are refused, measured in bytes.
+pub const MAX_ROOM_NAME_LEN: usize = 32;
+
+/// Longer game names are refused, measured in bytes.
+pub const MAX_GAME_NAME_LEN: usize = 128;
+
+/// Longer player names are refused, measured in bytes.
+pub const MAX_PLAYER_NAME_LEN: usize = 32;
+
+/// The most rooms sent in a single room list. Along with the name limits,
+/// this keeps the list within the message size limit.
+pub const MAX_LISTED_ROOMS: usize = 64;
+
+/// Messages larger than this are treated as a broken connection.
+pub(crate) const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+pub type RoomId = u32;
+
+/// Describes a console taking part in a room.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlayerInfo {
+    pub name: String,
+
+    /// The UDP port the console will play the game on
+    pub game_port: u16,
+
+    /// How many players are sharing this console
+    pub local_players: usize,
+}
+
+/// A room as seen by the clients browsing the lobby.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RoomInfo {
+    pub id: RoomId,
+    pub name: String,
+
+    /// The game being played, so players can pick the same game before joining
+    pub game: String,
+    pub max_players: usize,
+
+    /// The members, in the order they joined. The first is the host.
+    pub members: Vec<PlayerInfo>,
+}
+
+impl RoomInfo {
+    /// The total number of players, counting every member's local players.
+    pub fn player_count(&self) -> usize {
+        self.members.iter().map(|member| member.local_players).sum()
+    }
+
+    pub fn host(&self) -> Option<&PlayerInfo> {
+        self.members.first()
+    }
+}
+
+/// A member of a started game, along with where to reach them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GamePeer {
+    pub name: String,
+    pub addr: SocketAddr,
+    pub local_players: usize,
+}
+
+/// Sent to every member once the host starts the game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GameStart {
+    pub seed: u64,
+
+    /// Every member in player order. Each member's local players
+    /// take the handles directly after the previous member's.
+    pub peers: Vec<GamePeer>,
+
+    /// Which of the peers is the receiving console
+    pub local_index: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// Must be sent first, before any other message.
+    Hello {
+        version: u32,
+    },
+    ListRooms,
+    CreateRoom {
+        name: String,
+        game: String,
+        max_players: usize,
+        player: PlayerInfo,
+    },
+    JoinRoom {
+        room: RoomId,
+        player: PlayerInfo,
+    },
+    LeaveRoom,
+
+    /// Only the host of a room can start the game.
+    StartGame,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    Welcome,
+    RoomList(Vec<RoomInfo>),
+
+    /// The room the client is in, sent whenever its members change.
+    RoomUpdated(RoomInfo),
+
+    /// The client has left the room, or the host closed it.
+    RoomClosed,
+    GameStarted(GameStart),
+    Error(String),
+}
+
+/// Writes the message, prefixed by its length.
+pub fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let body =
+        bincode::serialize(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    writer.write_all(&(body.len() as u32).to_le_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// Blocks until a whole message has been read.
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> io::Result<T> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Message of {} bytes is too large", len),
+        ));
+    }
+
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    bincode::deserialize(&body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
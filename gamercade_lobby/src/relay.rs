@@ -0,0 +1,72 @@
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever a relay packet changes, so mismatched consoles and relays can tell.
+pub const RELAY_PROTOCOL_VERSION: u32 = 1;
+
+/// The UDP port the relay server listens on, unless told otherwise.
+pub const DEFAULT_RELAY_PORT: u16 = 7001;
+
+/// Session codes longer than this are refused.
+pub const MAX_SESSION_CODE_LEN: usize = 32;
+
+/// Large enough for any packet a console sends, along with the relay's header.
+pub const MAX_RELAY_PACKET_SIZE: usize = 8192;
+
+/// Identifies a console within a session. Matches the console's instance id.
+pub type MemberId = u16;
+
+/// Packets sent over UDP between consoles and the relay server, and directly
+/// between consoles once hole punching has opened a path through their NATs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RelayPacket {
+    /// Sent to the relay regularly, which keeps both the registration
+    /// and the console's NAT mapping alive.
+    Register {
+        version: u32,
+        code: String,
+        member: MemberId,
+    },
+
+    /// The relay's reply to `Register`. Every member of the session, along
+    /// with their public address as seen by the relay.
+    Members(Vec<(MemberId, SocketAddr)>),
+
+    /// Asks the relay to pass the payload on to another member of the session.
+    Forward {
+        to: MemberId,
+        payload: Vec<u8>,
+    },
+
+    /// A payload passed on by the relay.
+    Forwarded {
+        from: MemberId,
+        payload: Vec<u8>,
+    },
+
+    /// Sent directly between members until one of them replies.
+    Punch {
+        from: MemberId,
+    },
+    PunchReply {
+        from: MemberId,
+    },
+
+    /// A payload sent directly to another member, once punching has succeeded.
+    Direct {
+        from: MemberId,
+        payload: Vec<u8>,
+    },
+    Error(String),
+}
+
+impl RelayPacket {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bincode::deserialize(bytes).ok()
+    }
+}
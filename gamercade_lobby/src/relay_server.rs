@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::relay::{
+    MemberId, RelayPacket, MAX_RELAY_PACKET_SIZE, MAX_SESSION_CODE_LEN, RELAY_PROTOCOL_VERSION,
+};
+
+/// Members which haven't registered again within this time are forgotten.
+const MEMBER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the server wakes up to forget timed out members, even if nothing was received.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Member {
+    code: String,
+    id: MemberId,
+    last_seen: Instant,
+}
+
+/// Passes packets between the consoles of a session, for consoles which can't
+/// reach each other directly. Consoles join a session by registering with the
+/// same session code. Also acts as the rendezvous for hole punching, by telling
+/// each member the public address of the others.
+pub struct RelayServer {
+    socket: UdpSocket,
+    members: HashMap<SocketAddr, Member>,
+
+    /// Each member's address, by session code and member id
+    sessions: HashMap<(String, MemberId), SocketAddr>,
+}
+
+impl RelayServer {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(EXPIRY_INTERVAL))?;
+
+        Ok(Self {
+            socket,
+            members: HashMap::new(),
+            sessions: HashMap::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Relays packets until the socket fails.
+    pub fn run(mut self) -> io::Result<()> {
+        let mut buffer = [0; MAX_RELAY_PACKET_SIZE];
+        let mut last_expired = Instant::now();
+
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((number_of_bytes, addr)) => {
+                    if let Some(packet) = RelayPacket::from_bytes(&buffer[..number_of_bytes]) {
+                        self.handle_packet(addr, packet);
+                    }
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+                // Windows reports a client closing its socket as an error, which can be ignored
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => (),
+                Err(e) => return Err(e),
+            }
+
+            if last_expired.elapsed() >= EXPIRY_INTERVAL {
+                self.expire_members();
+                last_expired = Instant::now();
+            }
+        }
+    }
+
+    fn send(&self, addr: SocketAddr, packet: &RelayPacket) {
+        // Lost packets are resent by the consoles
+        let _ = self.socket.send_to(&packet.to_bytes(), addr);
+    }
+
+    fn handle_packet(&mut self, addr: SocketAddr, packet: RelayPacket) {
+        match packet {
+            RelayPacket::Register {
+                version,
+                code,
+                member,
+            } => self.register(addr, version, code, member),
+            RelayPacket::Forward { to, payload } => {
+                let from = match self.members.get(&addr) {
+                    Some(member) => member,
+                    None => return self.send(addr, &RelayPacket::Error("Not registered".into())),
+                };
+
+                // Members may still be joining, so unknown members are ignored
+                if let Some(to) = self.sessions.get(&(from.code.clone(), to)) {
+                    self.send(
+                        *to,
+                        &RelayPacket::Forwarded {
+                            from: from.id,
+                            payload,
+                        },
+                    );
+                }
+            }
+            // Only sent between consoles
+            _ => (),
+        }
+    }
+
+    fn register(&mut self, addr: SocketAddr, version: u32, code: String, id: MemberId) {
+        if version != RELAY_PROTOCOL_VERSION {
+            return self.send(
+                addr,
+                &RelayPacket::Error(format!(
+                    "The relay is running version {}, but this console is running version {}",
+                    RELAY_PROTOCOL_VERSION, version
+                )),
+            );
+        }
+
+        if code.is_empty() || code.len() > MAX_SESSION_CODE_LEN {
+            return self.send(
+                addr,
+                &RelayPacket::Error(format!(
+                    "Session codes must be between 1 and {} bytes long",
+                    MAX_SESSION_CODE_LEN
+                )),
+            );
+        }
+
+        // A console which registered from another address, or under another code, has restarted
+        if let Some(previous) = self.sessions.insert((code.clone(), id), addr) {
+            if previous != addr {
+                self.members.remove(&previous);
+            }
+        }
+        if let Some(previous) = self.members.insert(
+            addr,
+            Member {
+                code: code.clone(),
+                id,
+                last_seen: Instant::now(),
+            },
+        ) {
+            if previous.code != code || previous.id != id {
+                self.sessions.remove(&(previous.code, previous.id));
+            }
+        }
+
+        let mut members = self
+            .sessions
+            .iter()
+            .filter(|((member_code, _), _)| *member_code == code)
+            .map(|((_, id), addr)| (*id, *addr))
+            .collect::<Vec<_>>();
+        members.sort_unstable();
+
+        self.send(addr, &RelayPacket::Members(members));
+    }
+
+    fn expire_members(&mut self) {
+        let sessions = &mut self.sessions;
+        self.members.retain(|_, member| {
+            let alive = member.last_seen.elapsed() < MEMBER_TIMEOUT;
+            if !alive {
+                println!("Member {} of session {} timed out", member.id, member.code);
+                sessions.remove(&(member.code.clone(), member.id));
+            }
+            alive
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn start_server() -> SocketAddr {
+        let server = RelayServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+        addr
+    }
+
+    fn client() -> UdpSocket {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(TIMEOUT)).unwrap();
+        socket
+    }
+
+    fn send(socket: &UdpSocket, server: SocketAddr, packet: RelayPacket) {
+        socket.send_to(&packet.to_bytes(), server).unwrap();
+    }
+
+    fn recv(socket: &UdpSocket) -> RelayPacket {
+        let mut buffer = [0; MAX_RELAY_PACKET_SIZE];
+        let (number_of_bytes, _) = socket.recv_from(&mut buffer).unwrap();
+        RelayPacket::from_bytes(&buffer[..number_of_bytes]).unwrap()
+    }
+
+    fn register(socket: &UdpSocket, server: SocketAddr, code: &str, member: MemberId) {
+        send(
+            socket,
+            server,
+            RelayPacket::Register {
+                version: RELAY_PROTOCOL_VERSION,
+                code: code.to_string(),
+                member,
+            },
+        );
+    }
+
+    #[test]
+    fn test_register_and_forward() {
+        let server = start_server();
+        let first = client();
+        let second = client();
+        let other_session = client();
+
+        register(&first, server, "code", 1);
+        assert_eq!(
+            recv(&first),
+            RelayPacket::Members(vec![(1, first.local_addr().unwrap())])
+        );
+
+        register(&other_session, server, "other", 2);
+        recv(&other_session);
+
+        register(&second, server, "code", 2);
+        assert_eq!(
+            recv(&second),
+            RelayPacket::Members(vec![
+                (1, first.local_addr().unwrap()),
+                (2, second.local_addr().unwrap()),
+            ])
+        );
+
+        send(
+            &first,
+            server,
+            RelayPacket::Forward {
+                to: 2,
+                payload: vec![1, 2, 3],
+            },
+        );
+        assert_eq!(
+            recv(&second),
+            RelayPacket::Forwarded {
+                from: 1,
+                payload: vec![1, 2, 3],
+            }
+        );
+
+        // Packets never cross between sessions
+        send(
+            &other_session,
+            server,
+            RelayPacket::Forward {
+                to: 1,
+                payload: vec![4],
+            },
+        );
+        send(
+            &second,
+            server,
+            RelayPacket::Forward {
+                to: 1,
+                payload: vec![5],
+            },
+        );
+        assert_eq!(
+            recv(&first),
+            RelayPacket::Forwarded {
+                from: 2,
+                payload: vec![5],
+            }
+        );
+    }
+
+    #[test]
+    fn test_register_again_from_new_address() {
+        let server = start_server();
+        let first = client();
+        let restarted = client();
+
+        register(&first, server, "code", 1);
+        recv(&first);
+
+        register(&restarted, server, "code", 1);
+        assert_eq!(
+            recv(&restarted),
+            RelayPacket::Members(vec![(1, restarted.local_addr().unwrap())])
+        );
+
+        // The old address is no longer a member
+        send(
+            &first,
+            server,
+            RelayPacket::Forward {
+                to: 1,
+                payload: vec![1],
+            },
+        );
+        assert_eq!(recv(&first), RelayPacket::Error("Not registered".into()));
+    }
+}
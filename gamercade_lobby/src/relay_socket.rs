@@ -0,0 +1,353 @@
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::relay::{MemberId, RelayPacket, MAX_RELAY_PACKET_SIZE, RELAY_PROTOCOL_VERSION};
+
+/// How often the console registers with the relay. Also keeps the NAT mapping alive.
+const REGISTER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often each member is punched, until it replies.
+const PUNCH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long to keep punching a member after finding it, before settling for the relay.
+const PUNCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where to find the relay server, and the session to join on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayConfig {
+    pub server: SocketAddr,
+
+    /// Shared by every console in the session
+    pub code: String,
+    pub member: MemberId,
+
+    /// Always uses the relay, without trying to connect directly first.
+    /// Useful for testing the relay with consoles on a single machine.
+    pub relay_only: bool,
+}
+
+/// The placeholder address a member is known by, since its real address may change
+/// from the relay to a direct path. These are never sent to directly.
+pub fn member_addr(member: MemberId) -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::UNSPECIFIED, member))
+}
+
+/// The member a placeholder address refers to.
+pub fn addr_member(addr: &SocketAddr) -> Option<MemberId> {
+    addr.ip().is_unspecified().then_some(addr.port())
+}
+
+struct KnownMember {
+    /// The member's public address, as seen by the relay
+    addr: SocketAddr,
+    found: Instant,
+
+    /// Set once a punch has made it through, after which packets skip the relay
+    direct: bool,
+}
+
+/// A non-blocking UDP socket which reaches the other members of a session through a relay
+/// server. Members are also punched directly, using the addresses the relay saw them at,
+/// and any member which replies is sent packets directly from then on. Members are
+/// addressed by their placeholder address, from `member_addr`.
+pub struct RelaySocket {
+    socket: UdpSocket,
+    config: RelayConfig,
+    members: HashMap<MemberId, KnownMember>,
+    buffer: Box<[u8]>,
+    last_registered: Option<Instant>,
+    last_punched: Option<Instant>,
+}
+
+impl RelaySocket {
+    pub fn bind(port: u16, config: RelayConfig) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))?;
+        socket.set_nonblocking(true)?;
+
+        let mut relay_socket = Self {
+            socket,
+            config,
+            members: HashMap::new(),
+            buffer: vec![0; MAX_RELAY_PACKET_SIZE].into_boxed_slice(),
+            last_registered: None,
+            last_punched: None,
+        };
+
+        // Registers straight away, so the relay knows us before any packets are forwarded
+        relay_socket.maintain();
+        Ok(relay_socket)
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// True once packets to the member skip the relay.
+    pub fn is_direct(&self, member: MemberId) -> bool {
+        matches!(self.members.get(&member), Some(known) if known.direct)
+    }
+
+    pub fn send_to(&self, payload: &[u8], addr: &SocketAddr) -> io::Result<usize> {
+        let member = addr_member(addr).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} isn't a member of the relay session", addr),
+            )
+        })?;
+
+        let (packet, to) = match self.members.get(&member) {
+            Some(known) if known.direct => (
+                RelayPacket::Direct {
+                    from: self.config.member,
+                    payload: payload.to_vec(),
+                },
+                known.addr,
+            ),
+            _ => (
+                RelayPacket::Forward {
+                    to: member,
+                    payload: payload.to_vec(),
+                },
+                self.config.server,
+            ),
+        };
+
+        self.socket
+            .send_to(&packet.to_bytes(), to)
+            .map(|_| payload.len())
+    }
+
+    /// Receives the next payload from another member. Returns `WouldBlock` once
+    /// there is nothing left to receive, like a non-blocking `UdpSocket`.
+    pub fn recv_from(&mut self, buffer: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.maintain();
+
+        loop {
+            let (number_of_bytes, addr) = self.socket.recv_from(&mut self.buffer)?;
+            let packet = match RelayPacket::from_bytes(&self.buffer[..number_of_bytes]) {
+                Some(packet) => packet,
+                None => continue,
+            };
+
+            if let Some((from, payload)) = self.handle_packet(addr, packet) {
+                if payload.len() <= buffer.len() {
+                    buffer[..payload.len()].copy_from_slice(&payload);
+                    return Ok((payload.len(), member_addr(from)));
+                }
+            }
+        }
+    }
+
+    fn send_packet(&self, addr: SocketAddr, packet: &RelayPacket) {
+        // Lost packets are resent by `maintain`
+        let _ = self.socket.send_to(&packet.to_bytes(), addr);
+    }
+
+    /// Registers with the relay, and punches any members which haven't replied yet.
+    fn maintain(&mut self) {
+        let register_due = match self.last_registered {
+            Some(sent) => sent.elapsed() >= REGISTER_INTERVAL,
+            None => true,
+        };
+        if register_due {
+            self.send_packet(
+                self.config.server,
+                &RelayPacket::Register {
+                    version: RELAY_PROTOCOL_VERSION,
+                    code: self.config.code.clone(),
+                    member: self.config.member,
+                },
+            );
+            self.last_registered = Some(Instant::now());
+        }
+
+        let punch_due = match self.last_punched {
+            Some(sent) => sent.elapsed() >= PUNCH_INTERVAL,
+            None => true,
+        };
+        if self.config.relay_only || !punch_due {
+            return;
+        }
+
+        let punch = RelayPacket::Punch {
+            from: self.config.member,
+        };
+        self.members
+            .values()
+            .filter(|known| !known.direct && known.found.elapsed() < PUNCH_TIMEOUT)
+            .for_each(|known| self.send_packet(known.addr, &punch));
+        self.last_punched = Some(Instant::now());
+    }
+
+    /// Returns the payload, if the packet carried one.
+    fn handle_packet(
+        &mut self,
+        addr: SocketAddr,
+        packet: RelayPacket,
+    ) -> Option<(MemberId, Vec<u8>)> {
+        let from_server = addr == self.config.server;
+
+        match packet {
+            RelayPacket::Members(members) if from_server => {
+                members
+                    .into_iter()
+                    .filter(|(member, _)| *member != self.config.member)
+                    .for_each(|(member, addr)| {
+                        // Members which moved are punched again
+                        if self.members.get(&member).map(|known| known.addr) != Some(addr) {
+                            self.members.insert(
+                                member,
+                                KnownMember {
+                                    addr,
+                                    found: Instant::now(),
+                                    direct: false,
+                                },
+                            );
+                        }
+                    });
+                None
+            }
+            RelayPacket::Forwarded { from, payload } if from_server => Some((from, payload)),
+            RelayPacket::Error(error) if from_server => {
+                println!("Relay server error: {}", error);
+                None
+            }
+            RelayPacket::Punch { from } => {
+                if self.accept_direct(from, addr) {
+                    self.send_packet(
+                        addr,
+                        &RelayPacket::PunchReply {
+                            from: self.config.member,
+                        },
+                    );
+                }
+                None
+            }
+            RelayPacket::PunchReply { from } => {
+                self.accept_direct(from, addr);
+                None
+            }
+            RelayPacket::Direct { from, payload } => {
+                self.accept_direct(from, addr).then_some((from, payload))
+            }
+            _ => None,
+        }
+    }
+
+    /// Direct packets are only accepted from the address the relay saw the member at.
+    fn accept_direct(&mut self, member: MemberId, addr: SocketAddr) -> bool {
+        if self.config.relay_only {
+            return false;
+        }
+
+        match self.members.get_mut(&member) {
+            Some(known) if known.addr == addr => {
+                if !known.direct {
+                    println!("Connected directly to member {} at {}", member, addr);
+                    known.direct = true;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RelayServer;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn start_server() -> SocketAddr {
+        let server = RelayServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+        addr
+    }
+
+    fn bind(server: SocketAddr, member: MemberId, relay_only: bool) -> RelaySocket {
+        let config = RelayConfig {
+            server,
+            code: "test".to_string(),
+            member,
+            relay_only,
+        };
+        RelaySocket::bind(0, config).unwrap()
+    }
+
+    /// Polls both sockets, until the payload sent from the first arrives at the second.
+    fn exchange(from: &mut RelaySocket, to: &mut RelaySocket, payload: &[u8]) {
+        let to_addr = member_addr(to.config.member);
+        let from_addr = member_addr(from.config.member);
+        let started = Instant::now();
+        let mut buffer = [0; MAX_RELAY_PACKET_SIZE];
+
+        while started.elapsed() < TIMEOUT {
+            from.send_to(payload, &to_addr).unwrap();
+
+            for socket in [&mut *from, &mut *to] {
+                loop {
+                    match socket.recv_from(&mut buffer) {
+                        Ok((number_of_bytes, addr)) if addr == from_addr => {
+                            assert_eq!(&buffer[..number_of_bytes], payload);
+                            return;
+                        }
+                        Ok(_) => (),
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                        Err(e) => panic!("{}", e),
+                    }
+                }
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        panic!("Payload didn't arrive");
+    }
+
+    #[test]
+    fn test_relay_only() {
+        let server = start_server();
+        let mut first = bind(server, 1, true);
+        let mut second = bind(server, 2, true);
+
+        exchange(&mut first, &mut second, &[1, 2, 3]);
+        exchange(&mut second, &mut first, &[4, 5, 6]);
+
+        assert!(!first.is_direct(2));
+        assert!(!second.is_direct(1));
+    }
+
+    #[test]
+    fn test_hole_punching() {
+        let server = start_server();
+        let mut first = bind(server, 1, false);
+        let mut second = bind(server, 2, false);
+
+        let started = Instant::now();
+        while !(first.is_direct(2) && second.is_direct(1)) {
+            assert!(started.elapsed() < TIMEOUT, "Punching didn't succeed");
+            exchange(&mut first, &mut second, &[1]);
+            exchange(&mut second, &mut first, &[2]);
+        }
+
+        // Still works once packets skip the relay
+        exchange(&mut first, &mut second, &[7, 8, 9]);
+    }
+
+    #[test]
+    fn test_only_members_can_be_sent_to() {
+        let server = start_server();
+        let socket = bind(server, 1, false);
+
+        let result = socket.send_to(&[1], &"127.0.0.1:8000".parse().unwrap());
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+}
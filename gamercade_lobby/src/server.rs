@@ -0,0 +1,616 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    net::{IpAddr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::protocol::{
+    read_message, write_message, ClientMessage, GamePeer, GameStart, PlayerInfo, RoomId, RoomInfo,
+    ServerMessage, MAX_GAME_NAME_LEN, MAX_LISTED_ROOMS, MAX_PLAYER_NAME_LEN, MAX_ROOM_NAME_LEN,
+    MAX_ROOM_PLAYERS, PROTOCOL_VERSION,
+};
+
+/// Clients which don't keep up with their messages are dropped, rather than holding up the lobby.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How many messages can wait to be written to a client, before it's considered too slow.
+const OUTGOING_QUEUE_LEN: usize = 64;
+
+type ClientId = u64;
+
+struct Client {
+    /// Only used to disconnect the client. Messages go through `outgoing`.
+    stream: TcpStream,
+
+    /// Written to the client by its own thread, so the lobby is never locked while writing
+    outgoing: SyncSender<ServerMessage>,
+    ip: IpAddr,
+
+    /// The server's address, as seen by this client
+    server_ip: IpAddr,
+    room: Option<RoomId>,
+}
+
+struct Room {
+    name: String,
+    game: String,
+    max_players: usize,
+    members: Vec<(ClientId, PlayerInfo)>,
+}
+
+impl Room {
+    fn info(&self, id: RoomId) -> RoomInfo {
+        RoomInfo {
+            id,
+            name: self.name.clone(),
+            game: self.game.clone(),
+            max_players: self.max_players,
+            members: self
+                .members
+                .iter()
+                .map(|(_, player)| player.clone())
+                .collect(),
+        }
+    }
+
+    fn player_count(&self) -> usize {
+        self.members
+            .iter()
+            .map(|(_, player)| player.local_players)
+            .sum()
+    }
+}
+
+#[derive(Default)]
+struct Lobby {
+    next_client_id: ClientId,
+    next_room_id: RoomId,
+    clients: HashMap<ClientId, Client>,
+
+    /// Rooms waiting for their host to start the game. Started rooms are removed.
+    rooms: BTreeMap<RoomId, Room>,
+}
+
+impl Lobby {
+    fn send(&mut self, client_id: ClientId, message: &ServerMessage) {
+        if let Some(client) = self.clients.get(&client_id) {
+            // A full queue means the writer is stuck, and is disconnecting the client anyway
+            if client.outgoing.try_send(message.clone()).is_err() {
+                println!("Client {} isn't keeping up with its messages", client_id);
+                let _ = client.stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    fn send_error(&mut self, client_id: ClientId, error: impl Into<String>) {
+        self.send(client_id, &ServerMessage::Error(error.into()))
+    }
+
+    fn broadcast_room(&mut self, room_id: RoomId) {
+        if let Some(room) = self.rooms.get(&room_id) {
+            let message = ServerMessage::RoomUpdated(room.info(room_id));
+            let members = room.members.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+            members
+                .into_iter()
+                .for_each(|client_id| self.send(client_id, &message));
+        }
+    }
+
+    fn handle(&mut self, client_id: ClientId, message: ClientMessage) {
+        match message {
+            ClientMessage::Hello { .. } => self.send_error(client_id, "Already connected"),
+            ClientMessage::ListRooms => {
+                let rooms = self
+                    .rooms
+                    .iter()
+                    .take(MAX_LISTED_ROOMS)
+                    .map(|(id, room)| room.info(*id))
+                    .collect();
+                self.send(client_id, &ServerMessage::RoomList(rooms));
+            }
+            ClientMessage::CreateRoom {
+                name,
+                game,
+                max_players,
+                player,
+            } => self.create_room(client_id, name, game, max_players, player),
+            ClientMessage::JoinRoom { room, player } => self.join_room(client_id, room, player),
+            ClientMessage::LeaveRoom => {
+                self.leave_room(client_id);
+                self.send(client_id, &ServerMessage::RoomClosed);
+            }
+            ClientMessage::StartGame => self.start_game(client_id),
+        }
+    }
+
+    fn create_room(
+        &mut self,
+        client_id: ClientId,
+        name: String,
+        game: String,
+        max_players: usize,
+        player: PlayerInfo,
+    ) {
+        if !(2..=MAX_ROOM_PLAYERS).contains(&max_players) {
+            return self.send_error(
+                client_id,
+                format!("Rooms must hold between 2 and {} players", MAX_ROOM_PLAYERS),
+            );
+        } else if player.local_players == 0 || player.local_players > max_players {
+            return self.send_error(client_id, "Too many local players for the room");
+        } else if name.len() > MAX_ROOM_NAME_LEN {
+            return self.send_error(
+                client_id,
+                format!("Room names can be at most {} bytes long", MAX_ROOM_NAME_LEN),
+            );
+        } else if game.len() > MAX_GAME_NAME_LEN {
+            return self.send_error(
+                client_id,
+                format!("Game names can be at most {} bytes long", MAX_GAME_NAME_LEN),
+            );
+        } else if let Err(e) = check_player_name(&player) {
+            return self.send_error(client_id, e);
+        }
+
+        self.leave_room(client_id);
+
+        let room_id = self.next_room_id;
+        self.next_room_id += 1;
+
+        println!("Client {} created room {}: {}", client_id, room_id, name);
+        self.rooms.insert(
+            room_id,
+            Room {
+                name,
+                game,
+                max_players,
+                members: vec![(client_id, player)],
+            },
+        );
+        self.clients.get_mut(&client_id).unwrap().room = Some(room_id);
+        self.broadcast_room(room_id);
+    }
+
+    fn join_room(&mut self, client_id: ClientId, room_id: RoomId, player: PlayerInfo) {
+        let room = match self.rooms.get(&room_id) {
+            Some(room) => room,
+            None => return self.send_error(client_id, "Room doesn't exist"),
+        };
+
+        if room.members.iter().any(|(id, _)| *id == client_id) {
+            return self.send_error(client_id, "Already in the room");
+        } else if player.local_players == 0
+            || room.player_count() + player.local_players > room.max_players
+        {
+            return self.send_error(client_id, "Not enough space in the room");
+        } else if let Err(e) = check_player_name(&player) {
+            return self.send_error(client_id, e);
+        }
+
+        self.leave_room(client_id);
+
+        println!("Client {} joined room {}", client_id, room_id);
+        self.rooms
+            .get_mut(&room_id)
+            .unwrap()
+            .members
+            .push((client_id, player));
+        self.clients.get_mut(&client_id).unwrap().room = Some(room_id);
+        self.broadcast_room(room_id);
+    }
+
+    /// Removes the client from its room. If the host leaves, the room is closed.
+    fn leave_room(&mut self, client_id: ClientId) {
+        let room_id = match self.clients.get_mut(&client_id).and_then(|c| c.room.take()) {
+            Some(room_id) => room_id,
+            None => return,
+        };
+        let room = self.rooms.get_mut(&room_id).unwrap();
+
+        let was_host = room.members.first().map(|(id, _)| *id) == Some(client_id);
+        room.members.retain(|(id, _)| *id != client_id);
+
+        if was_host {
+            println!("Room {} closed", room_id);
+            let room = self.rooms.remove(&room_id).unwrap();
+            room.members.into_iter().for_each(|(member_id, _)| {
+                if let Some(member) = self.clients.get_mut(&member_id) {
+                    member.room = None;
+                }
+                self.send(member_id, &ServerMessage::RoomClosed);
+            });
+        } else {
+            self.broadcast_room(room_id);
+        }
+    }
+
+    fn start_game(&mut self, client_id: ClientId) {
+        let room_id = match self.clients.get(&client_id).and_then(|client| client.room) {
+            Some(room_id) => room_id,
+            None => return self.send_error(client_id, "Not in a room"),
+        };
+        let room = &self.rooms[&room_id];
+
+        if room.members[0].0 != client_id {
+            return self.send_error(client_id, "Only the host can start the game");
+        } else if room.members.len() < 2 {
+            return self.send_error(client_id, "Waiting for another player to join");
+        }
+
+        let room = self.rooms.remove(&room_id).unwrap();
+        let seed = fastrand::u64(..);
+        println!(
+            "Room {} started with {} members",
+            room_id,
+            room.members.len()
+        );
+
+        for (local_index, (receiver_id, _)) in room.members.iter().enumerate() {
+            let receiver = &self.clients[receiver_id];
+            let peers = room
+                .members
+                .iter()
+                .map(|(member_id, player)| {
+                    let member = &self.clients[member_id];
+
+                    // Members on the same machine as the server connect through
+                    // loopback, which the other members can't reach
+                    let ip = if member.ip.is_loopback() && !receiver.ip.is_loopback() {
+                        receiver.server_ip
+                    } else {
+                        member.ip
+                    };
+
+                    GamePeer {
+                        name: player.name.clone(),
+                        addr: SocketAddr::new(ip, player.game_port),
+                        local_players: player.local_players,
+                    }
+                })
+                .collect();
+
+            let message = ServerMessage::GameStarted(GameStart {
+                seed,
+                peers,
+                local_index,
+            });
+            self.send(*receiver_id, &message);
+        }
+
+        room.members.iter().for_each(|(member_id, _)| {
+            if let Some(member) = self.clients.get_mut(member_id) {
+                member.room = None;
+            }
+        });
+    }
+}
+
+fn check_player_name(player: &PlayerInfo) -> Result<(), String> {
+    if player.name.len() > MAX_PLAYER_NAME_LEN {
+        Err(format!(
+            "Player names can be at most {} bytes long",
+            MAX_PLAYER_NAME_LEN
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Writes the queued messages to the client, until it's removed from the lobby.
+fn write_messages(client_id: ClientId, mut stream: TcpStream, outgoing: Receiver<ServerMessage>) {
+    for message in outgoing {
+        if let Err(e) = write_message(&mut stream, &message) {
+            println!("Unable to send to client {}: {}", client_id, e);
+
+            // Also ends the client's reading thread, which removes it from the lobby
+            let _ = stream.shutdown(Shutdown::Both);
+            break;
+        }
+    }
+}
+
+/// A lobby where hosts create rooms, which other consoles can browse and join.
+/// Once the host starts the game, each member is sent the addresses of the others,
+/// their player order and a shared seed.
+pub struct LobbyServer {
+    listener: TcpListener,
+    lobby: Arc<Mutex<Lobby>>,
+}
+
+impl LobbyServer {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            lobby: Arc::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts clients until the listener fails. Each client is handled on its own thread.
+    pub fn run(self) -> io::Result<()> {
+        loop {
+            let (stream, addr) = self.listener.accept()?;
+            let lobby = self.lobby.clone();
+
+            std::thread::spawn(move || {
+                if let Err(e) = handle_client(stream, addr, lobby) {
+                    println!("Client {} disconnected: {}", addr, e);
+                }
+            });
+        }
+    }
+}
+
+fn handle_client(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    lobby: Arc<Mutex<Lobby>>,
+) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    match read_message(&mut stream)? {
+        ClientMessage::Hello { version } if version == PROTOCOL_VERSION => {
+            write_message(&mut stream, &ServerMessage::Welcome)?
+        }
+        ClientMessage::Hello { version } => {
+            let error = format!(
+                "Lobby server uses protocol version {}, but the client uses version {}",
+                PROTOCOL_VERSION, version
+            );
+            return write_message(&mut stream, &ServerMessage::Error(error));
+        }
+        _ => {
+            let error = "Expected Hello".to_string();
+            return write_message(&mut stream, &ServerMessage::Error(error));
+        }
+    }
+
+    let (outgoing, receiver) = sync_channel(OUTGOING_QUEUE_LEN);
+    let writer = stream.try_clone()?;
+    let client = Client {
+        stream: stream.try_clone()?,
+        outgoing,
+        ip: addr.ip(),
+        server_ip: stream.local_addr()?.ip(),
+        room: None,
+    };
+
+    let client_id = {
+        let mut lobby = lobby.lock().unwrap();
+        let client_id = lobby.next_client_id;
+        lobby.next_client_id += 1;
+        lobby.clients.insert(client_id, client);
+        client_id
+    };
+    std::thread::spawn(move || write_messages(client_id, writer, receiver));
+    println!("Client {} connected from {}", client_id, addr);
+
+    let result = loop {
+        match read_message(&mut stream) {
+            Ok(message) => lobby.lock().unwrap().handle(client_id, message),
+            Err(e) => break e,
+        }
+    };
+
+    let mut lobby = lobby.lock().unwrap();
+    lobby.leave_room(client_id);
+    lobby.clients.remove(&client_id);
+
+    // A closed connection is the usual way to leave
+    if result.kind() == io::ErrorKind::UnexpectedEof {
+        println!("Client {} disconnected", client_id);
+        Ok(())
+    } else {
+        Err(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{ClientMessage, LobbyClient, PlayerInfo, ServerMessage};
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn player(name: &str, game_port: u16, local_players: usize) -> PlayerInfo {
+        PlayerInfo {
+            name: name.to_string(),
+            game_port,
+            local_players,
+        }
+    }
+
+    fn connect(addr: SocketAddr) -> LobbyClient {
+        let mut client = LobbyClient::connect(addr).unwrap();
+        assert_eq!(client.recv_timeout(TIMEOUT), Some(ServerMessage::Welcome));
+        client
+    }
+
+    #[test]
+    fn test_create_join_and_start() {
+        let server = LobbyServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+
+        let mut host = connect(addr);
+        host.send(&ClientMessage::CreateRoom {
+            name: "Room".to_string(),
+            game: "game".to_string(),
+            max_players: 4,
+            player: player("host", 8001, 2),
+        })
+        .unwrap();
+        let room = match host.recv_timeout(TIMEOUT) {
+            Some(ServerMessage::RoomUpdated(room)) => room,
+            other => panic!("Expected RoomUpdated, got {:?}", other),
+        };
+
+        let mut guest = connect(addr);
+        guest.send(&ClientMessage::ListRooms).unwrap();
+        assert_eq!(
+            guest.recv_timeout(TIMEOUT),
+            Some(ServerMessage::RoomList(vec![room.clone()]))
+        );
+
+        // The guest can't start the game, and can't fit three more players
+        guest
+            .send(&ClientMessage::JoinRoom {
+                room: room.id,
+                player: player("guest", 8002, 3),
+            })
+            .unwrap();
+        assert!(matches!(
+            guest.recv_timeout(TIMEOUT),
+            Some(ServerMessage::Error(_))
+        ));
+
+        guest
+            .send(&ClientMessage::JoinRoom {
+                room: room.id,
+                player: player("guest", 8002, 1),
+            })
+            .unwrap();
+        for client in [&mut host, &mut guest] {
+            match client.recv_timeout(TIMEOUT) {
+                Some(ServerMessage::RoomUpdated(room)) => assert_eq!(room.player_count(), 3),
+                other => panic!("Expected RoomUpdated, got {:?}", other),
+            }
+        }
+
+        guest.send(&ClientMessage::StartGame).unwrap();
+        assert!(matches!(
+            guest.recv_timeout(TIMEOUT),
+            Some(ServerMessage::Error(_))
+        ));
+
+        host.send(&ClientMessage::StartGame).unwrap();
+        let host_start = match host.recv_timeout(TIMEOUT) {
+            Some(ServerMessage::GameStarted(start)) => start,
+            other => panic!("Expected GameStarted, got {:?}", other),
+        };
+        let guest_start = match guest.recv_timeout(TIMEOUT) {
+            Some(ServerMessage::GameStarted(start)) => start,
+            other => panic!("Expected GameStarted, got {:?}", other),
+        };
+
+        assert_eq!(host_start.seed, guest_start.seed);
+        assert_eq!(host_start.peers, guest_start.peers);
+        assert_eq!(host_start.local_index, 0);
+        assert_eq!(guest_start.local_index, 1);
+        assert_eq!(
+            host_start.peers[1].addr,
+            SocketAddr::from(([127, 0, 0, 1], 8002))
+        );
+
+        // Started rooms are no longer listed
+        guest.send(&ClientMessage::ListRooms).unwrap();
+        assert_eq!(
+            guest.recv_timeout(TIMEOUT),
+            Some(ServerMessage::RoomList(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn test_names_are_limited() {
+        let server = LobbyServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+
+        let mut host = connect(addr);
+        let long_name = "a".repeat(MAX_ROOM_NAME_LEN + 1);
+        host.send(&ClientMessage::CreateRoom {
+            name: long_name.clone(),
+            game: "game".to_string(),
+            max_players: 2,
+            player: player("host", 8001, 1),
+        })
+        .unwrap();
+        assert!(matches!(
+            host.recv_timeout(TIMEOUT),
+            Some(ServerMessage::Error(_))
+        ));
+
+        host.send(&ClientMessage::CreateRoom {
+            name: "Room".to_string(),
+            game: "game".to_string(),
+            max_players: 2,
+            player: player(&long_name, 8001, 1),
+        })
+        .unwrap();
+        assert!(matches!(
+            host.recv_timeout(TIMEOUT),
+            Some(ServerMessage::Error(_))
+        ));
+
+        // Nothing was created
+        host.send(&ClientMessage::ListRooms).unwrap();
+        assert_eq!(
+            host.recv_timeout(TIMEOUT),
+            Some(ServerMessage::RoomList(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn test_largest_room_list_fits_in_a_message() {
+        let room = Room {
+            name: "a".repeat(MAX_ROOM_NAME_LEN),
+            game: "a".repeat(MAX_GAME_NAME_LEN),
+            max_players: MAX_ROOM_PLAYERS,
+            members: (0..MAX_ROOM_PLAYERS as ClientId)
+                .map(|id| (id, player(&"a".repeat(MAX_PLAYER_NAME_LEN), 8000, 1)))
+                .collect(),
+        };
+        let rooms = (0..MAX_LISTED_ROOMS as RoomId)
+            .map(|id| room.info(id))
+            .collect();
+
+        let size = bincode::serialized_size(&ServerMessage::RoomList(rooms)).unwrap();
+        assert!(size as usize <= crate::protocol::MAX_MESSAGE_SIZE);
+    }
+
+    #[test]
+    fn test_host_leaving_closes_room() {
+        let server = LobbyServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+
+        let mut host = connect(addr);
+        host.send(&ClientMessage::CreateRoom {
+            name: "Room".to_string(),
+            game: "game".to_string(),
+            max_players: 2,
+            player: player("host", 8001, 1),
+        })
+        .unwrap();
+        let room = match host.recv_timeout(TIMEOUT) {
+            Some(ServerMessage::RoomUpdated(room)) => room,
+            other => panic!("Expected RoomUpdated, got {:?}", other),
+        };
+
+        let mut guest = connect(addr);
+        guest
+            .send(&ClientMessage::JoinRoom {
+                room: room.id,
+                player: player("guest", 8002, 1),
+            })
+            .unwrap();
+        assert!(matches!(
+            guest.recv_timeout(TIMEOUT),
+            Some(ServerMessage::RoomUpdated(_))
+        ));
+
+        drop(host);
+        assert_eq!(guest.recv_timeout(TIMEOUT), Some(ServerMessage::RoomClosed));
+    }
+}
@@ -0,0 +1,81 @@
+use super::{i32_bool_to_option, i32_u32_to_option};
+use crate::{prelude::GraphicsParameters, raw};
+
+/// Returns true if the two rectangles overlap. Each rectangle is given by
+/// its top left point (x, y), width, and height.
+pub fn rects_overlap(
+    (x0, y0, width0, height0): (i32, i32, u32, u32),
+    (x1, y1, width1, height1): (i32, i32, u32, u32),
+) -> bool {
+    unsafe {
+        raw::rects_overlap(
+            x0,
+            y0,
+            width0 as i32,
+            height0 as i32,
+            x1,
+            y1,
+            width1 as i32,
+            height1 as i32,
+        ) != 0
+    }
+}
+
+/// Returns true if the two circles overlap. Each circle is given by
+/// its center point (x, y) and radius.
+pub fn circles_overlap(
+    (x0, y0, radius0): (i32, i32, u32),
+    (x1, y1, radius1): (i32, i32, u32),
+) -> bool {
+    unsafe { raw::circles_overlap(x0, y0, radius0 as i32, x1, y1, radius1 as i32) != 0 }
+}
+
+/// Returns true if any opaque pixels of the two sprites overlap. Each sprite is given
+/// by the same parameters used to draw it: graphics parameters, transparency mask and
+/// the top left point (x, y).
+/// Uses palette_index, sprite_sheet_index, sprite_index, flip_x and flip_y.
+/// Colors which are transparent in the palette, or hidden by the mask, never overlap.
+/// Returns None if either sprite is invalid.
+pub fn sprites_overlap(
+    (graphics_parameters0, transparency_mask0, x0, y0): (GraphicsParameters, u64, i32, i32),
+    (graphics_parameters1, transparency_mask1, x1, y1): (GraphicsParameters, u64, i32, i32),
+) -> Option<bool> {
+    let val = unsafe {
+        raw::sprites_overlap(
+            graphics_parameters0.0,
+            transparency_mask0 as i64,
+            x0,
+            y0,
+            graphics_parameters1.0,
+            transparency_mask1 as i64,
+            x1,
+            y1,
+        )
+    };
+    i32_bool_to_option(val)
+}
+
+/// Returns the color index of the sprite pixel drawn at the screen position (screen_x, screen_y),
+/// if the sprite was drawn with the passed in graphics parameters, transparency mask and
+/// top left point (x, y).
+/// Returns None if the sprite is invalid, or if the pixel is transparent or outside of the sprite.
+pub fn sprite_pixel_at(
+    graphics_parameters: GraphicsParameters,
+    transparency_mask: u64,
+    x: i32,
+    y: i32,
+    screen_x: i32,
+    screen_y: i32,
+) -> Option<u8> {
+    let val = unsafe {
+        raw::sprite_pixel_at(
+            graphics_parameters.0,
+            transparency_mask as i64,
+            x,
+            y,
+            screen_x,
+            screen_y,
+        )
+    };
+    i32_u32_to_option(val).map(|color_index| color_index as u8)
+}
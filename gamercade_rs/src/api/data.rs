@@ -82,3 +82,32 @@ pub fn sfx_length_frames(sfx_index: usize) -> Option<u32> {
     let val = unsafe { raw::sfx_length_frames(sfx_index as i32) };
     i32_u32_to_option(val)
 }
+
+/// Copies the color indices of the requested sprite into the output slice, row by row.
+/// The output should be sprite_width * sprite_height long to fit the entire sprite.
+/// Returns the number of pixels copied, or None if the sprite sheet or sprite index is invalid.
+pub fn read_sprite(sprite_sheet: usize, sprite_index: usize, out: &mut [u8]) -> Option<u32> {
+    let val = unsafe {
+        raw::read_sprite(
+            sprite_sheet as i32,
+            sprite_index as i32,
+            out.as_mut_ptr() as i32,
+            out.len() as i32,
+        )
+    };
+    i32_u32_to_option(val)
+}
+
+/// Copies the colors of the requested palette into the output slice. Each color is
+/// stored as RGBA bytes, ie `[r, g, b, a]`.
+/// Returns the number of colors copied, or None if the palette index is invalid.
+pub fn read_palette(palette_index: usize, out: &mut [[u8; 4]]) -> Option<u32> {
+    let val = unsafe {
+        raw::read_palette(
+            palette_index as i32,
+            out.as_mut_ptr() as i32,
+            out.len() as i32,
+        )
+    };
+    i32_u32_to_option(val)
+}
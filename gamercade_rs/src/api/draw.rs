@@ -1,3 +1,4 @@
+use super::i32_u32_to_option;
 use crate::{prelude::GraphicsParameters, raw};
 
 /// Clears the entire screen, setting the color to the passed in graphics parameter
@@ -68,3 +69,43 @@ pub fn sprite(graphics_parameters: GraphicsParameters, transparency_mask: u64, x
 pub fn write_pixel_buffer(start_index: usize, data: &[GraphicsParameters]) {
     unsafe { raw::write_pixel_buffer(start_index as i32, data.as_ptr() as i32, data.len() as i32) }
 }
+
+/// Enables indexed mode, which displays the indexed buffer instead of the regular
+/// frame buffer. Each byte of the indexed buffer is a color index, which is resolved
+/// through the passed in palette when the frame is presented. Calling this again with
+/// a different palette will recolor everything already drawn to the indexed buffer.
+/// If the palette index is invalid, nothing will change.
+pub fn enable_indexed_mode(palette_index: u8) {
+    unsafe { raw::enable_indexed_mode(palette_index as i32) }
+}
+
+/// Disables indexed mode, returning to displaying the regular frame buffer.
+pub fn disable_indexed_mode() {
+    unsafe { raw::disable_indexed_mode() }
+}
+
+/// Writes multiple color indices into the indexed buffer, starting from the passed index
+/// and will continue until the data slice has been consumed. The buffer is only displayed
+/// while indexed mode is enabled.
+pub fn write_indexed_buffer(start_index: usize, data: &[u8]) {
+    unsafe {
+        raw::write_indexed_buffer(start_index as i32, data.as_ptr() as i32, data.len() as i32)
+    }
+}
+
+/// Copies pixels from the frame buffer into the output slice, starting from the passed index.
+/// Each pixel is stored as RGBA bytes, ie `[r, g, b, a]`. In indexed mode, the indexed
+/// buffer is read instead, resolved through the active palette like it is on screen.
+/// Returns the number of pixels copied, or None if the start index is out of bounds.
+/// WARNING: The frame buffer is not part of the rollback state. Only call this from within
+/// `draw()`, otherwise networked sessions may desync.
+pub fn read_pixel_buffer(start_index: usize, out: &mut [[u8; 4]]) -> Option<u32> {
+    let val = unsafe {
+        raw::read_pixel_buffer(
+            start_index as i32,
+            out.as_mut_ptr() as i32,
+            out.len() as i32,
+        )
+    };
+    i32_u32_to_option(val)
+}
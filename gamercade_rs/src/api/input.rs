@@ -120,6 +120,33 @@ macro_rules! derive_input_api {
                         Some(val)
                     }
                 }
+
+                /// Returns the mouse's $maxis_name position last frame, in pixel coordinates.
+                /// Returns None is player_id is invalid.
+                pub fn [<mouse_ $maxis_name _previous_pos>](player_id: usize) -> Option<u32> {
+                    let val = unsafe { raw::[<mouse_ $maxis_name _previous_pos>](player_id as i32) };
+                    i32_u32_to_option(val)
+                }
+
+                /// Returns the mouse's $maxis_name delta movement, including any fractional
+                /// movement. Useful for precise controls like mouse look.
+                /// Returns None is player_id is invalid.
+                pub fn [<mouse_ $maxis_name _delta_precise>](player_id: usize) -> Option<f32> {
+                    let val = unsafe { raw::[<mouse_ $maxis_name _delta_precise>](player_id as i32) };
+                    f32_to_option(val)
+                }
+
+                /// Returns the number of steps the mouse wheel was scrolled along $maxis_name this frame.
+                /// Positive values are up and to the right.
+                /// Returns None is player_id is invalid.
+                pub fn [<mouse_wheel_ $maxis_name>](player_id: usize) -> Option<i32> {
+                    let val = unsafe { raw::[<mouse_wheel_ $maxis_name>](player_id as i32) };
+                    if val == i32::MIN {
+                        None
+                    } else {
+                        Some(val)
+                    }
+                }
             )*
 
             $(
@@ -176,6 +203,77 @@ pub fn lock_mouse(locked: bool) {
     unsafe { raw::lock_mouse(locked as i32) }
 }
 
+/// Returns true if the player's keyboard or gamepad is connected. This is
+/// the same for every player in a network session, so it's safe to use for
+/// things like pausing the game.
+/// Returns None if player_id is invalid.
+pub fn controller_connected(player_id: usize) -> Option<bool> {
+    let val = unsafe { raw::controller_connected(player_id as i32) };
+    i32_bool_to_option(val)
+}
+
+/// Returns true if the action was just pressed this frame. Action ids are the
+/// order the actions were declared in the editor, starting at zero.
+/// Returns None if player_id or action_id is invalid.
+pub fn action_pressed(player_id: usize, action_id: usize) -> Option<bool> {
+    let val = unsafe { raw::action_pressed(player_id as i32, action_id as i32) };
+    i32_bool_to_option(val)
+}
+
+/// Returns true if the action was just released this frame.
+/// Returns None if player_id or action_id is invalid.
+pub fn action_released(player_id: usize, action_id: usize) -> Option<bool> {
+    let val = unsafe { raw::action_released(player_id as i32, action_id as i32) };
+    i32_bool_to_option(val)
+}
+
+/// Returns true if any of the buttons bound to the action are held this frame.
+/// Returns None if player_id or action_id is invalid.
+pub fn action_held(player_id: usize, action_id: usize) -> Option<bool> {
+    let val = unsafe { raw::action_held(player_id as i32, action_id as i32) };
+    i32_bool_to_option(val)
+}
+
+/// The maximum number of touch points tracked for each player.
+pub const MAX_TOUCH_POINTS: usize = 4;
+
+/// Returns the number of touch points currently held down.
+/// Returns None if player_id is invalid.
+pub fn touch_count(player_id: usize) -> Option<u32> {
+    let val = unsafe { raw::touch_count(player_id as i32) };
+    i32_u32_to_option(val)
+}
+
+/// Returns true if the touch point started this frame.
+/// Returns None if player_id or index is invalid.
+pub fn touch_pressed(player_id: usize, index: usize) -> Option<bool> {
+    let val = unsafe { raw::touch_pressed(player_id as i32, index as i32) };
+    i32_bool_to_option(val)
+}
+
+/// Returns true if the touch point ended this frame.
+/// Returns None if player_id or index is invalid.
+pub fn touch_released(player_id: usize, index: usize) -> Option<bool> {
+    let val = unsafe { raw::touch_released(player_id as i32, index as i32) };
+    i32_bool_to_option(val)
+}
+
+/// Returns true if the touch point is currently held down.
+/// Touch points keep the same index for as long as they are held.
+/// Returns None if player_id or index is invalid.
+pub fn touch_held(player_id: usize, index: usize) -> Option<bool> {
+    let val = unsafe { raw::touch_held(player_id as i32, index as i32) };
+    i32_bool_to_option(val)
+}
+
+/// Returns the x and y position of the touch point, in pixels.
+/// Returns None if player_id or index is invalid.
+pub fn touch_position(player_id: usize, index: usize) -> Option<(u32, u32)> {
+    let x = unsafe { raw::touch_x_pos(player_id as i32, index as i32) };
+    let y = unsafe { raw::touch_y_pos(player_id as i32, index as i32) };
+    Some((i32_u32_to_option(x)?, i32_u32_to_option(y)?))
+}
+
 derive_input_api! {
     Buttons {
         a,
@@ -1,6 +1,9 @@
 /// Functions related to getting sound engine state, or playing and stopping music or sfx.
 pub mod audio;
 
+/// Functions for checking overlap between rectangles, circles, and sprites.
+pub mod collision;
+
 /// Functions and queries for gathering data about the game ROM itself.
 pub mod data;
 
@@ -36,9 +39,18 @@ pub mod multiplayer;
 /// Functions to handle random number generation.
 pub mod random;
 
+/// Functions for rumbling the controllers of local players.
+pub mod rumble;
+
 /// Functions for dealing with text and strings.
 pub mod text;
 
+/// Functions for reading text typed by the local players, such as for entering a name.
+///
+/// Typed text isn't part of the synchronized input state, so text input is only
+/// available in sessions without any remote players, and not in SyncTest sessions.
+pub mod text_input;
+
 pub(crate) fn i32_bool_to_option(val: i32) -> Option<bool> {
     match val {
         0 => Some(false),
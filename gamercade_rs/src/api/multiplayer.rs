@@ -23,3 +23,12 @@ pub fn is_remote_player(player_id: usize) -> Option<bool> {
     let val = unsafe { raw::is_remote_player(player_id as i32) };
     i32_bool_to_option(val)
 }
+
+/// Returns false once the requested player has disconnected from the session.
+/// Their inputs stay empty from then on, so games can show a notice or
+/// take over the player with AI. Every console sees the same frame.
+/// Returns None if the player_id is invalid.
+pub fn is_player_connected(player_id: usize) -> Option<bool> {
+    let val = unsafe { raw::is_player_connected(player_id as i32) };
+    i32_bool_to_option(val)
+}
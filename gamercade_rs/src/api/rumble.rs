@@ -0,0 +1,16 @@
+use crate::raw;
+
+/// Rumbles the player's controller for the given number of frames. Strong and weak
+/// are the strength of the low and high frequency motors, each in the range 0.0 to 1.0.
+///
+/// Calling this again replaces the current rumble. This does nothing for remote players,
+/// or players without a controller which supports rumble. It is safe to call
+/// from `update()`, as rumble is rolled back along with the rest of the game state.
+pub fn rumble(player_id: usize, strong: f32, weak: f32, duration_frames: u32) {
+    unsafe { raw::rumble(player_id as i32, strong, weak, duration_frames as i32) }
+}
+
+/// Stops any rumble on the player's controller.
+pub fn stop_rumble(player_id: usize) {
+    unsafe { raw::stop_rumble(player_id as i32) }
+}
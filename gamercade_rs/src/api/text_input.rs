@@ -0,0 +1,50 @@
+use crate::raw;
+
+/// The most bytes of UTF-8 text which can be typed in a single frame.
+/// Any characters typed past this limit are dropped.
+pub const MAX_TEXT_INPUT_BYTES: usize = 64;
+
+/// Starts collecting the text typed by the local players. Returns false if text
+/// input isn't available, such as in a session with remote players or a SyncTest session.
+///
+/// While text input is active, keys still trigger their bound buttons as usual.
+pub fn start_text_input() -> bool {
+    unsafe { raw::start_text_input() != 0 }
+}
+
+/// Stops collecting typed text.
+pub fn stop_text_input() {
+    unsafe { raw::stop_text_input() }
+}
+
+/// Returns true if text input is currently active.
+pub fn text_input_active() -> bool {
+    unsafe { raw::text_input_active() != 0 }
+}
+
+/// Returns the text typed during this frame. The text is at most
+/// [`MAX_TEXT_INPUT_BYTES`] long, and is empty if text input isn't active.
+pub fn text_input() -> String {
+    let mut out = vec![0; unsafe { raw::text_input_len() } as usize];
+    let len = read_text_input(&mut out);
+    out.truncate(len);
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// Copies the UTF-8 text typed during this frame into the output slice,
+/// without allocating. Returns the number of bytes copied. Characters are
+/// never split, so fewer bytes than the slice length may be copied.
+pub fn read_text_input(out: &mut [u8]) -> usize {
+    unsafe { raw::read_text_input(out.as_mut_ptr() as i32, out.len() as i32) as usize }
+}
+
+/// Returns the number of times backspace was pressed during this frame.
+/// Backspaces which removed text typed during this same frame aren't counted.
+pub fn text_input_backspace_count() -> u32 {
+    unsafe { raw::text_input_backspace_count() as u32 }
+}
+
+/// Returns true if enter was pressed during this frame.
+pub fn text_input_enter_pressed() -> bool {
+    unsafe { raw::text_input_enter_pressed() != 0 }
+}
@@ -22,11 +22,14 @@ pub mod api;
 /// Re-exports the entire Api for convenience, that can be glob imported via `use gamercade_rs::prelude::*`.
 pub mod prelude {
     pub use crate::api::audio::*;
+    pub use crate::api::collision::*;
     pub use crate::api::data::*;
     pub use crate::api::draw::*;
     pub use crate::api::graphics_parameters::*;
     pub use crate::api::input::*;
     pub use crate::api::multiplayer::*;
     pub use crate::api::random::*;
+    pub use crate::api::rumble::*;
     pub use crate::api::text::*;
+    pub use crate::api::text_input::*;
 }
@@ -25,6 +25,8 @@ extern "C" {
     pub fn bgm_length_frames(bgm_index: i32) -> i32;
     pub fn sfx_length_secs(sfx_index: i32) -> f32;
     pub fn sfx_length_frames(sfx_index: i32) -> i32;
+    pub fn read_sprite(sprite_sheet: i32, sprite_index: i32, out_ptr: i32, len: i32) -> i32;
+    pub fn read_palette(palette_index: i32, out_ptr: i32, len: i32) -> i32;
 }
 
 // Graphics Params
@@ -56,6 +58,10 @@ extern "C" {
     pub fn line(graphics_parameters: i32, x0: i32, y0: i32, x1: i32, y1: i32);
     pub fn sprite(graphics_parameters: i32, transparency_mask: i64, x: i32, y: i32);
     pub fn write_pixel_buffer(start_index: i32, parameters_ptr: i32, len: i32);
+    pub fn enable_indexed_mode(palette_index: i32);
+    pub fn disable_indexed_mode();
+    pub fn write_indexed_buffer(start_index: i32, data_ptr: i32, len: i32);
+    pub fn read_pixel_buffer(start_index: i32, out_ptr: i32, len: i32) -> i32;
 }
 
 // Text
@@ -143,20 +149,90 @@ extern "C" {
     pub fn mouse_y_pos(player_id: i32) -> i32;
     pub fn mouse_x_delta(player_id: i32) -> i32;
     pub fn mouse_y_delta(player_id: i32) -> i32;
+    pub fn mouse_x_previous_pos(player_id: i32) -> i32;
+    pub fn mouse_y_previous_pos(player_id: i32) -> i32;
+    pub fn mouse_x_delta_precise(player_id: i32) -> f32;
+    pub fn mouse_y_delta_precise(player_id: i32) -> f32;
 
     pub fn mouse_wheel_up(player_id: i32) -> i32;
     pub fn mouse_wheel_down(player_id: i32) -> i32;
     pub fn mouse_wheel_left(player_id: i32) -> i32;
     pub fn mouse_wheel_right(player_id: i32) -> i32;
+    pub fn mouse_wheel_x(player_id: i32) -> i32;
+    pub fn mouse_wheel_y(player_id: i32) -> i32;
     pub fn lock_mouse(locked: i32);
 
+    pub fn touch_count(player_id: i32) -> i32;
+    pub fn touch_pressed(player_id: i32, index: i32) -> i32;
+    pub fn touch_released(player_id: i32, index: i32) -> i32;
+    pub fn touch_held(player_id: i32, index: i32) -> i32;
+    pub fn touch_x_pos(player_id: i32, index: i32) -> i32;
+    pub fn touch_y_pos(player_id: i32, index: i32) -> i32;
+
+    pub fn controller_connected(player_id: i32) -> i32;
+
+    pub fn action_pressed(player_id: i32, action_id: i32) -> i32;
+    pub fn action_released(player_id: i32, action_id: i32) -> i32;
+    pub fn action_held(player_id: i32, action_id: i32) -> i32;
+
     pub fn raw_input_state(player_id: i32) -> i64;
     pub fn raw_mouse_state(player_id: i32) -> i64;
 }
 
+// Rumble
+extern "C" {
+    pub fn rumble(player_id: i32, strong: f32, weak: f32, duration_frames: i32);
+    pub fn stop_rumble(player_id: i32);
+}
+
+// Text Input
+extern "C" {
+    pub fn start_text_input() -> i32;
+    pub fn stop_text_input();
+    pub fn text_input_active() -> i32;
+    pub fn text_input_len() -> i32;
+    pub fn text_input_backspace_count() -> i32;
+    pub fn text_input_enter_pressed() -> i32;
+    pub fn read_text_input(out_ptr: i32, len: i32) -> i32;
+}
+
+// Collision
+extern "C" {
+    pub fn rects_overlap(
+        x0: i32,
+        y0: i32,
+        width0: i32,
+        height0: i32,
+        x1: i32,
+        y1: i32,
+        width1: i32,
+        height1: i32,
+    ) -> i32;
+    pub fn circles_overlap(x0: i32, y0: i32, radius0: i32, x1: i32, y1: i32, radius1: i32) -> i32;
+    pub fn sprites_overlap(
+        graphics_parameters0: i32,
+        transparency_mask0: i64,
+        x0: i32,
+        y0: i32,
+        graphics_parameters1: i32,
+        transparency_mask1: i64,
+        x1: i32,
+        y1: i32,
+    ) -> i32;
+    pub fn sprite_pixel_at(
+        graphics_parameters: i32,
+        transparency_mask: i64,
+        x: i32,
+        y: i32,
+        screen_x: i32,
+        screen_y: i32,
+    ) -> i32;
+}
+
 // Multiplayer
 extern "C" {
     pub fn num_players() -> i32;
     pub fn is_local_player(player_id: i32) -> i32;
     pub fn is_remote_player(player_id: i32) -> i32;
+    pub fn is_player_connected(player_id: i32) -> i32;
 }
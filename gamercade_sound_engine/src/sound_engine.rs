@@ -152,10 +152,14 @@ impl SoundEngineData {
     }
 }
 
+/// The sample rate used when there's no output device to match.
+const SILENT_OUTPUT_SAMPLE_RATE: usize = 48_000;
+
 pub struct SoundEngine {
-    _stream: Stream,
+    /// None when there's no audio output, in which case nothing is played.
+    _stream: Option<Stream>,
     sound_frames_per_render_frame: usize,
-    sound_thread_producer: Producer<SoundEngineChannelType>,
+    sound_thread_producer: Option<Producer<SoundEngineChannelType>>,
     output_sample_rate: usize,
 }
 
@@ -164,11 +168,25 @@ impl SoundEngine {
         self.output_sample_rate
     }
 
+    /// Plays through the default output device. Falls back to a silent
+    /// engine if there isn't one.
     pub fn new(fps: usize, rom: &Arc<SoundRomInstance>, message_buffer_size: usize) -> Self {
         initialize_globals();
-        let mut device = default_host().default_output_device().unwrap();
+        let mut device = match default_host().default_output_device() {
+            Some(device) => device,
+            None => {
+                println!("No audio output device found, sound is disabled.");
+                return Self::silent(fps);
+            }
+        };
 
-        let supported_config = device.default_output_config().unwrap();
+        let supported_config = match device.default_output_config() {
+            Ok(config) => config,
+            Err(e) => {
+                println!("Failed to configure audio output, sound is disabled: {}", e);
+                return Self::silent(fps);
+            }
+        };
         let output_sample_rate = supported_config.sample_rate().0 as usize;
 
         let (stream, producer) = SoundEngineRunner::initialize_stream(
@@ -183,8 +201,21 @@ impl SoundEngine {
         Self {
             sound_frames_per_render_frame: output_sample_rate / fps,
             output_sample_rate,
-            _stream: stream,
-            sound_thread_producer: producer,
+            _stream: Some(stream),
+            sound_thread_producer: Some(producer),
+        }
+    }
+
+    /// An engine without any audio output, which drops every message sent to it.
+    /// Used when running without a window, or without an output device.
+    pub fn silent(fps: usize) -> Self {
+        initialize_globals();
+
+        Self {
+            sound_frames_per_render_frame: SILENT_OUTPUT_SAMPLE_RATE / fps,
+            output_sample_rate: SILENT_OUTPUT_SAMPLE_RATE,
+            _stream: None,
+            sound_thread_producer: None,
         }
     }
 
@@ -195,15 +226,19 @@ impl SoundEngine {
     }
 
     pub fn sync_audio_thread(&mut self, data: &SoundEngineData) {
-        self.sound_thread_producer
-            .push(SoundEngineChannelType::SoundEngineData(Box::new(
-                data.clone(),
-            )))
-            .unwrap()
+        if let Some(producer) = &mut self.sound_thread_producer {
+            producer
+                .push(SoundEngineChannelType::SoundEngineData(Box::new(
+                    data.clone(),
+                )))
+                .unwrap()
+        }
     }
 
     pub fn send(&mut self, message: SoundEngineChannelType) {
-        self.sound_thread_producer.push(message).unwrap();
+        if let Some(producer) = &mut self.sound_thread_producer {
+            producer.push(message).unwrap();
+        }
     }
 }
 